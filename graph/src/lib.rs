@@ -79,6 +79,17 @@
 //! assert_eq!(dag.topo_cmp(&lion, &human), Less);
 //! ```
 //!
+//! ## Concurrency
+//!
+//! A `DAG` is `Sync` when its node data, edge data, and hasher are, so all
+//! `&self` queries are safe for concurrent use from multiple threads. Queries
+//! that search the graph, such as `contains_transitive_edge`,
+//! `reachable_count`, `reachable_from_set`, `is_reachable_from_set`, and
+//! `connected_component`, share one scratch space to avoid allocations. A
+//! query only reuses that scratch space when no other query is using it, and
+//! allocates its own otherwise. For read-heavy analyses across many threads,
+//! `contains_transitive_edge_threadsafe` never uses the shared scratch space.
+//!
 //! ## Sources
 //!
 //! The [paper by D. J. Pearce and P. H. J. Kelly] contains descriptions of
@@ -88,22 +99,29 @@
 //! [paper by D. J. Pearce and P. H. J. Kelly]: http://www.doc.ic.ac.uk/~phjk/Publications/DynamicTopoSortAlg-JEA-07.pdf
 
 use std::{
-  borrow::Borrow,
+  borrow::{Borrow, Cow},
   cmp::{Ordering, Reverse},
   collections::BinaryHeap,
   fmt,
   iter::Iterator,
+  ops::RangeInclusive,
 };
-use std::cell::Cell;
-use std::collections::{HashMap, HashSet};
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::collections::hash_map::RandomState;
-use std::hash::BuildHasher;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::sync::{Mutex, PoisonError};
 
 use hashlink::LinkedHashSet;
-use slotmap::{DefaultKey, SlotMap};
+use slotmap::{DefaultKey, Key, KeyData, SlotMap};
 
 type TopoOrder = u32;
 
+/// Tag that annotates a node of a [DAG] with tooling information such as a color or category, without changing the node
+/// data type. See [DAG::add_tag].
+pub type Tag = Cow<'static, str>;
+
 
 /// Data structure for maintaining a directed-acyclic graph (DAG) with topological ordering, maintained in an
 /// incremental fashion.
@@ -122,7 +140,30 @@ pub struct DAG<N, E, H = RandomState> {
   last_topo_order: TopoOrder,
 
   #[cfg_attr(feature = "serde", serde(skip))]
-  stack_visited_scratch_space: Cell<StackVisitedScratchSpace<Node, H>>,
+  stack_visited_scratch_space: SharedScratchSpace<StackVisitedScratchSpace<Node, H>>,
+  #[cfg_attr(feature = "serde", serde(skip))]
+  edge_kind_index: Option<Box<dyn EdgeKindIndexObj<E> + Send + Sync>>,
+  #[cfg_attr(feature = "serde", serde(default))]
+  tags: Option<HashMap<Node, Vec<Tag>, H>>,
+  #[cfg(feature = "reorder_stats")]
+  #[cfg_attr(feature = "serde", serde(skip))]
+  reorder_stats: ReorderStats,
+}
+
+/// Statistics about the cost of maintaining the topological order when [adding edges](DAG::add_edge), measured as the
+/// number of nodes visited to reorder the affected region of the graph. Useful for diagnosing pathological edge
+/// insertion patterns, where edges are repeatedly added against the current topological order of large regions.
+#[cfg(feature = "reorder_stats")]
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ReorderStats {
+  /// Number of nodes visited for the last added edge. Zero if that edge did not change the topological order.
+  pub last_reorder_cost: usize,
+  /// Highest number of nodes visited for a single added edge.
+  pub max_reorder_cost: usize,
+  /// Total number of nodes visited for all added edges.
+  pub total_reorder_cost: u64,
+  /// Number of added edges that required visiting nodes to reorder them, including edges rejected due to cycles.
+  pub reorder_count: u64,
 }
 
 
@@ -135,6 +176,65 @@ pub struct DAG<N, E, H = RandomState> {
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Node(DefaultKey);
 
+impl Node {
+  /// Gets the numeric index of this node, for example for logging or for correlating nodes with external data
+  /// structures. Convert an index back into a node with [`DAG::node_from_index`].
+  ///
+  /// Indices are only valid within the [`DAG`] instance that created this node: the same index may refer to a
+  /// different node in another graph.
+  #[inline]
+  pub fn index(&self) -> u64 { self.0.data().as_ffi() }
+}
+
+
+/// Direction of an edge relative to a node, for [`DAG::neighbors`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Direction {
+  /// Edge from the neighbor to the node: the neighbor is a parent.
+  Incoming,
+  /// Edge from the node to the neighbor: the neighbor is a child.
+  Outgoing,
+}
+
+
+/// Edge data that has a kind, such as the variant of an enum, for
+/// [indexing incoming edges by kind](DAG::index_edge_kinds).
+pub trait EdgeKind {
+  /// Kind of edge data, typically a small enum without data.
+  type Kind: Copy + Eq + Hash;
+  /// Gets the kind of this edge data.
+  fn kind(&self) -> Self::Kind;
+}
+
+/// Internal object safe index of incoming edges by [kind](EdgeKind), type-erasing the kind type so that the index can
+/// be stored in [`DAG`]s whose edge data does not have a kind.
+trait EdgeKindIndexObj<E> {
+  fn insert(&mut self, src: Node, dst: Node, data: &E);
+  fn remove(&mut self, src: Node, dst: Node, data: &E);
+  fn as_any(&self) -> &dyn Any;
+}
+/// Index from destination nodes and edge kinds to the source nodes of the incoming edges of that kind.
+struct EdgeKindIndex<K>(HashMap<(Node, K), LinkedHashSet<Node>>);
+impl<E: EdgeKind> EdgeKindIndexObj<E> for EdgeKindIndex<E::Kind> where
+  E::Kind: 'static
+{
+  #[inline]
+  fn insert(&mut self, src: Node, dst: Node, data: &E) {
+    self.0.entry((dst, data.kind())).or_default().insert(src);
+  }
+  #[inline]
+  fn remove(&mut self, src: Node, dst: Node, data: &E) {
+    let key = (dst, data.kind());
+    if let Some(sources) = self.0.get_mut(&key) {
+      sources.remove(&src);
+      if sources.is_empty() {
+        self.0.remove(&key);
+      }
+    }
+  }
+  #[inline]
+  fn as_any(&self) -> &dyn Any { self }
+}
 
 /// Information about a node: its ordering, which nodes it points to, and which nodes point to it.
 #[derive(Debug)]
@@ -169,6 +269,9 @@ pub enum Error {
   NodeMissing,
   /// Cycles of nodes may not be formed in the graph.
   CycleDetected,
+  /// Given topological orders are not valid: they are not unique, or an edge does not go from a node with a lower
+  /// topological order to a node with a higher topological order.
+  InvalidTopoOrder,
 }
 
 impl fmt::Display for Error {
@@ -178,6 +281,7 @@ impl fmt::Display for Error {
         write!(f, "The given node was not found in the topological order")
       }
       Error::CycleDetected => write!(f, "Cycles of nodes may not be formed in the graph"),
+      Error::InvalidTopoOrder => write!(f, "The given topological orders are not valid"),
     }
   }
 }
@@ -192,7 +296,11 @@ impl<N, E, H: BuildHasher + Default> Default for DAG<N, E, H> {
       last_topo_order: 0,
       node_info: SlotMap::default(),
       edge_data: Default::default(),
-      stack_visited_scratch_space: Cell::default(),
+      stack_visited_scratch_space: SharedScratchSpace::default(),
+      edge_kind_index: None,
+      tags: None,
+      #[cfg(feature = "reorder_stats")]
+      reorder_stats: ReorderStats::default(),
     }
   }
 }
@@ -210,9 +318,149 @@ impl<N, E> DAG<N, E> {
   /// ```
   #[inline]
   pub fn new() -> Self { Self::default() }
+
+  /// Create a new DAG with pre-allocated capacity for at least `nodes` nodes and `edges` edges.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let dag = DAG::<(), ()>::with_capacity(16, 32);
+  ///
+  /// assert!(dag.is_empty());
+  /// ```
+  #[inline]
+  pub fn with_capacity(nodes: usize, edges: usize) -> Self {
+    let mut dag = Self::default();
+    dag.reserve(nodes, edges);
+    dag
+  }
 }
 
 impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
+  /// Create a new DAG from `nodes` and `edges` in one pass, returning the DAG and the created [`Node`]s in insertion
+  /// order. Edges are given as `(src, dst, data)` where `src` and `dst` are indices into `nodes`.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::NodeMissing`] if an edge index is out of range of `nodes`, or [`Error::CycleDetected`] if the
+  /// edges would form a cycle.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::{DAG, Error};
+  ///
+  /// let (dag, nodes) = DAG::<_, _>::from_nodes_and_edges(["cat", "mouse"], [(0, 1, ())]).unwrap();
+  /// assert_eq!(dag.get_node_data(&nodes[0]), Some(&"cat"));
+  /// assert!(dag.contains_edge(&nodes[0], &nodes[1]));
+  ///
+  /// let result = DAG::<_, _>::from_nodes_and_edges(["cat", "mouse"], [(0, 1, ()), (1, 0, ())]);
+  /// assert_eq!(result.err(), Some(Error::CycleDetected));
+  /// let result = DAG::<_, _>::from_nodes_and_edges(["cat", "mouse"], [(0, 2, ())]);
+  /// assert_eq!(result.err(), Some(Error::NodeMissing));
+  /// ```
+  pub fn from_nodes_and_edges(
+    nodes: impl IntoIterator<Item=N>,
+    edges: impl IntoIterator<Item=(usize, usize, E)>,
+  ) -> Result<(Self, Vec<Node>), Error> {
+    let mut dag = Self::default();
+    let nodes: Vec<Node> = nodes.into_iter().map(|data| dag.add_node(data)).collect();
+    for (src, dst, data) in edges {
+      let (Some(src), Some(dst)) = (nodes.get(src), nodes.get(dst)) else {
+        return Err(Error::NodeMissing);
+      };
+      dag.add_edge(src, dst, data)?;
+    }
+    Ok((dag, nodes))
+  }
+
+  /// Load a DAG from `nodes` with their topological orders and `edges` in one pass, returning the DAG and the created
+  /// [`Node`]s in insertion order. Edges are given as `(src, dst, data)` where `src` and `dst` are indices into
+  /// `nodes`.
+  ///
+  /// Unlike [`DAG::from_nodes_and_edges`], edges are inserted without cycle detection and reordering, trusting the
+  /// given topological orders instead, for example those of a known-good graph obtained with [`DAG::iter_unsorted`].
+  /// Topological orders are validated in constant time per node and edge: they must be unique, and every edge must go
+  /// from a node with a lower topological order to a node with a higher topological order. Such orders imply that the
+  /// edges do not form a cycle.
+  ///
+  /// If the given topological orders are so high that [adding nodes](DAG::add_node) could overflow them, the graph is
+  /// [compacted](DAG::compact), which preserves the relative order of the nodes.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::NodeMissing`] if an edge index is out of range of `nodes`, [`Error::CycleDetected`] if an edge
+  /// is a loop to self, or [`Error::InvalidTopoOrder`] if the topological orders are not valid.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::{DAG, Error};
+  ///
+  /// let (dag, nodes) = DAG::<_, _>::load([(1, "cat"), (2, "mouse")], [(0, 1, ())]).unwrap();
+  /// assert_eq!(dag.get_node_data(&nodes[0]), Some(&"cat"));
+  /// assert!(dag.contains_edge(&nodes[0], &nodes[1]));
+  ///
+  /// let result = DAG::<_, _>::load([(2, "cat"), (1, "mouse")], [(0, 1, ())]);
+  /// assert_eq!(result.err(), Some(Error::InvalidTopoOrder));
+  /// let result = DAG::<_, ()>::load([(1, "cat"), (1, "mouse")], []);
+  /// assert_eq!(result.err(), Some(Error::InvalidTopoOrder));
+  /// ```
+  pub fn load(
+    nodes: impl IntoIterator<Item=(TopoOrder, N)>,
+    edges: impl IntoIterator<Item=(usize, usize, E)>,
+  ) -> Result<(Self, Vec<Node>), Error> {
+    let mut dag = Self::default();
+    let mut topo_orders = HashSet::<_, H>::default();
+    let nodes: Vec<Node> = nodes.into_iter().map(|(topo_order, data)| {
+      if !topo_orders.insert(topo_order) {
+        return Err(Error::InvalidTopoOrder);
+      }
+      dag.last_topo_order = dag.last_topo_order.max(topo_order);
+      Ok(Node(dag.node_info.insert(NodeInfo::new(topo_order, data))))
+    }).collect::<Result<_, _>>()?;
+    for (src, dst, data) in edges {
+      let (Some(src), Some(dst)) = (nodes.get(src), nodes.get(dst)) else {
+        return Err(Error::NodeMissing);
+      };
+      if src == dst {
+        return Err(Error::CycleDetected);
+      }
+      if dag.node_info[src.0].topo_order >= dag.node_info[dst.0].topo_order {
+        return Err(Error::InvalidTopoOrder);
+      }
+      if dag.node_info[src.0].children.insert(*dst) {
+        dag.node_info[dst.0].parents.insert(*src);
+        dag.edge_data.insert((*src, *dst), data);
+      }
+    }
+    if dag.last_topo_order > TopoOrder::MAX / 2 {
+      dag.compact();
+    }
+    debug_assert!(dag.invariants_hold(), "BUG: loaded graph violates invariants");
+    Ok((dag, nodes))
+  }
+
+  /// Returns `true` if the invariants of this graph hold: topological orders are unique and at most the last
+  /// topological order, every edge goes from a node with a lower topological order to a node with a higher topological
+  /// order, and the children, parents, and edge data of all nodes agree with each other.
+  fn invariants_hold(&self) -> bool {
+    let mut topo_orders = HashSet::<_, H>::default();
+    let nodes_hold = self.node_info.iter().all(|(key, node_info)| {
+      let node = Node(key);
+      topo_orders.insert(node_info.topo_order)
+        && node_info.topo_order <= self.last_topo_order
+        && node_info.children.iter().all(|child| self.node_info.get(child.0).is_some_and(|child_info| {
+          child_info.parents.contains(&node)
+            && node_info.topo_order < child_info.topo_order
+            && self.edge_data.contains_key(&(node, *child))
+        }))
+        && node_info.parents.iter().all(|parent| self.node_info.get(parent.0)
+          .is_some_and(|parent_info| parent_info.children.contains(&node)))
+    });
+    nodes_hold && self.edge_data.keys().all(|(src, dst)| {
+      self.node_info.get(src.0).is_some_and(|src_info| src_info.children.contains(dst))
+    })
+  }
+
   /// Add a new node with `data` to the graph and return a unique [`Node`] which identifies it.
   ///
   /// Initially this node will not have any order relative to the nodes that are already in the graph. Only when
@@ -269,6 +517,26 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     self.node_info.contains_key(node.0)
   }
 
+  /// Gets the node for given `index` obtained from [`Node::index`], returning `None` if no node with that index is
+  /// present in this graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::<(), ()>::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// assert_eq!(dag.node_from_index(cat.index()), Some(cat));
+  ///
+  /// dag.remove_node(cat);
+  /// assert_eq!(dag.node_from_index(cat.index()), None);
+  /// ```
+  #[inline]
+  pub fn node_from_index(&self, index: u64) -> Option<Node> {
+    let node = Node(KeyData::from_ffi(index).into());
+    self.node_info.contains_key(node.0).then_some(node)
+  }
+
   /// Gets data for given `node`.
   #[inline]
   pub fn get_node_data(&self, node: impl Borrow<Node>) -> Option<&N> {
@@ -283,6 +551,54 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     self.node_info.get_mut(node.0).map(|d| &mut d.data)
   }
 
+  /// Adds `tag` to `node`, returning true if the tag was added, or false if `node` does not exist or already has `tag`.
+  ///
+  /// Tags are stored in a side-table that is only allocated when the first tag is added, and are removed when their
+  /// node is [removed](DAG::remove_node).
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::<(), ()>::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let dog = dag.add_node(());
+  ///
+  /// assert!(dag.add_tag(cat, "pet"));
+  /// assert!(dag.add_tag(dog, "pet"));
+  /// assert!(dag.add_tag(dog, "loyal"));
+  /// assert!(!dag.add_tag(dog, "loyal"));
+  ///
+  /// assert_eq!(dag.tags(dog), &["pet", "loyal"]);
+  /// assert_eq!(dag.nodes_with_tag("loyal").collect::<Vec<_>>(), vec![dog]);
+  /// ```
+  pub fn add_tag(&mut self, node: impl Borrow<Node>, tag: impl Into<Tag>) -> bool {
+    let node = *node.borrow();
+    if !self.contains_node(node) {
+      return false;
+    }
+    let tags = self.tags.get_or_insert_with(Default::default).entry(node).or_default();
+    let tag = tag.into();
+    if tags.contains(&tag) {
+      return false;
+    }
+    tags.push(tag);
+    true
+  }
+  /// Gets the tags of `node` in the order they were added, or an empty slice if `node` has no tags or does not exist.
+  #[inline]
+  pub fn tags(&self, node: impl Borrow<Node>) -> &[Tag] {
+    self.tags.as_ref().and_then(|tags| tags.get(node.borrow())).map_or(&[], |tags| tags.as_slice())
+  }
+  /// Gets all nodes that have `tag`, in no particular order.
+  #[inline]
+  pub fn nodes_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item=Node> + 'a {
+    self.tags.iter()
+      .flatten()
+      .filter(move |(_, tags)| tags.iter().any(|t| t == tag))
+      .map(|(node, _)| *node)
+  }
+
   /// Attempt to remove `node` from graph, returning true if the node was contained and removed.
   ///
   /// # Examples
@@ -311,14 +627,14 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
       if let Some(child_node) = self.node_info.get_mut(child.0) {
         child_node.parents.remove(&node.into());
       }
-      self.edge_data.remove(&(node, *child));
+      self.remove_edge_data(node, *child);
     }
     // Remove backward edges
     for parent in &node_info.parents {
       if let Some(parent_node) = self.node_info.get_mut(parent.0) {
         parent_node.children.remove(&node.into());
       }
-      self.edge_data.remove(&(*parent, node));
+      self.remove_edge_data(*parent, node);
     }
     // OPTO: inefficient compaction step
     for other_node in self.node_info.values_mut() {
@@ -328,10 +644,57 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     }
     // Decrement last topo order to account for shifted topo values
     self.last_topo_order -= 1;
+    // Remove tags
+    if let Some(tags) = &mut self.tags {
+      tags.remove(&node);
+    }
 
     true
   }
 
+  /// Attempt to contract `node`: add edges from each parent of `node` to each child of `node`, then remove `node`.
+  /// Returns true if the node was contained and contracted.
+  ///
+  /// The data of an edge from parent `p` to child `c` is `merge(data(p, node), data(node, c))`. If there already is an
+  /// edge from `p` to `c`, that edge and its data are kept. Because every added edge bypasses a path through `node`,
+  /// the topological order is maintained and no cycles are introduced.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let human = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let cat = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &dog, 1).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, 2).unwrap());
+  ///
+  /// assert!(dag.contract_node(dog, |a, b| a + b));
+  /// assert_eq!(dag.get_edge_data(&human, &cat), Some(&3));
+  ///
+  /// assert!(!dag.contract_node(dog, |a, b| a + b));
+  /// ```
+  pub fn contract_node(&mut self, node: Node, merge: impl Fn(&E, &E) -> E) -> bool {
+    let Some(node_info) = self.node_info.get(node.0) else {
+      return false;
+    };
+
+    let parents: Vec<Node> = node_info.parents.iter().copied().collect();
+    let children: Vec<Node> = node_info.children.iter().copied().collect();
+    for parent in &parents {
+      for child in &children {
+        if self.contains_edge(parent, child) {
+          continue;
+        }
+        let data = merge(&self.edge_data[&(*parent, node)], &self.edge_data[&(node, *child)]);
+        self.add_edge(parent, child, data).expect("BUG: bypassing edge introduced a cycle");
+      }
+    }
+    self.remove_node(node)
+  }
+
   /// Add a directed edge from `src` to `dst` with edge `data`.
   ///
   /// This edge indicates an ordering constraint on the two nodes, now `src` must always come before `dst` in the
@@ -401,9 +764,10 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     no_prev_edge = no_prev_edge && self.node_info[dst.0].parents.insert(*src);
     let lower_bound = self.node_info[dst.0].topo_order;
     if !no_prev_edge { // If edge already exists short circuit
+      self.record_reorder_cost(0);
       return Ok(false);
     }
-    self.edge_data.insert((*src, *dst), data);
+    self.insert_edge_data(*src, *dst, data);
 
     // If the affected region of the graph has non-zero size (i.e. the upper and
     // lower bound are equal) then perform an update to the topological ordering of
@@ -416,18 +780,96 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
         Err(err) => { // Need to remove parent + child + edge info that was previously added
           self.node_info[src.0].children.remove(dst);
           self.node_info[dst.0].parents.remove(src);
-          self.edge_data.remove(&(*src, *dst));
+          self.remove_edge_data(*src, *dst);
+          self.record_reorder_cost(visited.len());
           return Err(err);
         }
       };
       // Walk backwards from the pred
       let change_backward = self.dfs_backward(*src, &mut visited, lower_bound);
+      self.record_reorder_cost(visited.len());
       self.reorder_nodes(change_forward, change_backward);
+    } else {
+      self.record_reorder_cost(0);
     }
 
     Ok(true)
   }
 
+  /// Gets the number of nodes that were visited to maintain the topological order for the last
+  /// [added edge](DAG::add_edge), or zero if that edge did not change the topological order or no edge was added yet.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  /// assert_eq!(dag.last_reorder_cost(), 0);
+  /// ```
+  #[cfg(feature = "reorder_stats")]
+  #[inline]
+  pub fn last_reorder_cost(&self) -> usize { self.reorder_stats.last_reorder_cost }
+
+  /// Gets the [reorder statistics](ReorderStats), accumulated over all [added edges](DAG::add_edge) of this graph.
+  #[cfg(feature = "reorder_stats")]
+  #[inline]
+  pub fn reorder_stats(&self) -> ReorderStats { self.reorder_stats }
+
+  /// Returns true if adding an edge from `src` to `dst` would create a cycle, without modifying the graph. This agrees
+  /// with [`DAG::add_edge`]: it returns true exactly when `add_edge` would return [`Error::CycleDetected`]. An edge
+  /// that already exists does not create a cycle.
+  ///
+  /// # Errors
+  ///
+  /// Returns an `Err` if either of the given nodes is not found in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.would_create_cycle(&mouse, &human), Ok(true));
+  /// assert_eq!(dag.would_create_cycle(&human, &mouse), Ok(false));
+  /// assert_eq!(dag.would_create_cycle(&cat, &cat), Ok(true));
+  /// ```
+  pub fn would_create_cycle(&self, src: impl Borrow<Node>, dst: impl Borrow<Node>) -> Result<bool, Error> {
+    let src = src.borrow();
+    let dst = dst.borrow();
+
+    if !self.node_info.contains_key(src.0) || !self.node_info.contains_key(dst.0) {
+      return Err(Error::NodeMissing);
+    }
+    if src == dst { // Loops to self are cycles
+      return Ok(true);
+    }
+    if self.node_info[src.0].children.contains(dst) { // Edge already exists
+      return Ok(false);
+    }
+
+    // Same check as `add_edge`: only when `dst` is ordered before `src` can `src` be reachable from `dst`.
+    let upper_bound = self.node_info[src.0].topo_order;
+    let lower_bound = self.node_info[dst.0].topo_order;
+    if lower_bound < upper_bound {
+      let mut visited = HashSet::<_, H>::default(); // OPTO: reuse allocation.
+      if let Err(Error::CycleDetected) = self.dfs_forward(*dst, &mut visited, upper_bound) {
+        return Ok(true);
+      }
+    }
+
+    Ok(false)
+  }
+
   /// Returns true if the graph contains an edge from `src` to `dst`.
   ///
   /// Returns false if either node is not found, or if there is no dependency.
@@ -506,33 +948,86 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     // the overhead of the binary heap, and this task doesn't really need ordered
     // descendants.
     let mut scratch = self.stack_visited_scratch_space.take();
-    scratch.clear();
-    scratch.stack.push(*src);
-
-    // For each node key popped off the stack, check that we haven't seen it
-    // before, then check if its children contain the node we're searching for.
-    // If they don't, continue the search by extending the stack with the children.
-    while let Some(key) = scratch.stack.pop() {
-      if scratch.visited.contains(&key) {
-        continue;
-      } else {
-        scratch.visited.insert(key);
-      }
+    let contains = self.search_transitive_edge(*src, *dst, &mut scratch);
+    self.stack_visited_scratch_space.set(scratch);
+    contains
+  }
 
-      let children = &self.node_info.get(key.0).unwrap().children;
-      if children.contains(dst) {
-        self.stack_visited_scratch_space.set(scratch);
-        return true;
-      } else {
-        scratch.stack.extend(children.iter());
-        continue;
-      }
+  /// Returns true if the graph contains a path from `src` to `dst`, like [`DAG::contains_transitive_edge`], but
+  /// allocates its own scratch space instead of reusing the scratch space shared by queries on this graph.
+  ///
+  /// All `&self` queries are safe for concurrent use, but queries that search the graph, such as
+  /// [`DAG::contains_transitive_edge`] and [`DAG::reachable_count`], reuse the shared scratch space only when no
+  /// other query is using it, and otherwise allocate. This query never uses the shared scratch space, avoiding
+  /// contention on it in read-heavy analyses that query the graph from many threads.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// std::thread::scope(|scope| {
+  ///   scope.spawn(|| assert!(dag.contains_transitive_edge_threadsafe(&human, &mouse)));
+  ///   scope.spawn(|| assert!(!dag.contains_transitive_edge_threadsafe(&mouse, &human)));
+  /// });
+  /// ```
+  pub fn contains_transitive_edge_threadsafe(&self, src: impl Borrow<Node>, dst: impl Borrow<Node>) -> bool {
+    let src = src.borrow();
+    let dst = dst.borrow();
+    if !self.node_info.contains_key(src.0) || !self.node_info.contains_key(dst.0) || src.0 == dst.0 {
+      return false;
     }
+    self.search_transitive_edge(*src, *dst, &mut StackVisitedScratchSpace::default())
+  }
 
-    self.stack_visited_scratch_space.set(scratch);
+  /// Returns true if the graph contains a path from `src` to `dst` that passes through `via`.
+  ///
+  /// If `via` is equal to `src` or `dst`, any path from `src` to `dst` passes through `via`, so this is equivalent to
+  /// [`contains_transitive_edge(src, dst)`](Self::contains_transitive_edge).
+  ///
+  /// Returns false if any node is not found in the graph, if `src` is equal to `dst`, or there is no such path.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert!(dag.contains_path_through(&human, &cat, &mouse));
+  /// assert!(!dag.contains_path_through(&human, &dog, &mouse));
+  /// ```
+  pub fn contains_path_through(
+    &self,
+    src: impl Borrow<Node>,
+    via: impl Borrow<Node>,
+    dst: impl Borrow<Node>,
+  ) -> bool {
+    let src = src.borrow();
+    let via = via.borrow();
+    let dst = dst.borrow();
 
-    // If we exhaust the stack, then there is no transitive dependency.
-    false
+    if !self.node_info.contains_key(via.0) || src == dst {
+      return false;
+    }
+    if via == src || via == dst {
+      return self.contains_transitive_edge(src, dst);
+    }
+    self.contains_transitive_edge(src, via) && self.contains_transitive_edge(via, dst)
   }
 
 
@@ -552,6 +1047,19 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     self.edge_data.get_mut(&(*src, *dst))
   }
 
+  /// Replaces the data of the edge from `src` to `dst` with `data`, returning `Some(previous_data)`, or `None` if there
+  /// is no such edge, in which case `data` is dropped. Unlike mutating through [`DAG::get_edge_data_mut`], this keeps
+  /// the [edge kind index](DAG::index_edge_kinds) up-to-date when the kind of the edge changes.
+  pub fn replace_edge_data(&mut self, src: impl Borrow<Node>, dst: impl Borrow<Node>, data: E) -> Option<E> {
+    let src = *src.borrow();
+    let dst = *dst.borrow();
+    if !self.edge_data.contains_key(&(src, dst)) {
+      return None;
+    }
+    let previous = self.remove_edge_data(src, dst);
+    self.insert_edge_data(src, dst, data);
+    previous
+  }
 
   /// Gets the destination nodes and edge data for all outgoing edges of `src`.
   #[inline]
@@ -634,8 +1142,149 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
       .map(|node_info| &node_info.data)
   }
 
-
-  /// Attempt to remove the edge from `src` to `dst` from the graph, returning `Some(edge_data)` if the edge was
+  /// Gets the neighbors of `node`, ignoring edge direction: each parent tagged with [`Direction::Incoming`], followed
+  /// by each child tagged with [`Direction::Outgoing`]. Gets no neighbors if `node` is not present in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::{DAG, Direction};
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let neighbors = dag.neighbors(cat).collect::<Vec<_>>();
+  ///
+  /// assert_eq!(neighbors, vec![(Direction::Incoming, &human), (Direction::Outgoing, &mouse)]);
+  /// ```
+  #[inline]
+  pub fn neighbors(&self, node: impl Borrow<Node>) -> impl Iterator<Item=(Direction, &Node)> + '_ {
+    let node = *node.borrow();
+    self.node_info.get(node.0)
+      .into_iter()
+      .flat_map(|node_info| {
+        let parents = node_info.parents.iter().map(|n| (Direction::Incoming, n));
+        let children = node_info.children.iter().map(|n| (Direction::Outgoing, n));
+        parents.chain(children)
+      })
+  }
+
+  /// Index the incoming edges of all nodes by the [kind](EdgeKind) of their edge data, making
+  /// [`DAG::get_incoming_edges_of_kind`] take time proportional to the number of returned edges, instead of the number
+  /// of incoming edges. The index is built from all current edges, and is kept up-to-date when edges are added or
+  /// removed. Does nothing if edge kinds are already indexed.
+  ///
+  /// The index costs memory and makes adding and removing edges slightly slower. It is neither serialized nor kept by
+  /// [`DAG::map`] and [`DAG::map_edges`], so it must be enabled again on deserialized and mapped graphs. Data mutated
+  /// through [`DAG::get_edge_data_mut`] must not change kind, use [`DAG::replace_edge_data`] for changing the kind of
+  /// an edge instead.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::{DAG, EdgeKind};
+  ///
+  /// #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+  /// enum Access { Read, Write }
+  /// impl EdgeKind for Access {
+  ///   type Kind = Self;
+  ///   fn kind(&self) -> Self { *self }
+  /// }
+  ///
+  /// let mut dag = DAG::new();
+  /// let file = dag.add_node(());
+  /// let reader = dag.add_node(());
+  /// let writer = dag.add_node(());
+  /// dag.index_edge_kinds();
+  ///
+  /// assert!(dag.add_edge(&reader, &file, Access::Read).unwrap());
+  /// assert!(dag.add_edge(&writer, &file, Access::Write).unwrap());
+  ///
+  /// let writers: Vec<_> = dag.get_incoming_edges_of_kind(&file, Access::Write).map(|(n, _)| *n).collect();
+  /// assert_eq!(writers, vec![writer]);
+  /// ```
+  pub fn index_edge_kinds(&mut self) where
+    E: EdgeKind,
+    E::Kind: Send + Sync + 'static,
+  {
+    if self.edge_kind_index.is_some() {
+      return;
+    }
+    let mut index = EdgeKindIndex::<E::Kind>(HashMap::default());
+    // Insert in the order of parents, so that edges are returned in insertion order.
+    for (key, info) in self.node_info.iter() {
+      let dst = Node(key);
+      for src in info.parents.iter() {
+        EdgeKindIndexObj::<E>::insert(&mut index, *src, dst, &self.edge_data[&(*src, dst)]);
+      }
+    }
+    self.edge_kind_index = Some(Box::new(index));
+  }
+  /// Returns `true` if incoming edges are [indexed by kind](DAG::index_edge_kinds).
+  #[inline]
+  pub fn is_edge_kinds_indexed(&self) -> bool { self.edge_kind_index.is_some() }
+
+  /// Gets the source node and edge data for all incoming edges of `dst` whose edge data is of `kind`. Fast if edge
+  /// kinds are [indexed](DAG::index_edge_kinds), otherwise all incoming edges of `dst` are filtered.
+  ///
+  /// Edges are returned in insertion order.
+  pub fn get_incoming_edges_of_kind(
+    &self,
+    dst: impl Borrow<Node>,
+    kind: E::Kind,
+  ) -> impl Iterator<Item=(&Node, &E)> + '_ where
+    E: EdgeKind,
+    E::Kind: 'static,
+  {
+    let dst = *dst.borrow();
+    let index = self.edge_kind_index.as_ref().map(|index| index.as_any().downcast_ref::<EdgeKindIndex<E::Kind>>()
+      .expect("BUG: non-matching edge kind index type"));
+    let indexed = index.map(|index| index.0.get(&(dst, kind)).into_iter()
+      .flatten()
+      .map(move |src| (src, self.edge_data.get(&(*src, dst)).unwrap())));
+    let filtered = index.is_none().then(|| self.get_incoming_edges(dst).filter(move |(_, data)| data.kind() == kind));
+    indexed.into_iter().flatten().chain(filtered.into_iter().flatten())
+  }
+
+
+  /// Gets all edges with a source node in `from` and a destination node in `to`, as `(src, dst, edge_data)` triples
+  /// sorted by the topological order of `src` and then `dst`. When `from` and `to` partition the graph, these are the
+  /// edges of the cut between them. Nodes in `from` that are not in the graph are ignored.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use pie_graph::DAG;
+  /// use std::collections::HashSet;
+  ///
+  /// let mut dag = DAG::new();
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// dag.add_edge(&human, &cat, "pets").unwrap();
+  /// dag.add_edge(&cat, &mouse, "chases").unwrap();
+  ///
+  /// let from = HashSet::from([human, cat]);
+  /// let to = HashSet::from([mouse]);
+  /// assert_eq!(dag.edges_between(&from, &to), vec![(cat, mouse, &"chases")]);
+  /// ```
+  pub fn edges_between<S: BuildHasher>(&self, from: &HashSet<Node, S>, to: &HashSet<Node, S>) -> Vec<(Node, Node, &E)> {
+    let mut edges: Vec<_> = from.iter()
+      .flat_map(|src| self.get_outgoing_edges(src).map(move |(dst, data)| (*src, *dst, data)))
+      .filter(|(_, dst, _)| to.contains(dst))
+      .collect();
+    edges.sort_by(|(src_a, dst_a, _), (src_b, dst_b, _)| {
+      self.topo_cmp(src_a, src_b).then_with(|| self.topo_cmp(dst_a, dst_b))
+    });
+    edges
+  }
+
+
+  /// Attempt to remove the edge from `src` to `dst` from the graph, returning `Some(edge_data)` if the edge was
   /// removed, or `None` otherwise.
   ///
   /// Returns `false` is either node is not found in the graph.
@@ -674,7 +1323,7 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     }
     src_children.remove(&dst);
     self.node_info[dst.0].parents.remove(&src);
-    self.edge_data.remove(&(*src, *dst))
+    self.remove_edge_data(*src, *dst)
   }
 
   /// Attempt to remove all outgoing edges of `src` from the graph, returning `Some(edge_data)` if any edges were
@@ -695,7 +1344,7 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
       if let Some(succ) = self.node_info.get_mut(succ_id.0) {
         succ.parents.remove(&pred_id);
       }
-      if let Some(data) = self.edge_data.remove(&(*pred_id, succ_id)) {
+      if let Some(data) = self.remove_edge_data(*pred_id, succ_id) {
         edge_data.push((succ_id, data));
       }
     }
@@ -742,6 +1391,47 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     self.len() == 0
   }
 
+  /// Return the number of edges within the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::<(), ()>::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.edge_count(), 2);
+  /// ```
+  #[inline]
+  pub fn edge_count(&self) -> usize {
+    self.edge_data.len()
+  }
+
+  /// Reserve capacity for at least `nodes` more nodes and `edges` more edges to be inserted into the graph, avoiding
+  /// reallocations while inserting them.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::<(), ()>::new();
+  ///
+  /// dag.reserve(2, 1);
+  /// let cat = dag.add_node(());
+  /// let human = dag.add_node(());
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// ```
+  #[inline]
+  pub fn reserve(&mut self, nodes: usize, edges: usize) {
+    self.node_info.reserve(nodes);
+    self.edge_data.reserve(edges);
+    self.stack_visited_scratch_space.get_mut().visited.reserve(nodes);
+  }
+
   /// Return an iterator over all the nodes of the graph in an unsorted order.
   ///
   /// # Examples
@@ -773,6 +1463,66 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
       .map(|(key, node)| (node.topo_order, Node(key)))
   }
 
+  /// Return an iterator over all the nodes of the graph and their data, in a topologically sorted order: every node is
+  /// yielded before all nodes it has an edge to.
+  ///
+  /// This allocates and sorts an index of all nodes when called. If the order does not matter, use
+  /// [`DAG::iter_unsorted`] instead.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node("cat");
+  /// let mouse = dag.add_node("mouse");
+  /// let human = dag.add_node("human");
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let nodes = dag.iter_nodes_topo().collect::<Vec<_>>();
+  /// assert_eq!(nodes, vec![(human, &"human"), (cat, &"cat"), (mouse, &"mouse")]);
+  /// ```
+  pub fn iter_nodes_topo(&self) -> impl Iterator<Item=(Node, &N)> + '_ {
+    let mut nodes: Vec<_> = self.node_info
+      .iter()
+      .map(|(key, node_info)| (node_info.topo_order, Node(key), &node_info.data))
+      .collect();
+    nodes.sort_unstable_by_key(|(topo_order, _, _)| *topo_order);
+    nodes.into_iter().map(|(_, node, data)| (node, data))
+  }
+
+  /// Return the nodes of the graph whose topological order falls in `range`, sorted by ascending topological order.
+  /// Useful for paginating or incrementally rendering a topologically sorted listing of a large graph.
+  ///
+  /// Topological orders are not necessarily contiguous, so `range` may contain fewer nodes than orders.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.nodes_in_topo_range(1..=2), vec![human, cat]);
+  /// assert_eq!(dag.nodes_in_topo_range(3..=5), vec![mouse]);
+  /// ```
+  pub fn nodes_in_topo_range(&self, range: RangeInclusive<TopoOrder>) -> Vec<Node> {
+    let mut nodes: Vec<_> = self.node_info
+      .iter()
+      .filter(|(_, node_info)| range.contains(&node_info.topo_order))
+      .map(|(key, node_info)| (node_info.topo_order, Node(key)))
+      .collect();
+    nodes.sort_unstable_by_key(|(topo_order, _)| *topo_order);
+    nodes.into_iter().map(|(_, node)| node).collect()
+  }
+
   /// Return an iterator over the descendants of a node in the graph, in an unsorted order.
   ///
   /// Accessing the nodes in an unsorted order allows for faster access using a iterative DFS search. This is opposed to
@@ -829,10 +1579,10 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
     })
   }
 
-  /// Return an iterator over descendants of a node in the graph, in a topologically sorted order.
+  /// Return the number of distinct descendants of a node in the graph, without collecting them.
   ///
-  /// Accessing the nodes in a sorted order requires the use of a BinaryHeap, so some performance penalty is paid there.
-  /// If all is required is access to the descendants of a node, use [`DAG::descendants_unsorted`].
+  /// Uses the same iterative DFS search as [`DAG::descendants_unsorted`], but reuses the internal scratch space instead
+  /// of allocating a new stack and visited set.
   ///
   /// # Errors
   ///
@@ -853,70 +1603,912 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
   /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
   /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
   ///
-  /// let ordered_nodes = dag.descendants(human).unwrap().collect::<Vec<_>>();
-  ///
-  /// assert_eq!(ordered_nodes, vec![dog, cat, mouse]);
+  /// assert_eq!(dag.reachable_count(human), Ok(3));
+  /// assert_eq!(dag.reachable_count(mouse), Ok(0));
   /// ```
-  pub fn descendants(&self, node: impl Borrow<Node>) -> Result<Descendants<N, E, H>, Error> {
+  pub fn reachable_count(&self, node: impl Borrow<Node>) -> Result<usize, Error> {
     let node = node.borrow();
     if !self.node_info.contains_key(node.0) {
       return Err(Error::NodeMissing);
     }
 
-    let mut queue = BinaryHeap::new(); // OPTO: reuse allocation
-    // Add all children of selected node
-    queue.extend(
-      self.node_info[node.0]
-        .children
-        .iter()
-        .cloned()
-        .map(|child_node| {
-          let child_order = self.get_node(child_node).topo_order;
-          (Reverse(child_order), child_node)
-        }),
-    );
-    let visited = HashSet::<_, H>::default(); // OPTO: reuse allocation
+    let mut scratch = self.stack_visited_scratch_space.take();
+    scratch.clear();
+    scratch.stack.extend(self.node_info[node.0].children.iter());
 
-    Ok(Descendants {
-      dag: self,
-      queue,
-      visited,
-    })
+    let mut count = 0;
+    while let Some(key) = scratch.stack.pop() {
+      if !scratch.visited.insert(key) {
+        continue;
+      }
+      count += 1;
+      scratch.stack.extend(self.node_info[key.0].children.iter());
+    }
+
+    self.stack_visited_scratch_space.set(scratch);
+
+    Ok(count)
   }
 
-  /// Compare two nodes present in the graph, topographically.
+  /// Return all nodes reachable from any node in `sources`: the union of the descendants of `sources`. Nodes in
+  /// `sources` are only included if they are reachable from another node in `sources`. Nodes in `sources` that are not
+  /// present in the graph are ignored.
+  ///
+  /// Performs a single multi-source iterative DFS search, reusing the internal scratch space for its stack, which is
+  /// cheaper than searching from each source separately.
   ///
   /// # Examples
   /// ```
+  /// use std::collections::HashSet;
   /// use pie_graph::DAG;
-  /// use std::cmp::Ordering::*;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.reachable_from_set(&[human, dog]), HashSet::from([cat, mouse]));
+  /// assert_eq!(dag.reachable_from_set(&[human, cat]), HashSet::from([cat, mouse]));
+  /// ```
+  pub fn reachable_from_set(&self, sources: &[Node]) -> HashSet<Node, H> {
+    let mut scratch = self.stack_visited_scratch_space.take();
+    scratch.clear();
+    self.push_children_of_sources(&mut scratch.stack, sources);
+
+    while let Some(key) = scratch.stack.pop() {
+      if scratch.visited.insert(key) {
+        scratch.stack.extend(self.node_info[key.0].children.iter());
+      }
+    }
+
+    let reachable = std::mem::take(&mut scratch.visited);
+    self.stack_visited_scratch_space.set(scratch);
+    reachable
+  }
+
+  /// Returns `true` if `target` is reachable from any node in `sources`, `false` otherwise. Like
+  /// [`DAG::reachable_from_set`], `target` is only reachable from itself if it is reachable from another node in
+  /// `sources`. Nodes in `sources` that are not present in the graph are ignored.
+  ///
+  /// Performs a single multi-source iterative DFS search that stops as soon as `target` is found, reusing the internal
+  /// scratch space.
   ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert!(dag.is_reachable_from_set(&[dog, human], mouse));
+  /// assert!(!dag.is_reachable_from_set(&[dog, mouse], human));
+  /// ```
+  pub fn is_reachable_from_set(&self, sources: &[Node], target: impl Borrow<Node>) -> bool {
+    let target = *target.borrow();
+    let mut scratch = self.stack_visited_scratch_space.take();
+    scratch.clear();
+    self.push_children_of_sources(&mut scratch.stack, sources);
+
+    let mut reachable = false;
+    while let Some(key) = scratch.stack.pop() {
+      if key == target {
+        reachable = true;
+        break;
+      }
+      if scratch.visited.insert(key) {
+        scratch.stack.extend(self.node_info[key.0].children.iter());
+      }
+    }
+
+    self.stack_visited_scratch_space.set(scratch);
+    reachable
+  }
+
+  /// Returns the (weakly) connected component of `node`: the set of nodes that are connected to `node` by a path when
+  /// ignoring edge direction, following [neighbors](DAG::neighbors). The component includes `node` itself, and is
+  /// empty if `node` is not present in the graph.
+  ///
+  /// Performs an iterative DFS search, reusing the internal scratch space.
+  ///
+  /// # Examples
+  /// ```
+  /// use std::collections::HashSet;
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  ///
+  /// assert_eq!(dag.connected_component(human), HashSet::from([human, cat, dog]));
+  /// assert_eq!(dag.connected_component(mouse), HashSet::from([mouse]));
+  /// ```
+  pub fn connected_component(&self, node: impl Borrow<Node>) -> HashSet<Node, H> {
+    let node = *node.borrow();
+    let mut scratch = self.stack_visited_scratch_space.take();
+    scratch.clear();
+    if self.node_info.contains_key(node.0) {
+      scratch.stack.push(node);
+    }
+
+    while let Some(key) = scratch.stack.pop() {
+      if scratch.visited.insert(key) {
+        scratch.stack.extend(self.neighbors(key).map(|(_, n)| *n));
+      }
+    }
+
+    let component = std::mem::take(&mut scratch.visited);
+    self.stack_visited_scratch_space.set(scratch);
+    component
+  }
+
+  /// Return the descendants of `node` that are at most `max_distance` edges away from it, paired with their distance:
+  /// the length of the shortest path from `node` to them. Distance 1 are the direct children of `node`, distance 2
+  /// their children, and so forth. Descendants are returned in breadth-first order, thus ordered by distance. Neither
+  /// `node` itself (at distance 0) nor descendants further than `max_distance` away are returned.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the given node is not present in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
   /// let mut dag = DAG::new();
   ///
   /// let cat = dag.add_node(());
   /// let mouse = dag.add_node(());
   /// let dog = dag.add_node(());
   /// let human = dag.add_node(());
-  /// let horse = dag.add_node(());
   ///
   /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
   /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
   /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
   /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
   ///
-  /// assert_eq!(dag.topo_cmp(&human, &mouse), Less);
-  /// assert_eq!(dag.topo_cmp(&cat, &dog), Greater);
-  /// assert_eq!(dag.topo_cmp(&cat, &horse), Less);
+  /// assert_eq!(dag.descendants_within(human, 2), Ok(vec![(1, cat), (1, dog), (2, mouse)]));
+  /// assert_eq!(dag.descendants_within(human, 0), Ok(vec![]));
   /// ```
-  #[inline]
-  pub fn topo_cmp(&self, node_a: impl Borrow<Node>, node_b: impl Borrow<Node>) -> Ordering {
-    let node_a = node_a.borrow();
-    let node_b = node_b.borrow();
-    self.node_info[node_a.0]
+  pub fn descendants_within(&self, node: impl Borrow<Node>, max_distance: usize) -> Result<Vec<(usize, Node)>, Error> {
+    let node = *node.borrow();
+    if !self.node_info.contains_key(node.0) {
+      return Err(Error::NodeMissing);
+    }
+
+    let mut descendants = Vec::new();
+    let mut visited = HashSet::<_, H>::default();
+    visited.insert(node);
+    let mut queue = VecDeque::from([(0, node)]);
+    while let Some((distance, node)) = queue.pop_front() {
+      if distance == max_distance {
+        continue;
+      }
+      for child in self.node_info[node.0].children.iter() {
+        if visited.insert(*child) {
+          descendants.push((distance + 1, *child));
+          queue.push_back((distance + 1, *child));
+        }
+      }
+    }
+    Ok(descendants)
+  }
+
+  /// Return an iterator over descendants of `node` in breadth-first order, paired with their level: the length of the
+  /// shortest path from `node` to them. Level 1 are the direct children of `node`, level 2 their children, and so
+  /// forth. Descendants are therefore grouped by level, in increasing order. `node` itself is not returned.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the given node is not present in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let levels = dag.descendants_bfs(human).unwrap().map(|(level, _)| level).collect::<Vec<_>>();
+  ///
+  /// assert_eq!(levels, vec![1, 1, 2]);
+  /// ```
+  pub fn descendants_bfs(&self, node: impl Borrow<Node>) -> Result<impl Iterator<Item=(usize, Node)> + '_, Error> {
+    Bfs::new(self, *node.borrow(), true)
+  }
+
+  /// Return an iterator over ancestors of `node` in breadth-first order, paired with their level: the length of the
+  /// shortest path from them to `node`. Level 1 are the direct parents of `node`, level 2 their parents, and so forth.
+  /// Ancestors are therefore grouped by level, in increasing order. `node` itself is not returned.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the given node is not present in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let ancestors = dag.ancestors_bfs(mouse).unwrap().collect::<Vec<_>>();
+  ///
+  /// assert_eq!(ancestors[0], (1, cat));
+  /// assert_eq!(ancestors.len(), 3);
+  /// assert!(ancestors.contains(&(2, human)) && ancestors.contains(&(2, dog)));
+  /// ```
+  pub fn ancestors_bfs(&self, node: impl Borrow<Node>) -> Result<impl Iterator<Item=(usize, Node)> + '_, Error> {
+    Bfs::new(self, *node.borrow(), false)
+  }
+
+  /// Return an iterator over descendants of a node in the graph, in a topologically sorted order.
+  ///
+  /// Accessing the nodes in a sorted order requires the use of a BinaryHeap, so some performance penalty is paid there.
+  /// If all is required is access to the descendants of a node, use [`DAG::descendants_unsorted`].
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if the given node is not present in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let ordered_nodes = dag.descendants(human).unwrap().collect::<Vec<_>>();
+  ///
+  /// assert_eq!(ordered_nodes, vec![dog, cat, mouse]);
+  /// ```
+  pub fn descendants(&self, node: impl Borrow<Node>) -> Result<Descendants<N, E, H>, Error> {
+    let node = node.borrow();
+    if !self.node_info.contains_key(node.0) {
+      return Err(Error::NodeMissing);
+    }
+
+    let mut queue = BinaryHeap::new(); // OPTO: reuse allocation
+    // Add all children of selected node
+    queue.extend(
+      self.node_info[node.0]
+        .children
+        .iter()
+        .cloned()
+        .map(|child_node| {
+          let child_order = self.get_node(child_node).topo_order;
+          (Reverse(child_order), child_node)
+        }),
+    );
+    let visited = HashSet::<_, H>::default(); // OPTO: reuse allocation
+
+    Ok(Descendants {
+      dag: self,
+      queue,
+      visited,
+    })
+  }
+
+  /// Compare two nodes present in the graph, topographically.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// use std::cmp::Ordering::*;
+  ///
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  /// let horse = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.topo_cmp(&human, &mouse), Less);
+  /// assert_eq!(dag.topo_cmp(&cat, &dog), Greater);
+  /// assert_eq!(dag.topo_cmp(&cat, &horse), Less);
+  /// ```
+  #[inline]
+  pub fn topo_cmp(&self, node_a: impl Borrow<Node>, node_b: impl Borrow<Node>) -> Ordering {
+    let node_a = node_a.borrow();
+    let node_b = node_b.borrow();
+    self.node_info[node_a.0]
       .topo_order
       .cmp(&self.node_info[node_b.0].topo_order)
   }
 
+  /// Take a snapshot of the topological order of all nodes in the graph, which can be restored with
+  /// [`DAG::restore_order`].
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// use std::cmp::Ordering::*;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let snapshot = dag.snapshot_order();
+  ///
+  /// assert!(dag.add_edge(&mouse, &cat, ()).unwrap());
+  /// assert_eq!(dag.topo_cmp(&cat, &mouse), Greater);
+  ///
+  /// dag.remove_edge(&mouse, &cat);
+  /// dag.restore_order(snapshot).unwrap();
+  /// assert_eq!(dag.topo_cmp(&cat, &mouse), Less);
+  /// ```
+  pub fn snapshot_order(&self) -> OrderSnapshot {
+    OrderSnapshot(self.iter_unsorted().map(|(topo_order, node)| (node, topo_order)).collect())
+  }
+
+  /// Restore the topological order of all nodes in the graph to `snapshot`, taken with [`DAG::snapshot_order`]. Use
+  /// this to cheaply undo reordering caused by speculatively added edges, after those edges have been removed.
+  ///
+  /// # Errors
+  ///
+  /// Returns [`Error::NodeMissing`] if the nodes of the graph do not match the nodes of `snapshot`, or
+  /// [`Error::InvalidTopoOrder`] if an edge of the graph does not go from a node with a lower topological order to a
+  /// node with a higher topological order in `snapshot`, for example because an edge added after taking `snapshot` was
+  /// not removed. The graph is not modified when an error is returned.
+  pub fn restore_order(&mut self, snapshot: OrderSnapshot) -> Result<(), Error> {
+    if snapshot.0.len() != self.len() || snapshot.0.iter().any(|(node, _)| !self.contains_node(node)) {
+      return Err(Error::NodeMissing);
+    }
+    let topo_orders: HashMap<_, _, H> = snapshot.0.iter().copied().collect();
+    if self.edge_data.keys().any(|(src, dst)| topo_orders[src] >= topo_orders[dst]) {
+      return Err(Error::InvalidTopoOrder);
+    }
+    for (node, topo_order) in snapshot.0 {
+      self.node_info[node.0].topo_order = topo_order;
+    }
+    Ok(())
+  }
+  /// Compact the topological order of all nodes in the graph: reassign topological orders `1..=len` to the nodes in
+  /// their current relative order, removing gaps between topological orders. This preserves all [`DAG::topo_cmp`]
+  /// relationships. Gaps can occur when the graph was [loaded](DAG::load) with non-contiguous topological orders, and
+  /// accumulate in graphs built from such a graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use std::cmp::Ordering::Less;
+  /// use pie_graph::DAG;
+  ///
+  /// let (mut dag, nodes) = DAG::<_, _>::load([(10, "cat"), (20, "mouse")], [(0, 1, ())]).unwrap();
+  /// dag.compact();
+  ///
+  /// let mut topo_orders: Vec<_> = dag.iter_unsorted().map(|(topo_order, _)| topo_order).collect();
+  /// topo_orders.sort();
+  /// assert_eq!(topo_orders, vec![1, 2]);
+  /// assert_eq!(dag.topo_cmp(&nodes[0], &nodes[1]), Less);
+  /// ```
+  pub fn compact(&mut self) {
+    let mut nodes: Vec<_> = self.iter_unsorted().collect();
+    nodes.sort_unstable();
+    for (topo_order, (_, node)) in (1..).zip(nodes) {
+      self.node_info[node.0].topo_order = topo_order;
+    }
+    self.last_topo_order = self.len() as TopoOrder;
+  }
+
+  /// Return every elementary cycle in the graph, each as a list of nodes where each node has an edge to the next node,
+  /// and the last node has an edge to the first node. Returns an empty vector for a valid DAG.
+  ///
+  /// A DAG cannot contain cycles when edges are added through its methods, but it can when it was deserialized from
+  /// invalid data, for example. Use this to validate such a DAG and to report all its cycles, not just the first.
+  ///
+  /// Uses Johnson's algorithm, which finds all elementary cycles in `O((n + e)(c + 1))` time, where `n` is the number
+  /// of nodes, `e` the number of edges, and `c` the number of elementary cycles. Take into account that the number of
+  /// elementary cycles can be exponential in the number of nodes.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert!(dag.find_cycles().is_empty());
+  /// ```
+  pub fn find_cycles(&self) -> Vec<Vec<Node>> {
+    let nodes: Vec<Node> = self.node_info.keys().map(Node).collect();
+    let indices: HashMap<Node, usize, H> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let adjacency: Vec<Vec<usize>> = nodes.iter()
+      .map(|n| self.node_info[n.0].children.iter().map(|c| indices[c]).collect())
+      .collect();
+
+    let mut cycles = Vec::new();
+    let mut blocked = vec![false; nodes.len()];
+    let mut blocked_by: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut start = 0;
+    while start < nodes.len() {
+      // Find the strongly connected component with the least node, of the subgraph induced by nodes from `start`, that
+      // contains a cycle.
+      let component = strongly_connected_components(&adjacency, start).into_iter()
+        .filter(|c| c.len() > 1 || adjacency[c[0]].contains(&c[0]))
+        .min_by_key(|c| c.iter().min().copied());
+      let Some(component) = component else {
+        break;
+      };
+      start = *component.iter().min().unwrap();
+      let mut in_component = vec![false; nodes.len()];
+      for &node in &component {
+        in_component[node] = true;
+        blocked[node] = false;
+        blocked_by[node].clear();
+      }
+      // Successors of each node within the component, precomputed so that the DFS can index into them.
+      let mut successors: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+      for &node in &component {
+        successors[node] = adjacency[node].iter().copied().filter(|n| in_component[*n]).collect();
+      }
+
+      // Find all cycles through `start` in the component, with an iterative DFS. Nodes are blocked while on the path,
+      // and stay blocked after backtracking when no cycle was found through them, until a node they lead to is
+      // unblocked.
+      let mut path = vec![start];
+      // Stack of (node, index of next successor, whether a cycle was found through node).
+      let mut stack = vec![(start, 0, false)];
+      blocked[start] = true;
+      while let Some((node, next, found)) = stack.last_mut() {
+        if let Some(&successor) = successors[*node].get(*next) {
+          *next += 1;
+          if successor == start {
+            cycles.push(path.iter().map(|i| nodes[*i]).collect());
+            *found = true;
+          } else if !blocked[successor] {
+            blocked[successor] = true;
+            path.push(successor);
+            stack.push((successor, 0, false));
+          }
+        } else {
+          let (node, _, found) = stack.pop().unwrap();
+          if found {
+            unblock(node, &mut blocked, &mut blocked_by);
+          } else {
+            for &successor in &successors[node] {
+              if !blocked_by[successor].contains(&node) {
+                blocked_by[successor].push(node);
+              }
+            }
+          }
+          path.pop();
+          if let Some((_, _, parent_found)) = stack.last_mut() {
+            *parent_found |= found;
+          }
+        }
+      }
+      start += 1;
+    }
+    cycles
+  }
+
+
+  /// Return the level of every node in the graph, where the level of a node is the length of the longest path from any
+  /// root node (a node without parents) to it. Root nodes are at level 0.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let levels = dag.longest_path_levels();
+  /// assert_eq!(levels[&human], 0);
+  /// assert_eq!(levels[&dog], 1);
+  /// assert_eq!(levels[&cat], 2);
+  /// assert_eq!(levels[&mouse], 3);
+  /// ```
+  pub fn longest_path_levels(&self) -> HashMap<Node, u32> {
+    let mut nodes: Vec<_> = self.iter_unsorted().collect();
+    nodes.sort_unstable_by_key(|(topo_order, _)| *topo_order);
+
+    // Visiting nodes in topological order guarantees that all parents of a node have been visited before the node.
+    let mut levels = HashMap::with_capacity(nodes.len());
+    for (_, node) in nodes {
+      let level = self.get_node(node).parents.iter()
+        .map(|parent| levels[parent] + 1)
+        .max()
+        .unwrap_or(0);
+      levels.insert(node, level);
+    }
+    levels
+  }
+
+  /// Return the critical path of the graph: the path from a root node (a node without parents) that maximizes the
+  /// total `cost` of its nodes, ordered from the root node to the last node of the path. Total costs saturate at
+  /// [`u64::MAX`]. When multiple paths have the maximum total cost, the path ending at the node that is first in the
+  /// topological order is returned. Returns an empty path if the graph is empty.
+  ///
+  /// Where [`DAG::longest_path_levels`] counts edges, this weighs nodes by `cost`, for example the duration of building
+  /// a node, such that the total cost of the critical path is a lower bound on the duration of a fully parallel build.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(1);
+  /// let mouse = dag.add_node(1);
+  /// let dog = dag.add_node(5);
+  /// let human = dag.add_node(1);
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.critical_path(|_, cost| *cost), vec![human, dog]);
+  /// assert_eq!(dag.critical_path(|_, _| 1), vec![human, cat, mouse]);
+  /// ```
+  pub fn critical_path(&self, cost: impl Fn(Node, &N) -> u64) -> Vec<Node> {
+    let mut nodes: Vec<_> = self.iter_unsorted().collect();
+    nodes.sort_unstable_by_key(|(topo_order, _)| *topo_order);
+
+    // Visiting nodes in topological order guarantees that all parents of a node have been visited before the node.
+    let mut best: HashMap<Node, (u64, Option<Node>)> = HashMap::with_capacity(nodes.len());
+    let mut last: Option<(u64, Node)> = None;
+    for (_, node) in nodes {
+      let node_info = self.get_node(node);
+      let mut predecessor: Option<(u64, Node)> = None;
+      for parent in node_info.parents.iter() {
+        let (parent_total, _) = best[parent];
+        if predecessor.is_none_or(|(total, _)| parent_total > total) {
+          predecessor = Some((parent_total, *parent));
+        }
+      }
+      let total = predecessor.map_or(0, |(total, _)| total).saturating_add(cost(node, &node_info.data));
+      best.insert(node, (total, predecessor.map(|(_, parent)| parent)));
+      if last.is_none_or(|(last_total, _)| total > last_total) {
+        last = Some((total, node));
+      }
+    }
+
+    let mut path = Vec::new();
+    let mut current = last.map(|(_, node)| node);
+    while let Some(node) = current {
+      path.push(node);
+      current = best[&node].1;
+    }
+    path.reverse();
+    path
+  }
+
+  /// Count the number of distinct paths from `src` to `dst`, saturating at [`u64::MAX`]. There is exactly one (empty)
+  /// path from a node to itself, and no paths from `src` to `dst` if `dst` is not reachable from `src`.
+  ///
+  /// A high number of paths between two nodes often signals redundant transitive edges.
+  ///
+  /// Path counts are memoized per node, visiting the descendants of `src` in topological order up to `dst`, such that
+  /// the count of every parent of a node is known before the node itself is visited.
+  ///
+  /// # Errors
+  ///
+  /// This function will return an error if `src` or `dst` is not present in the graph.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// let dog = dag.add_node(());
+  /// let human = dag.add_node(());
+  ///
+  /// assert!(dag.add_edge(&human, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&human, &dog, ()).unwrap());
+  /// assert!(dag.add_edge(&dog, &cat, ()).unwrap());
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// assert_eq!(dag.count_paths(human, mouse), Ok(2));
+  /// assert_eq!(dag.count_paths(dog, mouse), Ok(1));
+  /// assert_eq!(dag.count_paths(mouse, human), Ok(0));
+  /// ```
+  pub fn count_paths(&self, src: impl Borrow<Node>, dst: impl Borrow<Node>) -> Result<u64, Error> {
+    let src = *src.borrow();
+    let dst = *dst.borrow();
+    if !self.node_info.contains_key(src.0) || !self.node_info.contains_key(dst.0) {
+      return Err(Error::NodeMissing);
+    }
+    if src == dst {
+      return Ok(1);
+    }
+
+    let dst_order = self.get_node(dst).topo_order;
+    let mut counts = HashMap::<Node, u64, H>::default();
+    counts.insert(src, 1);
+    for node in self.descendants(src)? {
+      if self.get_node(node).topo_order > dst_order {
+        break; // Nodes after `dst` in topological order cannot be on a path to `dst`.
+      }
+      // Parents that are not descendants of `src` are not in `counts`, and do not contribute any paths.
+      let count = self.get_node(node).parents.iter()
+        .filter_map(|parent| counts.get(parent))
+        .fold(0u64, |count, parent_count| count.saturating_add(*parent_count));
+      if node == dst {
+        return Ok(count);
+      }
+      counts.insert(node, count);
+    }
+    Ok(0)
+  }
+
+  /// Returns true if this graph is structurally equal to `other`: both graphs have the same multiset of node data, and
+  /// the same edges (with equal edge data) between nodes matched by their data. [`Node`] identifiers and topological
+  /// orders are ignored, so graphs built in different orders can be compared.
+  ///
+  /// This is *not* a full graph isomorphism check: nodes are matched by their data, taking O(V+E) time. When node data
+  /// is unique, this is exact. Nodes with equal data are matched arbitrarily, so this may return false for graphs that
+  /// only become equal under a different matching of such nodes.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag_a = DAG::new();
+  /// let cat = dag_a.add_node("cat");
+  /// let mouse = dag_a.add_node("mouse");
+  /// assert!(dag_a.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let mut dag_b = DAG::new();
+  /// let mouse = dag_b.add_node("mouse");
+  /// let cat = dag_b.add_node("cat");
+  /// assert!(!dag_a.structurally_eq(&dag_b));
+  ///
+  /// assert!(dag_b.add_edge(&cat, &mouse, ()).unwrap());
+  /// assert!(dag_a.structurally_eq(&dag_b));
+  /// ```
+  pub fn structurally_eq(&self, other: &Self) -> bool where
+    N: Eq + Hash,
+    E: Eq,
+  {
+    if self.len() != other.len() || self.edge_count() != other.edge_count() {
+      return false;
+    }
+
+    let mut other_nodes_by_data = HashMap::<_, Vec<Node>, H>::default();
+    for (key, node_info) in other.node_info.iter() {
+      other_nodes_by_data.entry(&node_info.data).or_default().push(Node(key));
+    }
+    let mut matched_nodes = HashMap::<_, _, H>::default();
+    for (key, node_info) in self.node_info.iter() {
+      let Some(other_node) = other_nodes_by_data.get_mut(&node_info.data).and_then(|n| n.pop()) else {
+        return false;
+      };
+      matched_nodes.insert(Node(key), other_node);
+    }
+
+    // Edge counts are equal and nodes are matched one-to-one, so all edges of `other` are matched by edges of `self`.
+    self.edge_data.iter().all(|((src, dst), data)| {
+      other.get_edge_data(matched_nodes[src], matched_nodes[dst]) == Some(data)
+    })
+  }
+
+  /// Create a new graph with the same topology and topological orders as this graph, with node data transformed by
+  /// `f`, which is called with every node and its data. Edge data is cloned.
+  ///
+  /// Returns the new graph and a mapping from nodes in this graph to their corresponding nodes in the new graph. Nodes
+  /// of the new graph may differ from the nodes of this graph, so use this mapping to correlate them.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node("cat");
+  /// let mouse = dag.add_node("mouse");
+  /// assert!(dag.add_edge(&cat, &mouse, ()).unwrap());
+  ///
+  /// let (lengths, mapping) = dag.map(|_, data| data.len());
+  /// assert_eq!(lengths.get_node_data(mapping[&cat]), Some(&3));
+  /// assert_eq!(lengths.get_node_data(mapping[&mouse]), Some(&5));
+  /// assert!(lengths.contains_edge(mapping[&cat], mapping[&mouse]));
+  /// ```
+  pub fn map<N2, F: FnMut(Node, &N) -> N2>(&self, f: F) -> (DAG<N2, E, H>, HashMap<Node, Node, H>) where
+    E: Clone
+  {
+    self.map_data(f, |_, _, data| data.clone())
+  }
+
+  /// Create a new graph with the same topology and topological orders as this graph, with edge data transformed by
+  /// `f`, which is called with the source node, destination node, and data of every edge. Node data is cloned.
+  ///
+  /// Returns the new graph and a mapping from nodes in this graph to their corresponding nodes in the new graph. Nodes
+  /// of the new graph may differ from the nodes of this graph, so use this mapping to correlate them.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node(());
+  /// let mouse = dag.add_node(());
+  /// assert!(dag.add_edge(&cat, &mouse, "eats").unwrap());
+  ///
+  /// let (dag, mapping) = dag.map_edges(|_, _, data| data.to_uppercase());
+  /// assert_eq!(dag.get_edge_data(mapping[&cat], mapping[&mouse]), Some(&"EATS".to_string()));
+  /// ```
+  pub fn map_edges<E2, F: FnMut(Node, Node, &E) -> E2>(&self, f: F) -> (DAG<N, E2, H>, HashMap<Node, Node, H>) where
+    N: Clone
+  {
+    self.map_data(|_, data| data.clone(), f)
+  }
+
+  /// Create a new graph with the same topology and topological orders as this graph, without node and edge data. This
+  /// is cheaper than cloning when only the shape of the graph is needed, such as for reachability analysis or
+  /// speculative changes to the graph, as no data is copied.
+  ///
+  /// Returns the new graph and a mapping from nodes in this graph to their corresponding nodes in the new graph. Nodes
+  /// of the new graph may differ from the nodes of this graph, so use this mapping to correlate them.
+  ///
+  /// # Examples
+  /// ```
+  /// use pie_graph::DAG;
+  /// let mut dag = DAG::new();
+  ///
+  /// let cat = dag.add_node("cat".to_string());
+  /// let mouse = dag.add_node("mouse".to_string());
+  /// assert!(dag.add_edge(&cat, &mouse, "eats".to_string()).unwrap());
+  ///
+  /// let (topology, mapping) = dag.clone_topology();
+  /// assert!(topology.contains_edge(mapping[&cat], mapping[&mouse]));
+  /// assert_eq!(topology.get_node_data(mapping[&cat]), Some(&()));
+  /// ```
+  pub fn clone_topology(&self) -> (DAG<(), (), H>, HashMap<Node, Node, H>) {
+    self.map_data(|_, _| (), |_, _, _| ())
+  }
+
+
+  fn map_data<N2, E2>(
+    &self,
+    mut map_node: impl FnMut(Node, &N) -> N2,
+    mut map_edge: impl FnMut(Node, Node, &E) -> E2,
+  ) -> (DAG<N2, E2, H>, HashMap<Node, Node, H>) {
+    let mut node_info = SlotMap::with_capacity(self.node_info.len());
+    let mut mapping = HashMap::<_, _, H>::default();
+    mapping.reserve(self.node_info.len());
+    for (key, info) in self.node_info.iter() {
+      let data = map_node(Node(key), &info.data);
+      let new_key = node_info.insert(NodeInfo::new(info.topo_order, data));
+      mapping.insert(Node(key), Node(new_key));
+    }
+    // Add parents and children in the same order, so that iteration orders are preserved as well.
+    for (key, info) in self.node_info.iter() {
+      let new_info = &mut node_info[mapping[&Node(key)].0];
+      new_info.parents.extend(info.parents.iter().map(|parent| mapping[parent]));
+      new_info.children.extend(info.children.iter().map(|child| mapping[child]));
+    }
+    let edge_data = self.edge_data.iter()
+      .map(|((src, dst), data)| ((mapping[src], mapping[dst]), map_edge(*src, *dst, data)))
+      .collect();
+
+    let dag = DAG {
+      node_info,
+      edge_data,
+      last_topo_order: self.last_topo_order,
+      stack_visited_scratch_space: SharedScratchSpace::default(),
+      edge_kind_index: None,
+      tags: self.tags.as_ref().map(|tags| tags.iter().map(|(node, tags)| (mapping[node], tags.clone())).collect()),
+      #[cfg(feature = "reorder_stats")]
+      reorder_stats: ReorderStats::default(),
+    };
+    (dag, mapping)
+  }
+
+  fn insert_edge_data(&mut self, src: Node, dst: Node, data: E) {
+    if let Some(index) = &mut self.edge_kind_index {
+      index.insert(src, dst, &data);
+    }
+    self.edge_data.insert((src, dst), data);
+  }
+  fn remove_edge_data(&mut self, src: Node, dst: Node) -> Option<E> {
+    let data = self.edge_data.remove(&(src, dst))?;
+    if let Some(index) = &mut self.edge_kind_index {
+      index.remove(src, dst, &data);
+    }
+    Some(data)
+  }
+
+  fn search_transitive_edge(&self, src: Node, dst: Node, scratch: &mut StackVisitedScratchSpace<Node, H>) -> bool {
+    scratch.clear();
+    scratch.stack.push(src);
+
+    // For each node key popped off the stack, check that we haven't seen it
+    // before, then check if its children contain the node we're searching for.
+    // If they don't, continue the search by extending the stack with the children.
+    while let Some(key) = scratch.stack.pop() {
+      if scratch.visited.contains(&key) {
+        continue;
+      } else {
+        scratch.visited.insert(key);
+      }
+
+      let children = &self.node_info.get(key.0).unwrap().children;
+      if children.contains(&dst) {
+        return true;
+      } else {
+        scratch.stack.extend(children.iter());
+        continue;
+      }
+    }
+
+    // If we exhaust the stack, then there is no transitive dependency.
+    false
+  }
+
+  fn push_children_of_sources(&self, stack: &mut Vec<Node>, sources: &[Node]) {
+    for source in sources {
+      if let Some(node_info) = self.node_info.get(source.0) {
+        stack.extend(node_info.children.iter());
+      }
+    }
+  }
+
+  #[inline]
+  fn record_reorder_cost(&mut self, _cost: usize) {
+    #[cfg(feature = "reorder_stats")] {
+      let stats = &mut self.reorder_stats;
+      stats.last_reorder_cost = _cost;
+      if _cost > 0 {
+        stats.max_reorder_cost = stats.max_reorder_cost.max(_cost);
+        stats.total_reorder_cost += _cost as u64;
+        stats.reorder_count += 1;
+      }
+    }
+  }
 
   fn dfs_forward(
     &self,
@@ -1021,6 +2613,11 @@ impl<N, E, H: BuildHasher + Default> DAG<N, E, H> {
   }
 }
 
+/// Snapshot of the topological order of all nodes in a [`DAG`], taken with [`DAG::snapshot_order`] and restored with
+/// [`DAG::restore_order`].
+#[derive(Clone, Debug)]
+pub struct OrderSnapshot(Vec<(Node, TopoOrder)>);
+
 /// An iterator over the descendants of a node in the graph, which outputs the nodes in an unsorted order with their
 /// topological ranking.
 ///
@@ -1131,6 +2728,119 @@ impl<'a, N, E, H: BuildHasher + Default> Iterator for Descendants<'a, N, E, H> {
 }
 
 
+/// Breadth-first iterator over descendants (if `forward`) or ancestors (otherwise) of a node, paired with their level.
+struct Bfs<'a, N, E, H> {
+  dag: &'a DAG<N, E, H>,
+  forward: bool,
+  queue: VecDeque<(usize, Node)>,
+  visited: HashSet<Node, H>,
+}
+
+impl<'a, N, E, H: BuildHasher + Default> Bfs<'a, N, E, H> {
+  #[inline]
+  fn new(dag: &'a DAG<N, E, H>, node: Node, forward: bool) -> Result<Self, Error> {
+    if !dag.node_info.contains_key(node.0) {
+      return Err(Error::NodeMissing);
+    }
+    let mut visited = HashSet::<_, H>::default();
+    visited.insert(node);
+    Ok(Self { dag, forward, queue: VecDeque::from([(0, node)]), visited })
+  }
+}
+
+impl<'a, N, E, H: BuildHasher> Iterator for Bfs<'a, N, E, H> {
+  type Item = (usize, Node);
+  #[inline]
+  fn next(&mut self) -> Option<Self::Item> {
+    // Nodes are enqueued when visited, so the front of the queue is the next node to return, except for the start node
+    // at level 0, which is only expanded.
+    loop {
+      let (level, node) = self.queue.pop_front()?;
+      let node_info = &self.dag.node_info[node.0];
+      let neighbours = if self.forward { &node_info.children } else { &node_info.parents };
+      for neighbour in neighbours.iter() {
+        if self.visited.insert(*neighbour) {
+          self.queue.push_back((level + 1, *neighbour));
+        }
+      }
+      if level != 0 {
+        return Some((level, node));
+      }
+    }
+  }
+}
+
+/// Return the strongly connected components of the subgraph of `adjacency` induced by nodes from `start`, using an
+/// iterative version of Tarjan's algorithm. `adjacency` contains the successors of each node.
+fn strongly_connected_components(adjacency: &[Vec<usize>], start: usize) -> Vec<Vec<usize>> {
+  const UNVISITED: usize = usize::MAX;
+  let mut index = vec![UNVISITED; adjacency.len()];
+  let mut low_link = vec![0; adjacency.len()];
+  let mut on_stack = vec![false; adjacency.len()];
+  let mut stack = Vec::new();
+  let mut next_index = 0;
+  let mut components = Vec::new();
+  for root in start..adjacency.len() {
+    if index[root] != UNVISITED {
+      continue;
+    }
+    // Stack of (node, index of next successor).
+    let mut call_stack = vec![(root, 0)];
+    index[root] = next_index;
+    low_link[root] = next_index;
+    next_index += 1;
+    stack.push(root);
+    on_stack[root] = true;
+    while let Some((node, next)) = call_stack.last_mut() {
+      let node = *node;
+      if let Some(&successor) = adjacency[node].get(*next) {
+        *next += 1;
+        if successor < start {
+          continue;
+        }
+        if index[successor] == UNVISITED {
+          index[successor] = next_index;
+          low_link[successor] = next_index;
+          next_index += 1;
+          stack.push(successor);
+          on_stack[successor] = true;
+          call_stack.push((successor, 0));
+        } else if on_stack[successor] {
+          low_link[node] = low_link[node].min(index[successor]);
+        }
+      } else {
+        call_stack.pop();
+        if let Some((parent, _)) = call_stack.last() {
+          low_link[*parent] = low_link[*parent].min(low_link[node]);
+        }
+        if low_link[node] == index[node] {
+          let mut component = Vec::new();
+          while let Some(member) = stack.pop() {
+            on_stack[member] = false;
+            component.push(member);
+            if member == node {
+              break;
+            }
+          }
+          components.push(component);
+        }
+      }
+    }
+  }
+  components
+}
+
+/// Unblock `node` for Johnson's algorithm in [`DAG::find_cycles`], iteratively unblocking the nodes blocked by it.
+fn unblock(node: usize, blocked: &mut [bool], blocked_by: &mut [Vec<usize>]) {
+  let mut stack = vec![node];
+  while let Some(node) = stack.pop() {
+    if blocked[node] {
+      blocked[node] = false;
+      stack.append(&mut blocked_by[node]);
+    }
+  }
+}
+
 #[derive(Debug)]
 struct StackVisitedScratchSpace<T, H> {
   stack: Vec<T>,
@@ -1150,6 +2860,31 @@ impl<T, H> StackVisitedScratchSpace<T, H> {
   }
 }
 
+/// Scratch space shared by `&self` queries. Unlike a `Cell`, this can be shared between threads: a query takes the
+/// scratch space if no other query is using it, and otherwise uses a new default scratch space.
+#[derive(Default, Debug)]
+struct SharedScratchSpace<T>(Mutex<T>);
+
+impl<T: Default> SharedScratchSpace<T> {
+  #[inline]
+  fn take(&self) -> T {
+    match self.0.try_lock() {
+      Ok(mut scratch) => mem::take(&mut *scratch),
+      Err(_) => T::default(),
+    }
+  }
+  #[inline]
+  fn set(&self, scratch: T) {
+    if let Ok(mut shared) = self.0.try_lock() {
+      *shared = scratch;
+    }
+  }
+  #[inline]
+  fn get_mut(&mut self) -> &mut T {
+    self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -1184,6 +2919,797 @@ mod tests {
     Ok(([dog, cat, mouse, lion, human, gazelle, grass], dag))
   }
 
+  #[test]
+  fn from_nodes_and_edges() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], expected) = get_basic_dag()?;
+    let (dag, nodes) = DAG::<_, _>::from_nodes_and_edges([(); 7], [
+      (3, 4, ()),
+      (3, 5, ()),
+      (4, 0, ()),
+      (4, 1, ()),
+      (0, 1, ()),
+      (1, 2, ()),
+      (5, 6, ()),
+      (2, 6, ()),
+    ])?;
+
+    assert_eq!(nodes, vec![dog, cat, mouse, lion, human, gazelle, grass]);
+    assert_eq!(dag.len(), expected.len());
+    assert_eq!(dag.edge_count(), expected.edge_count());
+    for src in &nodes {
+      for dst in &nodes {
+        assert_eq!(dag.contains_edge(src, dst), expected.contains_edge(src, dst));
+        assert_eq!(dag.topo_cmp(src, dst), expected.topo_cmp(src, dst));
+      }
+    }
+
+    assert_eq!(DAG::<_, _>::from_nodes_and_edges([(); 2], [(0, 1, ()), (1, 0, ())]).err(), Some(Error::CycleDetected));
+    assert_eq!(DAG::<_, _>::from_nodes_and_edges([(); 2], [(0, 0, ())]).err(), Some(Error::CycleDetected));
+    assert_eq!(DAG::<(), ()>::from_nodes_and_edges([(); 2], [(0, 2, ())]).err(), Some(Error::NodeMissing));
+
+    Ok(())
+  }
+
+  #[test]
+  fn load() -> Result<(), Error> {
+    let (nodes, expected) = get_basic_dag()?;
+    let topo_orders: Vec<_> = nodes.iter().map(|node| {
+      expected.iter_unsorted().find(|(_, n)| n == node).map(|(topo_order, _)| topo_order).unwrap()
+    }).collect();
+    let index = |node: &Node| nodes.iter().position(|n| n == node).unwrap();
+    let edges: Vec<_> = nodes.iter()
+      .flat_map(|src| expected.get_outgoing_edge_nodes(src).map(|dst| (index(src), index(dst), ())))
+      .collect();
+    let (mut dag, loaded_nodes) = DAG::<_, _>::load(topo_orders.iter().map(|topo_order| (*topo_order, ())), edges)?;
+
+    assert_eq!(loaded_nodes, nodes);
+    for src in &nodes {
+      for dst in &nodes {
+        assert_eq!(dag.contains_edge(src, dst), expected.contains_edge(src, dst));
+        assert_eq!(dag.contains_transitive_edge(src, dst), expected.contains_transitive_edge(src, dst));
+        assert_eq!(dag.topo_cmp(src, dst), expected.topo_cmp(src, dst));
+      }
+      assert_eq!(dag.descendants(src)?.collect::<Vec<_>>(), expected.descendants(src)?.collect::<Vec<_>>());
+    }
+
+    // Loaded graph is a fully functional graph.
+    let [dog, cat, _, lion, ..] = nodes;
+    assert_eq!(dag.add_edge(cat, lion, ()), Err(Error::CycleDetected));
+    let wolf = dag.add_node(());
+    assert!(dag.add_edge(wolf, dog, ())?);
+    assert!(dag.add_edge(lion, wolf, ())?);
+    assert!(dag.contains_transitive_edge(lion, cat));
+
+    assert_eq!(DAG::<_, _>::load([(1, ()), (2, ())], [(1, 0, ())]).err(), Some(Error::InvalidTopoOrder));
+    assert_eq!(DAG::<_, ()>::load([(1, ()), (1, ())], []).err(), Some(Error::InvalidTopoOrder));
+    assert_eq!(DAG::<_, _>::load([(1, ()), (2, ())], [(0, 0, ())]).err(), Some(Error::CycleDetected));
+    assert_eq!(DAG::<_, _>::load([(1, ()), (2, ())], [(0, 2, ())]).err(), Some(Error::NodeMissing));
+
+    Ok(())
+  }
+
+  #[test]
+  fn load_high_topo_orders() -> Result<(), Error> {
+    let (mut dag, nodes) = DAG::<_, _>::load([(TopoOrder::MAX - 1, ()), (TopoOrder::MAX, ())], [(0, 1, ())])?;
+    let [cat, mouse] = nodes[..] else { unreachable!() };
+
+    // Orders are compacted, so that adding nodes does not overflow them.
+    let mut topo_orders: Vec<_> = dag.iter_unsorted().map(|(topo_order, _)| topo_order).collect();
+    topo_orders.sort_unstable();
+    assert_eq!(topo_orders, vec![1, 2]);
+    assert_eq!(dag.topo_cmp(cat, mouse), Ordering::Less);
+    let dog = dag.add_node(());
+    assert!(dag.add_edge(mouse, dog, ())?);
+    assert!(dag.contains_transitive_edge(cat, dog));
+    assert_eq!(dag.add_edge(dog, cat, ()), Err(Error::CycleDetected));
+
+    // Orders below the threshold are kept as is.
+    let (dag, _) = DAG::<_, ()>::load([(TopoOrder::MAX / 2, ())], [])?;
+    assert_eq!(dag.iter_unsorted().map(|(topo_order, _)| topo_order).collect::<Vec<_>>(), vec![TopoOrder::MAX / 2]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn reachable_count() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+
+    assert_eq!(dag.reachable_count(human)?, dag.descendants(human)?.count());
+    assert_eq!(dag.reachable_count(human)?, 4);
+    assert_eq!(dag.reachable_count(lion)?, 6);
+    for node in [dog, cat, mouse, gazelle, grass] {
+      assert_eq!(dag.reachable_count(node)?, dag.descendants(node)?.count());
+    }
+    assert_eq!(dag.reachable_count(grass)?, 0);
+
+    dag.remove_node(grass);
+    assert_eq!(dag.reachable_count(grass), Err(Error::NodeMissing));
+    assert_eq!(dag.reachable_count(human)?, 3);
+
+    Ok(())
+  }
+
+  #[test]
+  fn count_paths() -> Result<(), Error> {
+    // Diamond: apex -> left -> sink, apex -> right -> sink.
+    let mut dag = DAG::<(), ()>::new();
+    let apex = dag.add_node(());
+    let left = dag.add_node(());
+    let right = dag.add_node(());
+    let sink = dag.add_node(());
+    dag.add_edge(apex, left, ())?;
+    dag.add_edge(apex, right, ())?;
+    dag.add_edge(left, sink, ())?;
+    dag.add_edge(right, sink, ())?;
+
+    assert_eq!(dag.count_paths(apex, sink)?, 2);
+    assert_eq!(dag.count_paths(left, sink)?, 1);
+    assert_eq!(dag.count_paths(apex, apex)?, 1);
+    assert_eq!(dag.count_paths(left, right)?, 0);
+    assert_eq!(dag.count_paths(sink, apex)?, 0);
+
+    dag.add_edge(apex, sink, ())?;
+    assert_eq!(dag.count_paths(apex, sink)?, 3);
+
+    dag.remove_node(sink);
+    assert_eq!(dag.count_paths(apex, sink), Err(Error::NodeMissing));
+    assert_eq!(dag.count_paths(sink, apex), Err(Error::NodeMissing));
+
+    Ok(())
+  }
+
+  #[test]
+  fn count_paths_saturates() -> Result<(), Error> {
+    // Chain of 70 diamonds has 2^70 paths from start to end, which does not fit in a u64.
+    let mut dag = DAG::<(), ()>::new();
+    let start = dag.add_node(());
+    let mut end = start;
+    for _ in 0..70 {
+      let left = dag.add_node(());
+      let right = dag.add_node(());
+      let sink = dag.add_node(());
+      dag.add_edge(end, left, ())?;
+      dag.add_edge(end, right, ())?;
+      dag.add_edge(left, sink, ())?;
+      dag.add_edge(right, sink, ())?;
+      end = sink;
+    }
+    assert_eq!(dag.count_paths(start, end)?, u64::MAX);
+
+    Ok(())
+  }
+
+  #[test]
+  fn node_index() -> Result<(), Error> {
+    let (nodes, mut dag) = get_basic_dag()?;
+
+    for node in nodes {
+      assert_eq!(dag.node_from_index(node.index()), Some(node));
+    }
+    let [dog, cat, ..] = nodes;
+    assert_ne!(dog.index(), cat.index());
+
+    dag.remove_node(dog);
+    assert_eq!(dag.node_from_index(dog.index()), None);
+    // A new node reusing the slot of the removed node gets a different index.
+    let wolf = dag.add_node(());
+    assert_ne!(wolf.index(), dog.index());
+    assert_eq!(dag.node_from_index(dog.index()), None);
+    assert_eq!(dag.node_from_index(wolf.index()), Some(wolf));
+
+    Ok(())
+  }
+
+  #[test]
+  fn descendants_within() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+
+    let direct_children: HashSet<_> = dag.descendants_within(human, 1)?.into_iter().collect();
+    assert_eq!(direct_children, HashSet::from([(1, dog), (1, cat)]));
+
+    // `cat` is both a direct child and a grandchild of `human`: it is returned once, at its shortest distance.
+    let descendants = dag.descendants_within(human, 2)?;
+    assert_eq!(descendants.len(), 3);
+    assert!(descendants.contains(&(1, cat)));
+    assert_eq!(descendants.last(), Some(&(2, mouse)));
+
+    let descendants = dag.descendants_within(lion, 2)?;
+    assert_eq!(descendants.len(), 5);
+    assert!(descendants.iter().all(|(distance, _)| *distance <= 2));
+    assert!(descendants.contains(&(2, grass)));
+    assert!(!descendants.iter().any(|(_, node)| *node == mouse));
+    assert_eq!(dag.descendants_within(lion, usize::MAX)?.len(), dag.reachable_count(lion)?);
+
+    assert_eq!(dag.descendants_within(gazelle, 0)?, vec![]);
+    assert_eq!(dag.descendants_within(grass, 5)?, vec![]);
+
+    dag.remove_node(grass);
+    assert_eq!(dag.descendants_within(grass, 1), Err(Error::NodeMissing));
+
+    Ok(())
+  }
+
+  #[test]
+  fn would_create_cycle() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], _) = get_basic_dag()?;
+    let cases = [
+      (mouse, lion), (cat, human), (grass, lion), (human, human), // Cycles.
+      (lion, mouse), (human, grass), (gazelle, dog), (dog, cat), (mouse, grass), (grass, mouse), // No cycles.
+    ];
+    for (src, dst) in cases {
+      // Start from a fresh graph for each case, as successfully adding an edge changes the graph. Building the same
+      // graph in the same order creates the same nodes.
+      let (_, mut dag) = get_basic_dag()?;
+      let would_create_cycle = dag.would_create_cycle(src, dst)?;
+      match dag.add_edge(src, dst, ()) {
+        Err(Error::CycleDetected) => assert!(would_create_cycle, "expected cycle from {:?} to {:?}", src, dst),
+        Ok(_) => assert!(!would_create_cycle, "expected no cycle from {:?} to {:?}", src, dst),
+        Err(e) => return Err(e),
+      }
+    }
+
+    let (_, mut dag) = get_basic_dag()?;
+    dag.remove_node(grass);
+    assert_eq!(dag.would_create_cycle(grass, lion), Err(Error::NodeMissing));
+    assert_eq!(dag.would_create_cycle(lion, grass), Err(Error::NodeMissing));
+
+    Ok(())
+  }
+
+  #[test]
+  fn map() -> Result<(), Error> {
+    let (nodes, mut dag) = get_basic_dag()?;
+    dag.remove_node(nodes[2]); // Remove `mouse` so that not all slots are occupied.
+
+    let mut next = 0;
+    let (mapped, mapping) = dag.map(|_, _| { next += 1; next });
+    assert_eq!(mapped.len(), dag.len());
+    assert_eq!(mapped.edge_count(), dag.edge_count());
+    assert_eq!(mapping.len(), dag.len());
+    let mut data: Vec<_> = mapped.iter_nodes_topo().map(|(_, data)| *data).collect();
+    data.sort_unstable();
+    assert_eq!(data, (1..=6).collect::<Vec<_>>());
+    for (_, node) in dag.iter_unsorted() {
+      let mapped_node = mapping[&node];
+      let children: Vec<_> = dag.get_outgoing_edge_nodes(node).map(|child| mapping[child]).collect();
+      let mapped_children: Vec<_> = mapped.get_outgoing_edge_nodes(mapped_node).copied().collect();
+      assert_eq!(children, mapped_children);
+      let parents: Vec<_> = dag.get_incoming_edge_nodes(node).map(|parent| mapping[parent]).collect();
+      let mapped_parents: Vec<_> = mapped.get_incoming_edge_nodes(mapped_node).copied().collect();
+      assert_eq!(parents, mapped_parents);
+      for (other_node, other_mapped_node) in mapping.iter() {
+        assert_eq!(dag.topo_cmp(node, other_node), mapped.topo_cmp(mapped_node, other_mapped_node));
+      }
+    }
+
+    // Mapped graph is a fully functional graph.
+    let [dog, cat, _, lion, ..] = nodes;
+    let mut mapped = mapped;
+    assert_eq!(mapped.add_edge(mapping[&cat], mapping[&lion], ()), Err(Error::CycleDetected));
+    let wolf = mapped.add_node(7);
+    assert!(mapped.add_edge(wolf, mapping[&dog], ())?);
+    assert!(mapped.contains_transitive_edge(wolf, mapping[&cat]));
+
+    let (strings, mapping) = dag.map_edges(|src, dst, _| format!("{:?}->{:?}", src, dst));
+    assert_eq!(strings.get_edge_data(mapping[&dog], mapping[&cat]), Some(&format!("{:?}->{:?}", dog, cat)));
+    assert_eq!(strings.edge_count(), dag.edge_count());
+
+    Ok(())
+  }
+
+  #[test]
+  fn clone_topology() -> Result<(), Error> {
+    let (nodes, dag) = get_basic_dag()?;
+    let (dag, node_mapping) = dag.map(|node, _| format!("{:?}", node));
+    let (dag, edge_mapping) = dag.map_edges(|src, dst, _| format!("{:?}->{:?}", src, dst));
+    let nodes = nodes.map(|node| edge_mapping[&node_mapping[&node]]);
+
+    let (topology, mapping): (DAG<(), ()>, _) = dag.clone_topology();
+    assert_eq!(topology.len(), dag.len());
+    assert_eq!(topology.edge_count(), dag.edge_count());
+    for src in nodes {
+      assert_eq!(topology.get_node_data(mapping[&src]), Some(&()));
+      for dst in nodes {
+        let contains = dag.contains_transitive_edge(src, dst);
+        assert_eq!(topology.contains_transitive_edge(mapping[&src], mapping[&dst]), contains);
+        assert_eq!(topology.topo_cmp(mapping[&src], mapping[&dst]), dag.topo_cmp(src, dst));
+        if dag.contains_edge(src, dst) {
+          assert_eq!(topology.get_edge_data(mapping[&src], mapping[&dst]), Some(&()));
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn snapshot_and_restore_order() -> Result<(), Error> {
+    let (nodes, mut dag) = get_basic_dag()?;
+    let [dog, cat, mouse, _, human, gazelle, _] = nodes;
+    let topo_cmps = |dag: &DAG<(), ()>| -> Vec<_> {
+      nodes.iter().flat_map(|a| nodes.iter().map(move |b| dag.topo_cmp(a, b))).collect()
+    };
+    let expected = topo_cmps(&dag);
+    let snapshot = dag.snapshot_order();
+
+    // Speculatively add edges that reorder nodes.
+    assert!(dag.add_edge(gazelle, dog, ())?);
+    assert_ne!(topo_cmps(&dag), expected);
+
+    // Cannot restore while the speculative edges are still present.
+    assert_eq!(dag.restore_order(snapshot.clone()), Err(Error::InvalidTopoOrder));
+
+    // Undo the speculative edges and restore.
+    dag.remove_edge(gazelle, dog);
+    dag.restore_order(snapshot.clone())?;
+    assert_eq!(topo_cmps(&dag), expected);
+    assert_eq!(dag.add_edge(cat, human, ()), Err(Error::CycleDetected));
+    assert!(dag.add_edge(gazelle, mouse, ())?);
+
+    // Cannot restore when nodes do not match.
+    dag.remove_node(gazelle);
+    assert_eq!(dag.restore_order(snapshot.clone()), Err(Error::NodeMissing));
+    dag.add_node(());
+    assert_eq!(dag.restore_order(snapshot), Err(Error::NodeMissing));
+
+    Ok(())
+  }
+
+  #[test]
+  fn edges_between() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], dag) = get_basic_dag()?;
+
+    let predators = HashSet::from([lion, human, dog]);
+    let prey = HashSet::from([cat, mouse, gazelle, grass]);
+    assert_eq!(dag.edges_between(&predators, &prey), vec![(lion, gazelle, &()), (human, cat, &()), (dog, cat, &())]);
+    assert_eq!(dag.edges_between(&prey, &predators), vec![]);
+    assert_eq!(dag.edges_between(&prey, &prey), vec![(cat, mouse, &()), (mouse, grass, &()), (gazelle, grass, &())]);
+    assert_eq!(dag.edges_between(&HashSet::new(), &prey), vec![]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn critical_path() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], dag) = get_basic_dag()?;
+
+    // Uniform costs: the path with the most nodes.
+    assert_eq!(dag.critical_path(|_, _| 1), vec![lion, human, dog, cat, mouse, grass]);
+    // An expensive gazelle outweighs the longer path.
+    let cost = |node| if node == gazelle { 10 } else { 1 };
+    assert_eq!(dag.critical_path(|node, _| cost(node)), vec![lion, gazelle, grass]);
+    // An expensive human makes all paths through it critical, but the longest one has the highest total.
+    let cost = |node| if node == human { 10 } else { 1 };
+    assert_eq!(dag.critical_path(|node, _| cost(node)), vec![lion, human, dog, cat, mouse, grass]);
+    // Zero costs: any single node is critical, and the first root is picked.
+    assert_eq!(dag.critical_path(|_, _| 0), vec![lion]);
+
+    assert_eq!(DAG::<(), ()>::new().critical_path(|_, _| 1), vec![]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn descendants_and_ancestors_bfs() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+
+    // Nodes are grouped by distance from `lion`: levels are non-decreasing, and each node has its shortest distance.
+    let descendants: Vec<_> = dag.descendants_bfs(lion)?.collect();
+    assert_eq!(descendants.len(), dag.reachable_count(lion)?);
+    assert!(descendants.windows(2).all(|w| w[0].0 <= w[1].0));
+    let level_1: HashSet<_> = descendants.iter().filter(|(l, _)| *l == 1).map(|(_, n)| *n).collect();
+    assert_eq!(level_1, HashSet::from([human, gazelle]));
+    let level_2: HashSet<_> = descendants.iter().filter(|(l, _)| *l == 2).map(|(_, n)| *n).collect();
+    assert_eq!(level_2, HashSet::from([dog, cat, grass]));
+    assert_eq!(descendants.last(), Some(&(3, mouse)));
+    assert_eq!(descendants, dag.descendants_within(lion, usize::MAX)?);
+
+    let ancestors: Vec<_> = dag.ancestors_bfs(grass)?.collect();
+    assert!(ancestors.windows(2).all(|w| w[0].0 <= w[1].0));
+    let level_1: HashSet<_> = ancestors.iter().filter(|(l, _)| *l == 1).map(|(_, n)| *n).collect();
+    assert_eq!(level_1, HashSet::from([gazelle, mouse]));
+    let level_2: HashSet<_> = ancestors.iter().filter(|(l, _)| *l == 2).map(|(_, n)| *n).collect();
+    assert_eq!(level_2, HashSet::from([lion, cat]));
+    let level_3: HashSet<_> = ancestors.iter().filter(|(l, _)| *l == 3).map(|(_, n)| *n).collect();
+    assert_eq!(level_3, HashSet::from([human, dog]));
+    assert_eq!(ancestors.len(), 6);
+
+    assert_eq!(dag.descendants_bfs(grass)?.count(), 0);
+    assert_eq!(dag.ancestors_bfs(lion)?.count(), 0);
+
+    dag.remove_node(grass);
+    assert!(dag.descendants_bfs(grass).is_err());
+    assert!(dag.ancestors_bfs(grass).is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn reachable_from_set() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+
+    let reachable = dag.reachable_from_set(&[human, gazelle]);
+    let mut union: HashSet<_> = dag.descendants_unsorted(human)?.map(|(_, n)| n).collect();
+    union.extend(dag.descendants_unsorted(gazelle)?.map(|(_, n)| n));
+    assert_eq!(reachable, union);
+    assert_eq!(reachable, HashSet::from([dog, cat, mouse, grass]));
+
+    assert_eq!(dag.reachable_from_set(&[lion, human]).len(), dag.reachable_count(lion)?);
+    assert!(dag.reachable_from_set(&[]).is_empty());
+    assert!(dag.reachable_from_set(&[grass]).is_empty());
+
+    assert!(dag.is_reachable_from_set(&[human, gazelle], grass));
+    assert!(dag.is_reachable_from_set(&[gazelle, human], mouse));
+    assert!(!dag.is_reachable_from_set(&[human, gazelle], lion));
+    assert!(!dag.is_reachable_from_set(&[human, gazelle], human));
+    assert!(dag.is_reachable_from_set(&[lion, human], human));
+
+    // Missing sources are ignored.
+    dag.remove_node(gazelle);
+    assert_eq!(dag.reachable_from_set(&[human, gazelle]), HashSet::from([dog, cat, mouse, grass]));
+    assert!(!dag.is_reachable_from_set(&[gazelle], grass));
+
+    Ok(())
+  }
+
+  #[test]
+  fn compact() -> Result<(), Error> {
+    let (mut dag, nodes) = DAG::<_, _>::load((1..=6).map(|i| (i * 10, ())), [(0, 1, ()), (1, 3, ()), (2, 5, ())])?;
+
+    // Churn the graph: remove and add nodes, and add edges that reorder nodes.
+    dag.remove_node(nodes[4]);
+    let wolf = dag.add_node(());
+    let sheep = dag.add_node(());
+    dag.add_edge(wolf, nodes[0], ())?;
+    dag.add_edge(nodes[5], sheep, ())?;
+    dag.remove_node(nodes[2]);
+    let eagle = dag.add_node(());
+    dag.add_edge(eagle, wolf, ())?;
+
+    let remaining: Vec<_> = dag.iter_unsorted().map(|(_, node)| node).collect();
+    let topo_cmps: Vec<_> = remaining.iter()
+      .flat_map(|a| remaining.iter().map(|b| dag.topo_cmp(a, b)).collect::<Vec<_>>())
+      .collect();
+
+    dag.compact();
+
+    let mut topo_orders: Vec<_> = dag.iter_unsorted().map(|(topo_order, _)| topo_order).collect();
+    topo_orders.sort_unstable();
+    assert_eq!(topo_orders, (1..=dag.len() as TopoOrder).collect::<Vec<_>>());
+    let compacted_topo_cmps: Vec<_> = remaining.iter()
+      .flat_map(|a| remaining.iter().map(|b| dag.topo_cmp(a, b)).collect::<Vec<_>>())
+      .collect();
+    assert_eq!(compacted_topo_cmps, topo_cmps);
+
+    // New nodes are ordered after the compacted range.
+    let lamb = dag.add_node(());
+    assert_eq!(dag.get_node(lamb).topo_order, dag.len() as TopoOrder);
+    assert!(dag.add_edge(sheep, lamb, ())?);
+    assert!(dag.contains_transitive_edge(eagle, nodes[3]));
+
+    Ok(())
+  }
+
+  #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+  enum Access {
+    Read,
+    Write,
+  }
+  impl EdgeKind for Access {
+    type Kind = Self;
+    fn kind(&self) -> Self { *self }
+  }
+
+  /// Creates a graph with a resource node that is read by `readers` nodes and written by one node.
+  fn get_many_readers_dag(readers: usize) -> (Node, Vec<Node>, Node, DAG<(), Access>) {
+    let mut dag = DAG::new();
+    let resource = dag.add_node(());
+    let reader_nodes: Vec<_> = (0..readers).map(|_| dag.add_node(())).collect();
+    for reader in &reader_nodes {
+      dag.add_edge(reader, resource, Access::Read).unwrap();
+    }
+    let writer = dag.add_node(());
+    dag.add_edge(writer, resource, Access::Write).unwrap();
+    (resource, reader_nodes, writer, dag)
+  }
+
+  #[test]
+  fn get_incoming_edges_of_kind() -> Result<(), Error> {
+    let (resource, readers, writer, mut unindexed) = get_many_readers_dag(1000);
+    let (_, _, _, mut dag) = get_many_readers_dag(1000);
+    dag.index_edge_kinds();
+    assert!(dag.is_edge_kinds_indexed());
+    assert!(!unindexed.is_edge_kinds_indexed());
+
+    let get_kind = |dag: &DAG<(), Access>, kind| -> Vec<Node> {
+      dag.get_incoming_edges_of_kind(resource, kind).map(|(n, _)| *n).collect()
+    };
+    for dag in [&dag, &unindexed] {
+      assert_eq!(get_kind(dag, Access::Write), vec![writer]);
+      assert_eq!(get_kind(dag, Access::Read), readers);
+    }
+
+    // Keep the index up-to-date when removing edges and nodes, and when replacing edge data.
+    for dag in [&mut dag, &mut unindexed] {
+      assert_eq!(dag.remove_edge(readers[0], resource), Some(Access::Read));
+      assert!(dag.remove_node(readers[1]));
+      assert_eq!(dag.replace_edge_data(readers[2], resource, Access::Write), Some(Access::Read));
+      assert_eq!(dag.replace_edge_data(readers[0], resource, Access::Write), None);
+      assert_eq!(dag.remove_outgoing_edges_of_node(writer), Some(vec![(resource, Access::Write)]));
+      assert_eq!(dag.add_edge(resource, readers[3], Access::Write), Err(Error::CycleDetected));
+    }
+    for dag in [&dag, &unindexed] {
+      assert_eq!(get_kind(dag, Access::Write), vec![readers[2]]);
+      assert_eq!(get_kind(dag, Access::Read), readers[3..]);
+      assert_eq!(dag.get_incoming_edges_of_kind(readers[3], Access::Write).count(), 0);
+    }
+
+    // Removing the destination node removes its index entries.
+    assert!(dag.remove_node(resource));
+    assert_eq!(get_kind(&dag, Access::Read), vec![]);
+
+    Ok(())
+  }
+
+  /// Benchmark of getting incoming edges of a kind, with and without index. Run with
+  /// `cargo test --release -p pie_graph -- --ignored --nocapture bench_get_incoming_edges_of_kind`.
+  #[test]
+  #[ignore]
+  fn bench_get_incoming_edges_of_kind() {
+    let (resource, _, writer, unindexed) = get_many_readers_dag(1000);
+    let (_, _, _, mut indexed) = get_many_readers_dag(1000);
+    indexed.index_edge_kinds();
+
+    const ITERATIONS: u32 = 10_000;
+    for (name, dag) in [("unindexed", &unindexed), ("indexed", &indexed)] {
+      let start = std::time::Instant::now();
+      for _ in 0..ITERATIONS {
+        let (node, _) = dag.get_incoming_edges_of_kind(resource, Access::Write).next().unwrap();
+        assert_eq!(*std::hint::black_box(node), writer);
+      }
+      println!("{}: {:?} per query over 1000 readers and 1 writer", name, start.elapsed() / ITERATIONS);
+    }
+  }
+
+  #[test]
+  fn neighbors_and_connected_component() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+
+    let neighbors: HashSet<_> = dag.neighbors(human).map(|(d, n)| (d, *n)).collect();
+    assert_eq!(neighbors, HashSet::from([
+      (Direction::Incoming, lion),
+      (Direction::Outgoing, dog),
+      (Direction::Outgoing, cat),
+    ]));
+    assert_eq!(dag.neighbors(grass).filter(|(d, _)| *d == Direction::Outgoing).count(), 0);
+
+    // All animals (and grass) are connected to `human`, through `lion` in the opposite edge direction.
+    let all = HashSet::from([dog, cat, mouse, lion, human, gazelle, grass]);
+    assert_eq!(dag.connected_component(human), all);
+    assert_eq!(dag.connected_component(grass), all);
+
+    // Removing edges splits the component.
+    dag.remove_edge(lion, human);
+    dag.remove_edge(mouse, grass);
+    assert_eq!(dag.connected_component(human), HashSet::from([dog, cat, mouse, human]));
+    assert_eq!(dag.connected_component(gazelle), HashSet::from([lion, gazelle, grass]));
+
+    // Missing nodes have no neighbors and an empty component.
+    dag.remove_node(mouse);
+    assert_eq!(dag.neighbors(mouse).count(), 0);
+    assert!(dag.connected_component(mouse).is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn concurrent_transitive_edge_queries() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], dag) = get_basic_dag()?;
+    let nodes = [dog, cat, mouse, lion, human, gazelle, grass];
+    let expected: Vec<bool> = nodes.iter()
+      .flat_map(|src| nodes.iter().map(|dst| dag.contains_transitive_edge(src, dst)).collect::<Vec<_>>())
+      .collect();
+
+    let (dag, expected) = (&dag, &expected);
+    std::thread::scope(|scope| {
+      let threads: Vec<_> = (0..8).map(|i| scope.spawn(move || {
+        (0..100).all(|_| {
+          let actual: Vec<bool> = nodes.iter().flat_map(|src| nodes.iter().map(|dst| if i % 2 == 0 {
+            dag.contains_transitive_edge_threadsafe(src, dst)
+          } else {
+            dag.contains_transitive_edge(src, dst)
+          }).collect::<Vec<_>>()).collect();
+          &actual == expected
+        })
+      })).collect();
+      for thread in threads {
+        assert!(thread.join().unwrap());
+      }
+    });
+    assert!(dag.contains_transitive_edge_threadsafe(lion, grass));
+    assert!(!dag.contains_transitive_edge_threadsafe(human, gazelle));
+    assert!(!dag.contains_transitive_edge_threadsafe(human, human));
+
+    Ok(())
+  }
+
+  #[test]
+  fn contract_node() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+    let edge_count = dag.edge_count();
+
+    // Contracting `human` connects `lion` directly to `dog` and `cat`.
+    assert!(dag.contract_node(human, |_, _| ()));
+    assert!(!dag.contains_node(human));
+    assert!(dag.contains_edge(lion, dog));
+    assert!(dag.contains_edge(lion, cat));
+    assert!(dag.contains_edge(lion, gazelle));
+    assert!(dag.contains_edge(dog, cat));
+    // Removed 3 edges from and to `human`, added 2 bypassing edges.
+    assert_eq!(dag.edge_count(), edge_count - 1);
+    assert_eq!(dag.topo_cmp(lion, dog), Ordering::Less);
+    assert_eq!(dag.add_edge(mouse, lion, ()), Err(Error::CycleDetected));
+
+    // Contracting `dog` keeps the existing edge from `lion` to `cat`.
+    assert!(dag.contract_node(dog, |_, _| ()));
+    assert!(dag.contains_edge(lion, cat));
+    assert_eq!(dag.edge_count(), edge_count - 3);
+
+    // Contracting a node without parents removes it.
+    assert!(dag.contract_node(lion, |_, _| ()));
+    assert_eq!(dag.get_incoming_edge_nodes(cat).count(), 0);
+    assert!(dag.contains_edge(gazelle, grass));
+
+    // Missing nodes are not contracted.
+    assert!(!dag.contract_node(human, |_, _| ()));
+
+    Ok(())
+  }
+
+  /// Adds an edge from `src` to `dst` without cycle detection and reordering, for creating invalid DAGs in tests.
+  fn add_edge_unchecked(dag: &mut DAG<(), ()>, src: Node, dst: Node) {
+    dag.node_info[src.0].children.insert(dst);
+    dag.node_info[dst.0].parents.insert(src);
+    dag.edge_data.insert((src, dst), ());
+  }
+
+  #[test]
+  fn find_cycles() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+    assert!(dag.find_cycles().is_empty());
+
+    // Two independent cycles: `dog → cat → mouse → dog`, and `gazelle → grass → gazelle`.
+    add_edge_unchecked(&mut dag, mouse, dog);
+    add_edge_unchecked(&mut dag, grass, gazelle);
+    // Normalize each cycle to start at its least node, as the start of each cycle is unspecified.
+    let normalize = |cycles: Vec<Vec<Node>>| {
+      let mut cycles: Vec<_> = cycles.into_iter().map(|mut cycle| {
+        let min = cycle.iter().enumerate().min_by_key(|(_, n)| **n).map(|(i, _)| i).unwrap();
+        cycle.rotate_left(min);
+        cycle
+      }).collect();
+      cycles.sort();
+      cycles
+    };
+    let mut expected = vec![vec![dog, cat, mouse], vec![gazelle, grass]];
+    expected.sort();
+    assert_eq!(normalize(dag.find_cycles()), expected);
+
+    // Multiple cycles in one strongly connected component: `human → cat → mouse → human` is added, and
+    // `human → dog → cat → mouse → human` is created through existing edges.
+    add_edge_unchecked(&mut dag, mouse, human);
+    let cycles = normalize(dag.find_cycles());
+    assert_eq!(cycles.len(), 4);
+    assert!(cycles.contains(&normalize(vec![vec![human, cat, mouse]])[0]));
+    assert!(cycles.contains(&normalize(vec![vec![human, dog, cat, mouse]])[0]));
+    assert!(cycles.iter().all(|c| !c.contains(&lion)));
+
+    Ok(())
+  }
+
+  #[cfg(feature = "reorder_stats")]
+  #[test]
+  fn reorder_stats() -> Result<(), Error> {
+    let mut dag = DAG::<(), ()>::new();
+    let cat = dag.add_node(());
+    let mouse = dag.add_node(());
+    let dog = dag.add_node(());
+    let human = dag.add_node(());
+
+    // Edges along the topological order do not change it: zero cost.
+    dag.add_edge(cat, mouse, ())?;
+    assert_eq!(dag.last_reorder_cost(), 0);
+    dag.add_edge(dog, human, ())?;
+    assert_eq!(dag.last_reorder_cost(), 0);
+    assert_eq!(dag.reorder_stats(), ReorderStats::default());
+
+    // Edge against the topological order: `cat` and `mouse` are visited forward, and `human` and `dog` backward.
+    dag.add_edge(human, cat, ())?;
+    assert_eq!(dag.last_reorder_cost(), 4);
+    // Existing edge: zero cost.
+    dag.add_edge(human, cat, ())?;
+    assert_eq!(dag.last_reorder_cost(), 0);
+    // Edge creating a cycle: rejected, but with a cost.
+    assert_eq!(dag.add_edge(mouse, dog, ()), Err(Error::CycleDetected));
+    assert!(dag.last_reorder_cost() > 0);
+
+    let stats = dag.reorder_stats();
+    assert_eq!(stats.reorder_count, 2);
+    assert_eq!(stats.max_reorder_cost, 4);
+    assert_eq!(stats.total_reorder_cost, 4 + stats.last_reorder_cost as u64);
+
+    Ok(())
+  }
+
+  #[test]
+  fn structurally_eq() -> Result<(), Error> {
+    let get_dag = |reverse: bool| -> Result<DAG<&str, u32>, Error> {
+      let mut names = vec!["dog", "cat", "mouse", "lion", "human", "gazelle", "grass"];
+      if reverse {
+        names.reverse();
+      }
+      let mut dag = DAG::new();
+      let nodes: HashMap<_, _> = names.into_iter().map(|n| (n, dag.add_node(n))).collect();
+      let mut edges = vec![
+        ("lion", "human", 1),
+        ("lion", "gazelle", 2),
+        ("human", "dog", 3),
+        ("human", "cat", 4),
+        ("dog", "cat", 5),
+        ("cat", "mouse", 6),
+        ("gazelle", "grass", 7),
+        ("mouse", "grass", 8),
+      ];
+      if reverse {
+        edges.reverse();
+      }
+      for (src, dst, data) in edges {
+        dag.add_edge(nodes[src], nodes[dst], data)?;
+      }
+      Ok(dag)
+    };
+
+    let dag = get_dag(false)?;
+    let mut reversed = get_dag(true)?;
+    assert!(dag.structurally_eq(&dag));
+    assert!(dag.structurally_eq(&reversed));
+    assert!(reversed.structurally_eq(&dag));
+
+    // Different edge data.
+    *reversed.edge_data.values_mut().find(|d| **d == 8).unwrap() = 9;
+    assert!(!dag.structurally_eq(&reversed));
+    // Different edges.
+    let mut reversed = get_dag(true)?;
+    let [mouse, grass] = ["mouse", "grass"].map(|name| reversed.node_info.iter()
+      .find(|(_, n)| n.data == name).map(|(k, _)| Node(k)).unwrap());
+    reversed.remove_edge(mouse, grass);
+    assert!(!dag.structurally_eq(&reversed));
+    // Different node data.
+    let mut reversed = get_dag(true)?;
+    reversed.add_node("dog");
+    let mut other = get_dag(false)?;
+    other.add_node("cat");
+    assert!(!reversed.structurally_eq(&other));
+
+    Ok(())
+  }
+
+  #[test]
+  fn iter_nodes_topo() -> Result<(), Error> {
+    let (nodes, dag) = get_basic_dag()?;
+
+    let sorted: Vec<_> = dag.iter_nodes_topo().map(|(node, _)| node).collect();
+    assert_eq!(sorted.len(), nodes.len());
+    let position = |node: &Node| sorted.iter().position(|n| n == node).unwrap();
+    for src in &nodes {
+      for dst in dag.get_outgoing_edge_nodes(src) {
+        assert!(position(src) < position(dst), "{:?} must come before {:?}", src, dst);
+      }
+    }
+
+    Ok(())
+  }
+
   #[test]
   fn add_nodes_basic() {
     let mut dag = DAG::<_, ()>::new();
@@ -1289,6 +3815,48 @@ mod tests {
     assert_eq!(animal_order, vec![dog, cat]);
   }
 
+  #[test]
+  fn nodes_in_topo_range() -> Result<(), Error> {
+    let ([dog, cat, _, _, human, _, _], dag) = get_basic_dag()?;
+
+    let mut expected: Vec<_> = dag.iter_unsorted().filter(|(topo_order, _)| (2..=4).contains(topo_order)).collect();
+    expected.sort_unstable();
+    let expected: Vec<_> = expected.into_iter().map(|(_, node)| node).collect();
+    assert_eq!(expected, vec![human, dog, cat]);
+    assert_eq!(dag.nodes_in_topo_range(2..=4), expected);
+    assert_eq!(dag.nodes_in_topo_range(0..=7).len(), dag.len());
+    assert!(dag.nodes_in_topo_range(8..=10).is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn tags() -> Result<(), Error> {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], mut dag) = get_basic_dag()?;
+    assert_eq!(dag.nodes_with_tag("mammal").count(), 0);
+
+    for node in [dog, cat, mouse, lion, human, gazelle] {
+      assert!(dag.add_tag(node, "mammal"));
+    }
+    for node in [lion, human] {
+      assert!(dag.add_tag(node, "apex"));
+    }
+    assert!(!dag.add_tag(lion, "apex"));
+    assert!(dag.tags(grass).is_empty());
+    assert_eq!(dag.tags(lion), &["mammal", "apex"]);
+    assert_eq!(dag.nodes_with_tag("apex").collect::<HashSet<_>>(), HashSet::from([lion, human]));
+    assert_eq!(dag.nodes_with_tag("mammal").count(), 6);
+    assert_eq!(dag.nodes_with_tag("plant").count(), 0);
+
+    assert!(dag.remove_node(lion));
+    assert!(dag.tags(lion).is_empty());
+    assert!(!dag.add_tag(lion, "apex"));
+    assert_eq!(dag.nodes_with_tag("apex").collect::<Vec<_>>(), vec![human]);
+    assert_eq!(dag.nodes_with_tag("mammal").count(), 5);
+
+    Ok(())
+  }
+
   #[test]
   fn unordered_iter() {
     let mut dag = DAG::new();
@@ -1314,6 +3882,57 @@ mod tests {
     assert_eq!(pairs, expected_pairs);
   }
 
+  #[test]
+  fn contains_path_through() {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], dag) = get_basic_dag().unwrap();
+
+    assert!(dag.contains_path_through(lion, human, cat));
+    assert!(dag.contains_path_through(human, dog, cat));
+    assert!(dag.contains_path_through(lion, mouse, grass));
+    assert!(!dag.contains_path_through(lion, gazelle, cat));
+    assert!(!dag.contains_path_through(human, gazelle, grass));
+
+    // Degenerate cases where nodes are equal.
+    assert!(dag.contains_path_through(lion, lion, cat));
+    assert!(dag.contains_path_through(lion, cat, cat));
+    assert!(!dag.contains_path_through(cat, lion, lion));
+    assert!(!dag.contains_path_through(cat, cat, cat));
+    assert!(!dag.contains_path_through(lion, human, lion));
+  }
+
+  #[test]
+  fn reserve() {
+    let mut dag = DAG::with_capacity(8, 8);
+    dag.reserve(64, 128);
+
+    let nodes: Vec<_> = (0..64).map(|i| dag.add_node(i)).collect();
+    for (i, src) in nodes.iter().enumerate() {
+      for dst in nodes.iter().skip(i + 1).take(2) {
+        assert!(dag.add_edge(src, dst, ()).unwrap());
+      }
+    }
+
+    assert_eq!(dag.len(), 64);
+    assert_eq!(dag.edge_count(), 125);
+  }
+
+  #[test]
+  fn longest_path_levels() {
+    let ([dog, cat, mouse, lion, human, gazelle, grass], dag) = get_basic_dag().unwrap();
+
+    let levels = dag.longest_path_levels();
+    assert_eq!(levels.len(), 7);
+    assert_eq!(levels[&lion], 0);
+    assert_eq!(levels[&human], 1);
+    assert_eq!(levels[&gazelle], 1);
+    assert_eq!(levels[&dog], 2);
+    assert_eq!(levels[&cat], 3);
+    assert_eq!(levels[&mouse], 4);
+    // `grass` is reachable via `gazelle` at level 2, but its longest path is via `mouse`.
+    assert_eq!(levels[&grass], 5);
+    assert_eq!(levels.values().max(), Some(&5));
+  }
+
   #[test]
   fn topo_cmp() {
     use std::cmp::Ordering::*;
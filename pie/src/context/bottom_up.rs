@@ -2,13 +2,15 @@ use std::collections::hash_map::RandomState;
 use std::collections::HashSet;
 use std::error::Error;
 use std::hash::BuildHasher;
+#[cfg(feature = "panic_fallback")]
+use std::panic::{AssertUnwindSafe, catch_unwind, RefUnwindSafe};
 
-use crate::{Context, OutputChecker, Resource, ResourceChecker, Task};
-use crate::context::SessionExt;
-use crate::dependency::ResourceDependencyObj;
+use crate::{BuildViolation, Context, Key, OutputChecker, Resource, ResourceChecker, Task, Value, WriteOutcome};
+use crate::context::{ChangedResources, SessionExt};
+use crate::dependency::{CheckError, ResourceDependencyObj};
 use crate::pie::{SessionInternal, Tracking};
 use crate::store::{Store, TaskNode};
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::trait_object::{KeyObj, ResourceCheckerObj, TaskCheckerObj, TaskKeyObj, ValueObj};
 use crate::trait_object::collection::TypeToAnyMap;
 use crate::trait_object::task::TaskObj;
 
@@ -34,15 +36,20 @@ impl<'p, 's> BottomUpContext<'p, 's> {
   pub fn schedule_tasks_affected_by(&mut self, resource: &dyn KeyObj) {
     let track_end = self.session.tracker.schedule_affected_by_resource(resource);
     let node = self.session.store.get_or_create_resource_node(resource);
+    let written = self.session.written_resources.contains(&node);
     for (task_node, dependency) in self.session.store.get_read_and_write_dependencies_to_resource(&node) {
+      if !written && !dependency.checks_external_changes() {
+        continue; // Resource can only change by being written to, which did not happen this session: skip checking.
+      }
       let task = self.session.store.get_task(&task_node);
       Self::try_schedule_task_by_resource_dependency(
-        task.as_key_obj(),
+        task.as_task_key_obj(),
         task_node,
         dependency,
         &mut self.session.resource_state,
         &mut self.session.tracker,
         &mut self.session.dependency_check_errors,
+        &mut self.session.changed_resources,
         &mut self.scheduled,
         &self.executing,
       );
@@ -71,12 +78,13 @@ impl<'p, 's> BottomUpContext<'p, 's> {
       for (reading_task_node, dependency) in self.session.store.get_read_dependencies_to_resource(&written_resource_node) {
         let reading_task = self.session.store.get_task(&reading_task_node);
         Self::try_schedule_task_by_resource_dependency(
-          reading_task.as_key_obj(),
+          reading_task.as_task_key_obj(),
           reading_task_node,
           dependency,
           &mut self.session.resource_state,
           &mut self.session.tracker,
           &mut self.session.dependency_check_errors,
+          &mut self.session.changed_resources,
           &mut self.scheduled,
           &self.executing,
         );
@@ -85,7 +93,7 @@ impl<'p, 's> BottomUpContext<'p, 's> {
     }
 
     // Schedule tasks affected by task `node`'s output.
-    let track_end = self.session.tracker.schedule_affected_by_task(task.as_ref().as_key_obj());
+    let track_end = self.session.tracker.schedule_affected_by_task(task.as_ref().as_task_key_obj());
     // Consider tasks that require `node`.
     for (requiring_task_node, dependency) in self.session.store.get_require_dependencies_to_task(&node) {
       // TODO: skip when task is already consistent?
@@ -93,7 +101,7 @@ impl<'p, 's> BottomUpContext<'p, 's> {
       if self.executing.contains(&requiring_task_node) {
         continue; // Don't schedule tasks that are already executing.
       }
-      let requiring_task = self.session.store.get_task(&requiring_task_node).as_key_obj();
+      let requiring_task = self.session.store.get_task(&requiring_task_node).as_task_key_obj();
       // Note: use `output.as_ref()` instead of `&output`, because `&output` results in a `&Box<dyn ValueObj>` which
       // also implements `dyn ValueObj`, but cannot be downcasted to the concrete unboxed type!
       if !dependency.is_consistent_bottom_up(output.as_ref(), requiring_task, &mut self.session.tracker) {
@@ -112,12 +120,13 @@ impl<'p, 's> BottomUpContext<'p, 's> {
   ///
   /// Note: passing in borrows explicitly instead of a mutable borrow of `self` to make borrows work.
   fn try_schedule_task_by_resource_dependency(
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     reading_task_node: TaskNode,
     dependency: &dyn ResourceDependencyObj,
     resource_state: &mut TypeToAnyMap,
     tracker: &mut Tracking,
-    dependency_check_errors: &mut Vec<Box<dyn Error>>,
+    dependency_check_errors: &mut Vec<CheckError>,
+    changed_resources: &mut ChangedResources,
     scheduled: &mut Queue,
     executing: &HashSet<TaskNode>,
   ) {
@@ -134,6 +143,7 @@ impl<'p, 's> BottomUpContext<'p, 's> {
         scheduled.add(reading_task_node);
       }
       Ok(false) => {
+        changed_resources.add(dependency.resource());
         tracker.schedule_task(reading_task);
         scheduled.add(reading_task_node);
       }
@@ -146,11 +156,16 @@ impl<'p, 's> BottomUpContext<'p, 's> {
   fn execute<T: Task>(&mut self, task: &T, node: TaskNode) -> T::Output {
     self.session.store.reset_task(&node);
     let previous_executing_task = self.session.current_executing_task.replace(node);
+    if let Err(message) = task.precondition(self) {
+      self.session.violations.raise(BuildViolation::PreconditionFailed { task: Box::new(task.clone()), message });
+    }
     let track_end = self.session.tracker.execute(task);
+    self.session.statistics.total_executions += 1;
     let output = task.execute(self);
     track_end(&mut self.session.tracker, &output);
     self.session.current_executing_task = previous_executing_task;
     self.session.store.set_task_output(&node, Box::new(output.clone()));
+    self.session.executed_tasks.push(node);
     output
   }
 
@@ -160,13 +175,19 @@ impl<'p, 's> BottomUpContext<'p, 's> {
   fn execute_obj(&mut self, task: &dyn TaskObj, node: TaskNode) -> Box<dyn ValueObj> {
     self.session.store.reset_task(&node);
     let previous_executing_task = self.session.current_executing_task.replace(node);
-    let track_end = self.session.tracker.execute(task.as_key_obj());
+    if let Err(message) = task.precondition_bottom_up(self) {
+      let task = task.as_key_obj().to_owned();
+      self.session.violations.raise(BuildViolation::PreconditionFailed { task, message });
+    }
+    let track_end = self.session.tracker.execute(task.as_task_key_obj());
+    self.session.statistics.total_executions += 1;
     let output = task.execute_bottom_up(self);
     // Note: use `output.as_ref()` instead of `&output`, because `&output` results in a `&Box<dyn ValueObj>` which also
     // implements `dyn ValueObj`, but cannot be downcasted to the concrete unboxed type!
     track_end(&mut self.session.tracker, output.as_ref());
     self.session.current_executing_task = previous_executing_task;
     self.session.store.set_task_output(&node, output.clone());
+    self.session.executed_tasks.push(node);
     output
   }
 
@@ -194,6 +215,7 @@ impl<'p, 's> BottomUpContext<'p, 's> {
   #[inline]
   fn make_task_consistent<T: Task>(&mut self, task: &T, node: TaskNode) -> T::Output {
     if self.session.consistent.contains(&node) { // Task is already consistent: return its output.
+      self.session.statistics.total_cache_hits += 1;
       return self.session.store.get_task_output(&node)
         .expect("BUG: no task output for already consistent task")
         .as_any().downcast_ref::<T::Output>()
@@ -227,6 +249,7 @@ impl<'p, 's> BottomUpContext<'p, 's> {
       //    the task and all its (indirect) dependencies consistent.
       //
       // All case cannot occur, thus the task cannot be affected. Therefore, we don't have to execute the task.
+      self.session.statistics.total_cache_hits += 1;
       let output = self.session.store.get_task_output(&node);
 
       output.expect("BUG: no task output for unaffected task")
@@ -244,18 +267,125 @@ impl<'p, 's> Context for BottomUpContext<'p, 's> {
     let track_end = self.session.tracker.require(task, &checker);
 
     let dst = self.session.store.get_or_create_task_node(task);
+    if let Some(output) = self.session.require_fixpoint_cycle::<T>(&dst) {
+      let stamp = checker.stamp(&output);
+      track_end(&mut self.session.tracker, &stamp, &output);
+      return output;
+    }
     self.session.reserve_require_dependency(&dst, task);
 
-    let output = self.make_task_consistent(task, dst);
+    let intercepted_output = self.session.intercept_require(task);
+    let is_intercepted = intercepted_output.is_some();
+    let output = match intercepted_output {
+      Some(output) => output,
+      None => self.make_task_consistent(task, dst),
+    };
     let stamp = checker.stamp(&output);
     track_end(&mut self.session.tracker, &stamp, &output);
 
     self.session.update_require_dependency(&dst, task, checker, stamp);
 
     // Note: make_task_consistent does not insert into self.session.consistent, so do that here.
-    self.session.consistent.insert(dst);
+    if !is_intercepted {
+      self.session.consistent.insert(dst);
+    }
+    output
+  }
+  #[cfg(feature = "panic_fallback")]
+  fn require_with_fallback<T, H>(&mut self, task: &T, checker: H, fallback: T::Output) -> T::Output where
+    T: Task + RefUnwindSafe,
+    H: OutputChecker<T::Output>,
+  {
+    let track_end = self.session.tracker.require(task, &checker);
+
+    let dst = self.session.store.get_or_create_task_node(task);
+    self.session.reserve_require_dependency(&dst, task);
+
+    let (previous_executing_task, previous_depth) = (self.session.current_executing_task, self.session.depth);
+    let previous_fixpoints = self.session.fixpoints.len();
+    let result = catch_unwind(AssertUnwindSafe(|| match self.session.intercept_require(task) {
+      Some(output) => (output, true),
+      None => (self.make_task_consistent(task, dst), false),
+    }));
+    match result {
+      Ok((output, is_intercepted)) => {
+        let stamp = checker.stamp(&output);
+        track_end(&mut self.session.tracker, &stamp, &output);
+        self.session.update_require_dependency(&dst, task, checker, stamp);
+        // Note: make_task_consistent does not insert into self.session.consistent, so do that here.
+        if !is_intercepted {
+          self.session.consistent.insert(dst);
+        }
+        output
+      }
+      Err(payload) => {
+        self.session.recover_require_panic(
+          payload,
+          &dst,
+          task,
+          previous_executing_task,
+          previous_depth,
+          previous_fixpoints,
+        );
+        let stamp = checker.stamp(&fallback);
+        track_end(&mut self.session.tracker, &stamp, &fallback);
+        fallback
+      }
+    }
+  }
+  fn require_fixpoint<T, H>(&mut self, task: &T, checker: H, initial: T::Output, max_iters: usize) -> T::Output where
+    T: Task,
+    H: OutputChecker<T::Output>,
+  {
+    let track_end = self.session.tracker.require(task, &checker);
+
+    let dst = self.session.store.get_or_create_task_node(task);
+    if let Some(output) = self.session.require_fixpoint_cycle::<T>(&dst) {
+      let stamp = checker.stamp(&output);
+      track_end(&mut self.session.tracker, &stamp, &output);
+      return output;
+    }
+    self.session.reserve_require_dependency(&dst, task);
+
+    let intercepted_output = self.session.intercept_require(task);
+    let is_intercepted = intercepted_output.is_some();
+    let output = match intercepted_output {
+      Some(output) => output,
+      None => {
+        self.session.push_fixpoint::<T>(dst, initial.clone());
+        let mut iteration = 1;
+        loop {
+          let output = self.make_task_consistent(task, dst);
+          if !self.session.next_fixpoint_iteration::<T, H>(&checker, &output, iteration, max_iters) {
+            break output;
+          }
+          iteration += 1;
+        }
+      }
+    };
+    let stamp = checker.stamp(&output);
+    track_end(&mut self.session.tracker, &stamp, &output);
+
+    self.session.update_fixpoint_dependency(&dst, task, checker, stamp, initial, max_iters);
+
+    // Note: make_task_consistent does not insert into self.session.consistent, so do that here.
+    if !is_intercepted {
+      self.session.consistent.insert(dst);
+    }
     output
   }
+  #[inline]
+  fn require_dyn(&mut self, task: &dyn KeyObj, checker: Box<dyn TaskCheckerObj>) -> Box<dyn ValueObj> {
+    checker.require_bottom_up(self, task)
+  }
+  #[inline]
+  fn peek_output<T: Task>(&self, task: &T) -> Option<T::Output> {
+    self.session.peek_output(task)
+  }
+  #[inline]
+  fn memo_local<K: Key, V: Value>(&mut self, key: K, compute: impl FnOnce() -> V) -> V {
+    self.session.memo_local(key, compute)
+  }
 
   #[inline]
   fn read<T, R, H>(&mut self, resource: &T, checker: H) -> Result<R::Reader<'_>, H::Error> where
@@ -274,6 +404,15 @@ impl<'p, 's> Context for BottomUpContext<'p, 's> {
   {
     self.session.write(resource, checker, write_fn)
   }
+  #[inline]
+  fn try_write<T, R, H, F>(&mut self, resource: &T, checker: H, write_fn: F) -> Result<WriteOutcome, H::Error> where
+    T: ToOwned<Owned=R>,
+    R: Resource,
+    H: ResourceChecker<R>,
+    F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>
+  {
+    self.session.try_write(resource, checker, write_fn)
+  }
 
   #[inline]
   fn create_writer<'r, R: Resource>(&'r mut self, resource: &'r R) -> Result<R::Writer<'r>, R::Error> {
@@ -287,6 +426,22 @@ impl<'p, 's> Context for BottomUpContext<'p, 's> {
   {
     self.session.written_to(resource, checker)
   }
+  #[inline]
+  fn written_to_dyn(
+    &mut self,
+    resource: &dyn KeyObj,
+    checker: Box<dyn ResourceCheckerObj>,
+  ) -> Result<(), Box<dyn Error>> {
+    checker.written_to_obj(self.session, resource)
+  }
+
+  #[inline]
+  fn scope<R>(&mut self, label: &'static str, f: impl FnOnce(&mut Self) -> R) -> R {
+    let track_end = self.session.tracker.scope(label);
+    let output = f(self);
+    track_end(&mut self.session.tracker);
+    output
+  }
 }
 
 
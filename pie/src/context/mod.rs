@@ -1,13 +1,28 @@
-use crate::{OutputChecker, Resource, ResourceChecker, Task};
-use crate::dependency::{Dependency, ResourceDependency, TaskDependency};
+#[cfg(feature = "panic_fallback")]
+use std::any::Any;
+use std::collections::HashSet;
+use std::mem;
+use std::panic::resume_unwind;
+use std::thread;
+use std::time::Duration;
+
+use crate::{BuildViolation, ErrorKind, Key, OutputChecker, Resource, ResourceChecker, ResourceState, RetryPolicy, Task,
+  Value, WriteOutcome};
+#[cfg(feature = "panic_fallback")]
+use crate::dependency::PanickedTaskDependency;
+use crate::dependency::{Dependency, FixpointTaskDependency, ResourceDependency, TaskDependency};
 use crate::pie::SessionInternal;
 use crate::store::{ResourceNode, TaskNode};
+use crate::trait_object::{KeyObj, ValueObj};
 
 pub mod top_down;
 pub mod bottom_up;
 
 /// Extension trait on [`SessionInternal`] for usage in [`Context`] implementations.
 pub trait SessionExt {
+  fn peek_output<T: Task>(&self, task: &T) -> Option<T::Output>;
+  fn memo_local<K: Key, V: Value>(&mut self, key: K, compute: impl FnOnce() -> V) -> V;
+
   fn read<T, R, H>(&mut self, resource: &T, checker: H) -> Result<R::Reader<'_>, H::Error> where
     T: ToOwned<Owned=R>,
     R: Resource,
@@ -17,6 +32,11 @@ pub trait SessionExt {
     R: Resource,
     H: ResourceChecker<R>,
     F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>;
+  fn try_write<T, R, H, F>(&mut self, resource: &T, checker: H, write_fn: F) -> Result<WriteOutcome, H::Error> where
+    T: ToOwned<Owned=R>,
+    R: Resource,
+    H: ResourceChecker<R>,
+    F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>;
 
   fn create_writer<'r, R: Resource>(&'r mut self, resource: &'r R) -> Result<R::Writer<'r>, R::Error>;
   fn written_to<T, R, H>(&mut self, resource: &T, checker: H) -> Result<(), H::Error> where
@@ -24,35 +44,83 @@ pub trait SessionExt {
     R: Resource,
     H: ResourceChecker<R>;
 
+  fn intercept_require<T: Task>(&mut self, task: &T) -> Option<T::Output>;
   fn reserve_require_dependency<T: Task>(&mut self, dst: &TaskNode, task: &T);
   fn update_require_dependency<T: Task, H: OutputChecker<T::Output>>(&mut self, dst: &TaskNode, task: &T, checker: H, stamp: H::Stamp);
+
+  fn require_fixpoint_cycle<T: Task>(&mut self, dst: &TaskNode) -> Option<T::Output>;
+  fn push_fixpoint<T: Task>(&mut self, node: TaskNode, initial: T::Output);
+  fn next_fixpoint_iteration<T: Task, H: OutputChecker<T::Output>>(
+    &mut self,
+    checker: &H,
+    output: &T::Output,
+    iteration: usize,
+    max_iters: usize,
+  ) -> bool;
+  fn update_fixpoint_dependency<T: Task, H: OutputChecker<T::Output>>(
+    &mut self,
+    dst: &TaskNode,
+    task: &T,
+    checker: H,
+    stamp: H::Stamp,
+    initial: T::Output,
+    max_iters: usize,
+  );
+  #[cfg(feature = "panic_fallback")]
+  fn recover_require_panic<T: Task>(
+    &mut self,
+    payload: Box<dyn Any + Send>,
+    dst: &TaskNode,
+    task: &T,
+    previous_executing_task: Option<TaskNode>,
+    previous_depth: usize,
+    previous_fixpoints: usize,
+  );
 }
 
 impl SessionExt for SessionInternal<'_> {
+  #[inline]
+  fn peek_output<T: Task>(&self, task: &T) -> Option<T::Output> {
+    let node = self.store.get_task_node(task)?;
+    self.store.get_task_output(&node)
+      .map(|o| o.as_any().downcast_ref::<T::Output>().expect("BUG: non-matching task output type").clone())
+  }
+  #[inline]
+  fn memo_local<K: Key, V: Value>(&mut self, key: K, compute: impl FnOnce() -> V) -> V {
+    if let Some(value) = self.memo.get(&key as &dyn KeyObj).and_then(|v| v.as_any().downcast_ref::<V>()) {
+      return value.clone();
+    }
+    let value = compute();
+    self.memo.insert(Box::new(key), Box::new(value.clone()));
+    value
+  }
+
   fn read<T, R, H>(&mut self, resource: &T, checker: H) -> Result<R::Reader<'_>, H::Error> where
     T: ToOwned<Owned=R>,
     R: Resource,
     H: ResourceChecker<R>,
   {
     let resource = resource.to_owned();
-    let mut reader = resource.read(self.resource_state)
-      .map_err(|e| checker.wrap_error(e))?;
+    let mut reader = read_with_retry(&resource, &checker, self.resource_state, &self.resource_retry)?;
     if let Some(current_executing_task_node) = &self.current_executing_task {
       let track_end = self.tracker.read(&resource, &checker);
       let dst = self.store.get_or_create_resource_node(&resource);
       if let Some(writer_node) = self.store.get_task_writing_to_resource(&dst) {
-        if !self.store.contains_transitive_task_dependency(current_executing_task_node, &writer_node) {
-          let current_executing_task = self.store.get_task(current_executing_task_node);
-          let writer_task = self.store.get_task(&writer_node);
-          panic!("Hidden dependency; resource '{:?}' is read by the current executing task '{:?}' without a dependency \
-                  to the task that writes to it: {:?}", resource, current_executing_task, writer_task);
+        let is_hidden = writer_node != *current_executing_task_node &&
+          !self.store.contains_transitive_task_dependency(current_executing_task_node, &writer_node);
+        if is_hidden {
+          self.violations.raise(BuildViolation::HiddenDependency {
+            resource: Box::new(resource.clone()),
+            reading_task: self.store.get_task(current_executing_task_node).as_key_obj().to_owned(),
+            writing_task: self.store.get_task(&writer_node).as_key_obj().to_owned(),
+          });
         }
       }
       let stamp = checker.stamp_reader(&resource, &mut reader)?;
       track_end(&mut self.tracker, &stamp);
       let resource_dependency = ResourceDependency::new(resource, checker, stamp);
       let dependency = Dependency::from_read(resource_dependency);
-      let _ = self.store.add_dependency(current_executing_task_node, &dst, dependency);
+      self.store.add_resource_dependency(current_executing_task_node, &dst, dependency);
     };
     Ok(reader)
   }
@@ -68,14 +136,15 @@ impl SessionExt for SessionInternal<'_> {
       // Validate write before actually writing to the resource, primarily to avoid lifetime issues.
       self.tracker.write_start(&resource, &checker);
       let dst = self.store.get_or_create_resource_node(&resource);
-      validate_write(self, &resource, current_executing_task_node, &dst);
+      if let Err(violation) = validate_write(self, &resource, current_executing_task_node, &dst) {
+        self.violations.raise(violation);
+      }
       Some((current_executing_task_node, dst))
     } else {
       None
     };
 
-    let mut writer = resource.write(self.resource_state)
-      .map_err(|e| checker.wrap_error(e))?;
+    let mut writer = write_with_retry(&resource, &checker, self.resource_state, &self.resource_retry)?;
     write_fn(&mut writer)
       .map_err(|e| checker.wrap_error(e))?;
 
@@ -84,12 +153,30 @@ impl SessionExt for SessionInternal<'_> {
       self.tracker.write_end(&resource, &checker, &stamp);
       let resource_dependency = ResourceDependency::new(resource, checker, stamp);
       let dependency = Dependency::from_write(resource_dependency);
-      let _ = self.store.add_dependency(current_executing_task_node, &dst, dependency);
+      self.store.add_resource_dependency(current_executing_task_node, &dst, dependency);
+      self.written_resources.insert(dst);
     }
     Ok(())
   }
 
   #[inline]
+  fn try_write<T, R, H, F>(&mut self, resource: &T, checker: H, write_fn: F) -> Result<WriteOutcome, H::Error> where
+    T: ToOwned<Owned=R>,
+    R: Resource,
+    H: ResourceChecker<R>,
+    F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>,
+  {
+    let resource = resource.to_owned();
+    let stamp = checker.stamp(&resource, self.resource_state)?;
+    self.write(&resource, checker.clone(), write_fn)?;
+    let outcome = if checker.check(&resource, self.resource_state, &stamp)?.is_some() {
+      WriteOutcome::Changed
+    } else {
+      WriteOutcome::Unchanged
+    };
+    Ok(outcome)
+  }
+
   fn create_writer<'r, R: Resource>(&'r mut self, resource: &'r R) -> Result<R::Writer<'r>, R::Error> {
     resource.write(self.resource_state)
   }
@@ -103,24 +190,37 @@ impl SessionExt for SessionInternal<'_> {
     if let Some(current_executing_task_node) = &self.current_executing_task {
       let track_end = self.tracker.write(&resource, &checker);
       let dst = self.store.get_or_create_resource_node(&resource);
-      validate_write(self, &resource, current_executing_task_node, &dst);
+      if let Err(violation) = validate_write(self, &resource, current_executing_task_node, &dst) {
+        self.violations.raise(violation);
+      }
       let stamp = checker.stamp(&resource, self.resource_state)?;
       track_end(&mut self.tracker, &stamp);
       let resource_dependency = ResourceDependency::new(resource, checker, stamp);
       let dependency = Dependency::from_write(resource_dependency);
-      let _ = self.store.add_dependency(current_executing_task_node, &dst, dependency);
+      self.store.add_resource_dependency(current_executing_task_node, &dst, dependency);
+      self.written_resources.insert(dst);
     };
     Ok(())
   }
 
+  fn intercept_require<T: Task>(&mut self, task: &T) -> Option<T::Output> {
+    let interceptor = self.require_interceptor.as_mut()?;
+    let output = interceptor(task as &dyn KeyObj)?;
+    let output = output.as_any().downcast_ref::<T::Output>()
+      .unwrap_or_else(|| panic!("Require interceptor returned an output of a non-matching type for task '{:?}'", task))
+      .clone();
+    Some(output)
+  }
+
   fn reserve_require_dependency<T: Task>(&mut self, dst: &TaskNode, task: &T) {
     if let Some(src) = &self.current_executing_task {
       // Before making the task consistent, first reserve a dependency in the dependency graph, ensuring that all cyclic
       // dependencies are caught before possibly executing a task.
       if let Err(()) = self.store.add_dependency(src, dst, Dependency::ReservedRequire) {
-        let src_task = self.store.get_task(src);
-        panic!("Cyclic task dependency; current executing task '{:?}' is requiring task '{:?}' which directly or \
-            indirectly requires the current executing task", src_task, &task);
+        self.violations.raise(BuildViolation::CyclicDependency {
+          requiring_task: self.store.get_task(src).as_key_obj().to_owned(),
+          required_task: Box::new(task.clone()),
+        });
       }
     }
   }
@@ -129,27 +229,270 @@ impl SessionExt for SessionInternal<'_> {
     if let Some(src) = &self.current_executing_task {
       // Update the dependency in the graph from a reserved dependency to a real task require dependency.
       let task_dependency = TaskDependency::new(task.clone(), checker, stamp);
-      let dependency = self.store.get_dependency_mut(src, dst);
-      *dependency = task_dependency.into();
+      self.store.set_dependency(src, dst, task_dependency.into());
+    }
+  }
+
+  fn require_fixpoint_cycle<T: Task>(&mut self, dst: &TaskNode) -> Option<T::Output> {
+    // Requiring a task that is being made consistent by `require_fixpoint` always closes a cycle back to that task.
+    let fixpoint = self.fixpoints.iter().find(|f| f.node == *dst)?;
+    let output = fixpoint.provisional.as_any().downcast_ref::<T::Output>()
+      .expect("BUG: non-matching provisional task output type")
+      .clone();
+    if let Some(src) = &self.current_executing_task {
+      // No dependency is created as that would create a cycle, so record it to re-execute `src` in a next iteration.
+      self.store.add_cyclic_requirer(src, dst);
     }
+    Some(output)
+  }
+  fn push_fixpoint<T: Task>(&mut self, node: TaskNode, initial: T::Output) {
+    self.fixpoints.push(Fixpoint {
+      node,
+      provisional: Box::new(initial.clone()),
+      initial: Box::new(initial),
+      stale_requirers: self.store.take_cyclic_requirers(&node),
+      executed_tasks_start: self.executed_tasks.len(),
+    });
+  }
+  fn next_fixpoint_iteration<T: Task, H: OutputChecker<T::Output>>(
+    &mut self,
+    checker: &H,
+    output: &T::Output,
+    iteration: usize,
+    max_iters: usize,
+  ) -> bool {
+    let fixpoint = self.fixpoints.last_mut().expect("BUG: no fixpoint for fixpoint iteration");
+    let node = fixpoint.node;
+    let cycled = !self.store.get_cyclic_requirers(&node).is_empty();
+    let executed = self.executed_tasks[fixpoint.executed_tasks_start..].contains(&node);
+    let provisional = fixpoint.provisional.as_any().downcast_ref::<T::Output>()
+      .expect("BUG: non-matching provisional task output type");
+    let stabilized = checker.check(output, &checker.stamp(provisional)).is_none();
+    // Iterate when a cycle was closed with a provisional output that has not stabilized yet, or when the task was
+    // executed with outputs of tasks that closed a cycle in a previous session, which are stale.
+    let iterate = ((cycled && !stabilized) || (!cycled && executed && !fixpoint.stale_requirers.is_empty()))
+      && iteration < max_iters;
+    if !iterate {
+      let fixpoint = self.fixpoints.pop().unwrap();
+      for src in &fixpoint.stale_requirers {
+        self.store.add_cyclic_requirer(src, &node);
+      }
+      return false;
+    }
+
+    // Reset the tasks in the cycle so that they are executed again with `output` as provisional output. If the task was
+    // executed with stale outputs instead, it has to iterate from `initial` again to be consistent with a clean build.
+    let mut requirers = mem::take(&mut fixpoint.stale_requirers);
+    for src in self.store.take_cyclic_requirers(&node) {
+      if !requirers.contains(&src) {
+        requirers.push(src);
+      }
+    }
+    fixpoint.provisional = if cycled { Box::new(output.clone()) } else { fixpoint.initial.clone() };
+    fixpoint.executed_tasks_start = self.executed_tasks.len();
+    for task_node in self.store.get_tasks_on_paths(&node, &requirers) {
+      self.store.remove_task_output(&task_node);
+      self.consistent.remove(&task_node);
+    }
+    true
+  }
+  fn update_fixpoint_dependency<T: Task, H: OutputChecker<T::Output>>(
+    &mut self,
+    dst: &TaskNode,
+    task: &T,
+    checker: H,
+    stamp: H::Stamp,
+    initial: T::Output,
+    max_iters: usize,
+  ) {
+    if let Some(src) = &self.current_executing_task {
+      // Update the reserved dependency to a fixpoint dependency, which iterates to a fixpoint when checked.
+      let task_dependency = TaskDependency::new(task.clone(), checker, stamp);
+      let fixpoint_dependency = FixpointTaskDependency::new(task_dependency, initial, max_iters);
+      self.store.set_dependency(src, dst, Dependency::Require(Box::new(fixpoint_dependency)));
+    }
+  }
+
+  #[cfg(feature = "panic_fallback")]
+  fn recover_require_panic<T: Task>(
+    &mut self,
+    payload: Box<dyn Any + Send>,
+    dst: &TaskNode,
+    task: &T,
+    previous_executing_task: Option<TaskNode>,
+    previous_depth: usize,
+    previous_fixpoints: usize,
+  ) {
+    if payload.is::<BudgetUnwind>() || payload.is::<ViolationUnwind>() || self.violations.raised.is_some() {
+      resume_unwind(payload); // Do not catch unwinds that interrupt the build.
+    }
+    // Unwinding interrupted `task` and the tasks it (transitively) required: reset them and their partially created
+    // dependencies.
+    self.current_executing_task = previous_executing_task;
+    self.depth = previous_depth;
+    self.fixpoints.truncate(previous_fixpoints);
+    self.store.reset_tasks_without_output_from(dst);
+
+    let message = payload.downcast_ref::<&str>().copied()
+      .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+      .unwrap_or("Box<dyn Any>");
+    self.tracker.execute_panicked(task, message);
+
+    if let Some(src) = &self.current_executing_task {
+      // Update the reserved dependency to one that is always inconsistent, so that the requiring task is re-executed.
+      let task_dependency = PanickedTaskDependency::new(task.clone());
+      self.store.set_dependency(src, dst, Dependency::Require(Box::new(task_dependency)));
+    }
+  }
+}
+
+/// Reads `resource` with `state`, retrying on failure according to `policy`, unless `checker` classifies the error as
+/// permanent. Returns the result of the last attempt, with its error wrapped by `checker`.
+#[inline]
+fn read_with_retry<'rs, R: Resource, H: ResourceChecker<R>, RS: ResourceState<R>>(
+  resource: &R,
+  checker: &H,
+  state: &'rs mut RS,
+  policy: &RetryPolicy,
+) -> Result<R::Reader<'rs>, H::Error> {
+  let mut backoff = Backoff::new(policy);
+  resource.read_retrying(state, |e| checker.wrap_error(e), |e| backoff.retry(checker, e))
+}
+
+/// Writes `resource` with `state`, retrying on failure according to `policy`, unless `checker` classifies the error as
+/// permanent. Returns the result of the last attempt, with its error wrapped by `checker`.
+#[inline]
+fn write_with_retry<'r, R: Resource, H: ResourceChecker<R>, RS: ResourceState<R>>(
+  resource: &'r R,
+  checker: &H,
+  state: &'r mut RS,
+  policy: &RetryPolicy,
+) -> Result<R::Writer<'r>, H::Error> {
+  let mut backoff = Backoff::new(policy);
+  resource.write_retrying(state, |e| checker.wrap_error(e), |e| backoff.retry(checker, e))
+}
+
+/// Tracks attempts of a [`RetryPolicy`].
+struct Backoff {
+  remaining_retries: usize,
+  duration: Duration,
+}
+impl Backoff {
+  #[inline]
+  fn new(policy: &RetryPolicy) -> Self {
+    Self { remaining_retries: policy.max_attempts.saturating_sub(1), duration: policy.backoff }
+  }
+
+  /// Returns `true` if another attempt should be made after an attempt failed with `error`, after waiting for the
+  /// current backoff duration. Returns `false` without waiting if `checker` classifies `error` as permanent, or if all
+  /// attempts have been exhausted.
+  #[inline]
+  fn retry<R: Resource, H: ResourceChecker<R>>(&mut self, checker: &H, error: &H::Error) -> bool {
+    checker.classify_error(error) == ErrorKind::Transient && self.wait()
+  }
+
+  /// Waits for the current backoff duration and doubles it, returning `true` if another attempt may be made, `false`
+  /// if all attempts have been exhausted (without waiting).
+  #[inline]
+  fn wait(&mut self) -> bool {
+    if self.remaining_retries == 0 {
+      return false;
+    }
+    self.remaining_retries -= 1;
+    thread::sleep(self.duration);
+    self.duration = self.duration.saturating_mul(2);
+    true
   }
 }
 
-/// Validates a `resource` write from `src` to `dst`, panicking if an overlapping write or hidden dependency was found.
+/// Validates a `resource` write from `src` to `dst`, returning `Err(violation)` if an overlapping write or hidden
+/// dependency was found. Earlier reads and writes of `resource` by `src` itself are not violations, as they are merged
+/// into the write dependency.
 #[inline]
-fn validate_write<R: Resource>(session: &SessionInternal<'_>, resource: &R, src: &TaskNode, dst: &ResourceNode) {
-  if let Some(previous_writing_task_node) = session.store.get_task_writing_to_resource(dst) {
-    let src_task = session.store.get_task(src);
-    let previous_writing_task = session.store.get_task(&previous_writing_task_node);
-    panic!("Overlapping write; resource '{:?}' is written to by the current executing task '{:?}' that was \
-            previously written to by task: {:?}", resource, src_task, previous_writing_task);
+fn validate_write<R: Resource>(
+  session: &SessionInternal<'_>,
+  resource: &R,
+  src: &TaskNode,
+  dst: &ResourceNode,
+) -> Result<(), BuildViolation> {
+  let previous_writing_task_node = session.store.get_task_writing_to_resource(dst).filter(|n| n != src);
+  if let Some(previous_writing_task_node) = previous_writing_task_node {
+    return Err(BuildViolation::OverlappingWrite {
+      resource: Box::new(resource.clone()),
+      writing_task: session.store.get_task(src).as_key_obj().to_owned(),
+      previous_writing_task: session.store.get_task(&previous_writing_task_node).as_key_obj().to_owned(),
+    });
   }
   for reading_task_node in session.store.get_tasks_reading_from_resource(dst) {
-    if !session.store.contains_transitive_task_dependency(&reading_task_node, src) {
-      let src_task = session.store.get_task(src);
-      let reading_task = session.store.get_task(&reading_task_node);
-      panic!("Hidden dependency; resource '{:?}' is written to by the current executing task '{:?}' without a \
-              dependency from reading task '{:?}' to the current executing task", resource, src_task, reading_task);
+    if reading_task_node != *src && !session.store.contains_transitive_task_dependency(&reading_task_node, src) {
+      return Err(BuildViolation::HiddenDependency {
+        resource: Box::new(resource.clone()),
+        reading_task: session.store.get_task(&reading_task_node).as_key_obj().to_owned(),
+        writing_task: session.store.get_task(src).as_key_obj().to_owned(),
+      });
+    }
+  }
+  Ok(())
+}
+
+/// Handling of [build violations](BuildViolation) in a session.
+#[derive(Default)]
+pub struct Violations {
+  /// Whether violations are returned as errors, instead of panicking.
+  pub as_errors: bool,
+  /// The violation that was raised, if any.
+  pub raised: Option<BuildViolation>,
+}
+/// Resources that were found to be changed in a session, in the order they were found, without duplicates.
+#[derive(Default)]
+pub struct ChangedResources {
+  /// Changed resources in the order they were found.
+  pub ordered: Vec<Box<dyn KeyObj>>,
+  /// Changed resources, for checking whether a resource was already found in constant time.
+  set: HashSet<Box<dyn KeyObj>>,
+}
+impl ChangedResources {
+  /// Adds `resource` if it is not in here yet.
+  #[inline]
+  pub fn add(&mut self, resource: &dyn KeyObj) {
+    if !self.set.contains(resource) {
+      self.set.insert(resource.to_owned());
+      self.ordered.push(resource.to_owned());
+    }
+  }
+}
+/// Fixpoint iteration of a task that is being [required as a fixpoint](crate::Context::require_fixpoint).
+pub struct Fixpoint {
+  /// Node of the fixpoint task.
+  pub node: TaskNode,
+  /// Provisional output of the fixpoint task, returned when it is required in a cycle.
+  pub provisional: Box<dyn ValueObj>,
+  /// Initial provisional output of the fixpoint task.
+  pub initial: Box<dyn ValueObj>,
+  /// Tasks that required the fixpoint task in a cycle in a previous session, and have not been reset yet.
+  pub stale_requirers: Vec<TaskNode>,
+  /// Length of the executed tasks of the session at the start of the current iteration.
+  pub executed_tasks_start: usize,
+}
+
+/// Panic payload for unwinding due to an exhausted execution budget.
+pub struct BudgetUnwind;
+
+/// Panic payload for unwinding due to a raised violation. The violation itself is stored in [`Violations`], as panic
+/// payloads must be `Send`.
+pub struct ViolationUnwind;
+impl Violations {
+  /// Raises `violation`, storing it. If violations are returned as errors, unwinds with [`ViolationUnwind`] without
+  /// invoking the panic hook. Otherwise, panics with `violation` as message.
+  #[inline]
+  pub fn raise(&mut self, violation: BuildViolation) -> ! {
+    if self.as_errors {
+      self.raised = Some(violation);
+      resume_unwind(Box::new(ViolationUnwind));
+    } else {
+      let message = violation.to_string();
+      self.raised = Some(violation);
+      panic!("{}", message);
     }
   }
 }
@@ -1,11 +1,18 @@
 use std::any::Any;
+use std::error::Error;
 use std::fmt::Debug;
+use std::panic::resume_unwind;
+#[cfg(feature = "panic_fallback")]
+use std::panic::{AssertUnwindSafe, catch_unwind, RefUnwindSafe};
 
-use crate::{Context, OutputChecker, Resource, ResourceChecker, Task};
-use crate::context::SessionExt;
-use crate::dependency::{Dependency, TaskDependency};
+use crate::{BuildViolation, Context, Key, OutputChecker, Resource, ResourceChecker, Task, Value, WriteOutcome};
+use crate::context::{BudgetUnwind, SessionExt};
+#[cfg(feature = "panic_fallback")]
+use crate::dependency::PanickedTaskDependency;
+use crate::dependency::{Dependency, FixpointTaskDependency, TaskDependency};
 use crate::pie::SessionInternal;
 use crate::store::TaskNode;
+use crate::trait_object::{KeyObj, ResourceCheckerObj, TaskCheckerObj, ValueObj};
 
 /// Top-down incremental context implementation.
 ///
@@ -33,9 +40,17 @@ impl Context for TopDownContext<'_, '_> {
     let track_end = self.session.tracker.require(task, &checker);
 
     let dst = self.session.store.get_or_create_task_node(task);
+    if let Some(output) = self.session.require_fixpoint_cycle::<T>(&dst) {
+      let stamp = checker.stamp(&output);
+      track_end(&mut self.session.tracker, &stamp, &output);
+      return output;
+    }
     self.session.reserve_require_dependency(&dst, task);
 
-    let output = self.make_task_consistent(task);
+    let output = match self.session.intercept_require(task) {
+      Some(output) => output,
+      None => self.make_task_consistent(task),
+    };
     let stamp = checker.stamp(&output);
     track_end(&mut self.session.tracker, &stamp, &output);
 
@@ -43,6 +58,81 @@ impl Context for TopDownContext<'_, '_> {
 
     output
   }
+  #[cfg(feature = "panic_fallback")]
+  fn require_with_fallback<T, H>(&mut self, task: &T, checker: H, fallback: T::Output) -> T::Output where
+    T: Task + RefUnwindSafe,
+    H: OutputChecker<T::Output>,
+  {
+    let track_end = self.session.tracker.require(task, &checker);
+
+    let dst = self.session.store.get_or_create_task_node(task);
+    self.session.reserve_require_dependency(&dst, task);
+
+    let (previous_executing_task, previous_depth) = (self.session.current_executing_task, self.session.depth);
+    let previous_fixpoints = self.session.fixpoints.len();
+    let result = catch_unwind(AssertUnwindSafe(|| match self.session.intercept_require(task) {
+      Some(output) => output,
+      None => self.make_task_consistent(task),
+    }));
+    match result {
+      Ok(output) => {
+        let stamp = checker.stamp(&output);
+        track_end(&mut self.session.tracker, &stamp, &output);
+        self.session.update_require_dependency(&dst, task, checker, stamp);
+        output
+      }
+      Err(payload) => {
+        self.session.recover_require_panic(
+          payload,
+          &dst,
+          task,
+          previous_executing_task,
+          previous_depth,
+          previous_fixpoints,
+        );
+        let stamp = checker.stamp(&fallback);
+        track_end(&mut self.session.tracker, &stamp, &fallback);
+        fallback
+      }
+    }
+  }
+  fn require_fixpoint<T, H>(&mut self, task: &T, checker: H, initial: T::Output, max_iters: usize) -> T::Output where
+    T: Task,
+    H: OutputChecker<T::Output>,
+  {
+    let track_end = self.session.tracker.require(task, &checker);
+
+    let dst = self.session.store.get_or_create_task_node(task);
+    if let Some(output) = self.session.require_fixpoint_cycle::<T>(&dst) {
+      let stamp = checker.stamp(&output);
+      track_end(&mut self.session.tracker, &stamp, &output);
+      return output;
+    }
+    self.session.reserve_require_dependency(&dst, task);
+
+    let output = match self.session.intercept_require(task) {
+      Some(output) => output,
+      None => self.make_fixpoint_consistent(task, &checker, initial.clone(), max_iters),
+    };
+    let stamp = checker.stamp(&output);
+    track_end(&mut self.session.tracker, &stamp, &output);
+
+    self.session.update_fixpoint_dependency(&dst, task, checker, stamp, initial, max_iters);
+
+    output
+  }
+  #[inline]
+  fn require_dyn(&mut self, task: &dyn KeyObj, checker: Box<dyn TaskCheckerObj>) -> Box<dyn ValueObj> {
+    checker.require_top_down(self, task)
+  }
+  #[inline]
+  fn peek_output<T: Task>(&self, task: &T) -> Option<T::Output> {
+    self.session.peek_output(task)
+  }
+  #[inline]
+  fn memo_local<K: Key, V: Value>(&mut self, key: K, compute: impl FnOnce() -> V) -> V {
+    self.session.memo_local(key, compute)
+  }
 
   #[inline]
   fn read<T, R, H>(&mut self, resource: &T, checker: H) -> Result<R::Reader<'_>, H::Error> where
@@ -61,6 +151,15 @@ impl Context for TopDownContext<'_, '_> {
   {
     self.session.write(resource, checker, write_fn)
   }
+  #[inline]
+  fn try_write<T, R, H, F>(&mut self, resource: &T, checker: H, write_fn: F) -> Result<WriteOutcome, H::Error> where
+    T: ToOwned<Owned=R>,
+    R: Resource,
+    H: ResourceChecker<R>,
+    F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>
+  {
+    self.session.try_write(resource, checker, write_fn)
+  }
 
   #[inline]
   fn create_writer<'r, R: Resource>(&'r mut self, resource: &'r R) -> Result<R::Writer<'r>, R::Error> {
@@ -74,6 +173,22 @@ impl Context for TopDownContext<'_, '_> {
   {
     self.session.written_to(resource, checker)
   }
+  #[inline]
+  fn written_to_dyn(
+    &mut self,
+    resource: &dyn KeyObj,
+    checker: Box<dyn ResourceCheckerObj>,
+  ) -> Result<(), Box<dyn Error>> {
+    checker.written_to_obj(self.session, resource)
+  }
+
+  #[inline]
+  fn scope<R>(&mut self, label: &'static str, f: impl FnOnce(&mut Self) -> R) -> R {
+    let track_end = self.session.tracker.scope(label);
+    let output = f(self);
+    track_end(&mut self.session.tracker);
+    output
+  }
 }
 
 impl TopDownContext<'_, '_> {
@@ -90,23 +205,69 @@ impl TopDownContext<'_, '_> {
         .clone();
     }
 
-    let output = if let Some(output) = self.check_task::<T::Output>(&node) {
-      output.clone()
+    self.session.depth += 1;
+    if let Some(max_depth) = self.session.max_depth {
+      if self.session.depth > max_depth {
+        self.session.violations.raise(BuildViolation::DepthExceeded { task: Box::new(task.clone()), max_depth });
+      }
+    }
+
+    let consistent_output = if task.always_execute() { None } else { self.check_task::<T::Output>(&node) };
+    let output = if let Some(output) = consistent_output {
+      let output = output.clone();
+      self.session.tracker.require_cache_hit(task);
+      self.session.statistics.total_cache_hits += 1;
+      output
     } else {
+      self.session.tracker.require_cache_miss(task);
+      if self.session.execution_budget == Some(0) { // Budget exhausted: defer this task, keeping it inconsistent.
+        resume_unwind(Box::new(BudgetUnwind));
+      }
       self.session.store.reset_task(&node);
       let previous_executing_task = self.session.current_executing_task.replace(node);
+      if let Err(message) = task.precondition(self) {
+        self.session.violations.raise(BuildViolation::PreconditionFailed { task: Box::new(task.clone()), message });
+      }
       let track_end = self.session.tracker.execute(task);
+      self.session.statistics.total_executions += 1;
       let output = task.execute(self);
+      if let Some(budget) = &mut self.session.execution_budget {
+        *budget = budget.saturating_sub(1);
+      }
       track_end(&mut self.session.tracker, &output);
       self.session.current_executing_task = previous_executing_task;
       self.session.store.set_task_output(&node, Box::new(output.clone()));
+      self.session.executed_tasks.push(node);
       output
     };
 
+    self.session.depth -= 1;
     self.session.consistent.insert(node);
     output
   }
 
+  /// Makes fixpoint `task` consistent by iterating until its output stabilizes according to `checker`, starting with
+  /// `initial` as provisional output, returning its output at the fixpoint.
+  #[inline]
+  fn make_fixpoint_consistent<T: Task, H: OutputChecker<T::Output>>(
+    &mut self,
+    task: &T,
+    checker: &H,
+    initial: T::Output,
+    max_iters: usize,
+  ) -> T::Output {
+    let node = self.session.store.get_or_create_task_node(task);
+    self.session.push_fixpoint::<T>(node, initial);
+    let mut iteration = 1;
+    loop {
+      let output = self.make_task_consistent(task);
+      if !self.session.next_fixpoint_iteration::<T, H>(checker, &output, iteration, max_iters) {
+        return output;
+      }
+      iteration += 1;
+    }
+  }
+
   /// Check whether task `src` is consistent. Returns `Some(output)` when the task is consistent, `None` when
   /// inconsistent. An inconsistent task must be executed.
   ///
@@ -125,10 +286,13 @@ impl TopDownContext<'_, '_> {
       let consistent = match dependency {
         Dependency::ReservedRequire => panic!("BUG: attempt to consistency check reserved require task dependency"),
         Dependency::Require(d) => Ok(d.as_top_down_check().is_consistent(self)),
-        Dependency::Read(d) | Dependency::Write(d) => d.is_consistent_top_down(
-          &mut self.session.resource_state,
-          &mut self.session.tracker,
-        ),
+        Dependency::Read(d) | Dependency::Write(d) => {
+          let consistent = d.is_consistent_top_down(&mut self.session.resource_state, &mut self.session.tracker);
+          if let Ok(false) = consistent {
+            self.session.changed_resources.add(d.resource());
+          }
+          consistent
+        }
       };
       match consistent {
         Ok(false) => return None,
@@ -161,3 +325,28 @@ impl<T: Task, C: OutputChecker<T::Output>> TopDownCheck for TaskDependency<T, C,
     inconsistency.is_none()
   }
 }
+impl<T: Task, C: OutputChecker<T::Output>> TopDownCheck for FixpointTaskDependency<T, C, C::Stamp> {
+  #[inline]
+  fn is_consistent(&self, context: &mut TopDownContext) -> bool {
+    let dependency = self.dependency();
+    let tracker = &mut context.session.tracker;
+    let check_task_end = tracker.check_task(dependency.task(), dependency.checker(), dependency.stamp());
+    let output = context.make_fixpoint_consistent(
+      dependency.task(),
+      dependency.checker(),
+      self.initial().clone(),
+      self.max_iters(),
+    );
+    let inconsistency = dependency.check(&output);
+    let inconsistency_dyn = inconsistency.as_ref().map(|o| o as &dyn Debug);
+    check_task_end(&mut context.session.tracker, inconsistency_dyn);
+    inconsistency.is_none()
+  }
+}
+#[cfg(feature = "panic_fallback")]
+impl<T: Task> TopDownCheck for PanickedTaskDependency<T> {
+  #[inline]
+  fn is_consistent(&self, _context: &mut TopDownContext) -> bool {
+    false // Do not make the task consistent, as that could panic again outside of `require_with_fallback`.
+  }
+}
@@ -2,13 +2,17 @@ use std::error::Error;
 use std::fmt::Debug;
 
 use dyn_clone::DynClone;
+use pie_graph::EdgeKind;
 
-use crate::{OutputChecker, Resource, ResourceChecker, ResourceState, Task};
+use crate::{Change, ErrorKind, OutputChecker, Resource, ResourceChecker, ResourceState, Task};
 use crate::context::top_down::TopDownCheck;
 use crate::pie::Tracking;
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
 use crate::trait_object::collection::TypeToAnyMap;
 
+/// Internal type for errors produced while checking dependencies, along with their classification.
+pub type CheckError = (Box<dyn Error>, ErrorKind);
+
 /// Internal type for task dependencies.
 #[derive(Clone, Debug)]
 pub struct TaskDependency<T, C, S> {
@@ -45,7 +49,12 @@ pub trait TaskDependencyObj: DynClone + Debug {
   fn stamp(&self) -> &dyn ValueObj;
 
   fn as_top_down_check(&self) -> &dyn TopDownCheck;
-  fn is_consistent_bottom_up(&self, output: &dyn ValueObj, requiring_task: &dyn KeyObj, tracker: &mut Tracking) -> bool;
+  fn is_consistent_bottom_up(
+    &self,
+    output: &dyn ValueObj,
+    requiring_task: &dyn TaskKeyObj,
+    tracker: &mut Tracking,
+  ) -> bool;
 }
 const_assert_object_safe!(dyn TaskDependencyObj);
 impl<T: Task, C: OutputChecker<T::Output>> TaskDependencyObj for TaskDependency<T, C, C::Stamp> {
@@ -59,7 +68,12 @@ impl<T: Task, C: OutputChecker<T::Output>> TaskDependencyObj for TaskDependency<
   #[inline]
   fn as_top_down_check(&self) -> &dyn TopDownCheck { self as &dyn TopDownCheck }
   #[inline]
-  fn is_consistent_bottom_up(&self, output: &dyn ValueObj, requiring_task: &dyn KeyObj, tracker: &mut Tracking) -> bool {
+  fn is_consistent_bottom_up(
+    &self,
+    output: &dyn ValueObj,
+    requiring_task: &dyn TaskKeyObj,
+    tracker: &mut Tracking,
+  ) -> bool {
     let Some(output) = output.as_any().downcast_ref::<T::Output>() else {
       return false;
     };
@@ -76,6 +90,80 @@ impl<T: Task, C: OutputChecker<T::Output>> TaskDependencyObj for TaskDependency<
     }
   }
 }
+/// Internal type for task dependencies to fixpoint tasks required with
+/// [`Context::require_fixpoint`](crate::Context::require_fixpoint), which are made consistent by iterating to a
+/// fixpoint from `initial` when checked top-down.
+#[derive(Clone, Debug)]
+pub struct FixpointTaskDependency<T: Task, C, S> {
+  dependency: TaskDependency<T, C, S>,
+  initial: T::Output,
+  max_iters: usize,
+}
+impl<T: Task, C: OutputChecker<T::Output>> FixpointTaskDependency<T, C, C::Stamp> {
+  #[inline]
+  pub fn new(dependency: TaskDependency<T, C, C::Stamp>, initial: T::Output, max_iters: usize) -> Self {
+    Self { dependency, initial, max_iters }
+  }
+
+  #[inline]
+  pub fn dependency(&self) -> &TaskDependency<T, C, C::Stamp> { &self.dependency }
+  #[inline]
+  pub fn initial(&self) -> &T::Output { &self.initial }
+  #[inline]
+  pub fn max_iters(&self) -> usize { self.max_iters }
+}
+impl<T: Task, C: OutputChecker<T::Output>> TaskDependencyObj for FixpointTaskDependency<T, C, C::Stamp> {
+  #[inline]
+  fn task(&self) -> &dyn KeyObj { self.dependency.task() as &dyn KeyObj }
+  #[inline]
+  fn checker(&self) -> &dyn ValueObj { self.dependency.checker() as &dyn ValueObj }
+  #[inline]
+  fn stamp(&self) -> &dyn ValueObj { self.dependency.stamp() as &dyn ValueObj }
+
+  #[inline]
+  fn as_top_down_check(&self) -> &dyn TopDownCheck { self as &dyn TopDownCheck }
+  #[inline]
+  fn is_consistent_bottom_up(
+    &self,
+    output: &dyn ValueObj,
+    requiring_task: &dyn TaskKeyObj,
+    tracker: &mut Tracking,
+  ) -> bool {
+    self.dependency.is_consistent_bottom_up(output, requiring_task, tracker)
+  }
+}
+
+/// Internal type for task dependencies to tasks that panicked while being required with
+/// [`Context::require_with_fallback`](crate::Context::require_with_fallback), which are always inconsistent.
+#[cfg(feature = "panic_fallback")]
+#[derive(Clone, Debug)]
+pub struct PanickedTaskDependency<T>(T);
+#[cfg(feature = "panic_fallback")]
+impl<T: Task> PanickedTaskDependency<T> {
+  #[inline]
+  pub fn new(task: T) -> Self { Self(task) }
+}
+#[cfg(feature = "panic_fallback")]
+impl<T: Task> TaskDependencyObj for PanickedTaskDependency<T> {
+  #[inline]
+  fn task(&self) -> &dyn KeyObj { &self.0 as &dyn KeyObj }
+  #[inline]
+  fn checker(&self) -> &dyn ValueObj { &() as &dyn ValueObj }
+  #[inline]
+  fn stamp(&self) -> &dyn ValueObj { &() as &dyn ValueObj }
+
+  #[inline]
+  fn as_top_down_check(&self) -> &dyn TopDownCheck { self as &dyn TopDownCheck }
+  #[inline]
+  fn is_consistent_bottom_up(
+    &self,
+    _output: &dyn ValueObj,
+    _requiring_task: &dyn TaskKeyObj,
+    _tracker: &mut Tracking,
+  ) -> bool {
+    false
+  }
+}
 impl Clone for Box<dyn TaskDependencyObj> {
   #[inline]
   fn clone(&self) -> Self { dyn_clone::clone_box(self.as_ref()) }
@@ -113,14 +201,20 @@ impl<R: Resource, C: ResourceChecker<R>> ResourceDependency<R, C, C::Stamp> {
     &'i self,
     state: &'i mut RS,
     tracker: &mut Tracking,
-    track_end: impl FnOnce(&mut Tracking, Result<Option<&dyn Debug>, &dyn Error>),
-  ) -> Result<bool, Box<dyn Error>> {
-    let inconsistency = self.check(state);
+    track_end: impl FnOnce(&mut Tracking, Result<Option<&Change>, &dyn Error>),
+  ) -> Result<bool, CheckError> {
+    let inconsistency = self.checker.describe_change(&self.resource, state, &self.stamp);
     let inconsistency_dyn = inconsistency.as_ref()
-      .map(|o| o.as_ref().map(|i| i as &dyn Debug))
+      .map(|o| o.as_ref())
       .map_err(|e| e as &dyn Error);
     track_end(tracker, inconsistency_dyn);
-    Ok(inconsistency?.is_none())
+    match inconsistency {
+      Ok(inconsistency) => Ok(inconsistency.is_none()),
+      Err(e) => {
+        let kind = self.checker.classify_error(&e);
+        Err((Box::new(e), kind))
+      }
+    }
   }
 
   #[inline]
@@ -134,36 +228,43 @@ impl<R: Resource, C: ResourceChecker<R>> ResourceDependency<R, C, C::Stamp> {
 /// Object-safe trait.
 pub trait ResourceDependencyObj: DynClone + Debug {
   fn resource(&self) -> &dyn KeyObj;
-  fn checker(&self) -> &dyn ValueObj;
-  fn stamp(&self) -> &dyn ValueObj;
+  fn checker(&self) -> &dyn KeyObj;
+  /// Returns the stamps of this dependency: one stamp, or the stamps of all dependencies of a merged dependency.
+  fn stamps(&self) -> Vec<&dyn ValueObj>;
+  fn checks_external_changes(&self) -> bool;
 
   fn is_consistent_top_down(
     &self,
     resource_state: &mut TypeToAnyMap,
     tracker: &mut Tracking,
-  ) -> Result<bool, Box<dyn Error>>;
+  ) -> Result<bool, CheckError>;
   fn is_consistent_bottom_up(
     &self,
     resource_state: &mut TypeToAnyMap,
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     tracker: &mut Tracking,
-  ) -> Result<bool, Box<dyn Error>>;
+  ) -> Result<bool, CheckError>;
+  fn describe_change(&self, resource_state: &mut TypeToAnyMap) -> Result<Option<Change>, Box<dyn Error>>;
+
+  fn into_dependencies(self: Box<Self>) -> Vec<Box<dyn ResourceDependencyObj>>;
 }
 const_assert_object_safe!(dyn ResourceDependencyObj);
 impl<R: Resource, C: ResourceChecker<R>> ResourceDependencyObj for ResourceDependency<R, C, C::Stamp> {
   #[inline]
   fn resource(&self) -> &dyn KeyObj { &self.resource as &dyn KeyObj }
   #[inline]
-  fn checker(&self) -> &dyn ValueObj { &self.checker as &dyn ValueObj }
+  fn checker(&self) -> &dyn KeyObj { &self.checker as &dyn KeyObj }
   #[inline]
-  fn stamp(&self) -> &dyn ValueObj { &self.stamp as &dyn ValueObj }
+  fn stamps(&self) -> Vec<&dyn ValueObj> { vec![&self.stamp as &dyn ValueObj] }
+  #[inline]
+  fn checks_external_changes(&self) -> bool { self.checker.checks_external_changes() }
 
   #[inline]
   fn is_consistent_top_down(
     &self,
     resource_state: &mut TypeToAnyMap,
     tracker: &mut Tracking,
-  ) -> Result<bool, Box<dyn Error>> {
+  ) -> Result<bool, CheckError> {
     let track_end = tracker.check_resource(&self.resource, &self.checker, &self.stamp);
     self.is_consistent(resource_state, tracker, track_end)
   }
@@ -171,12 +272,75 @@ impl<R: Resource, C: ResourceChecker<R>> ResourceDependencyObj for ResourceDepen
   fn is_consistent_bottom_up(
     &self,
     resource_state: &mut TypeToAnyMap,
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     tracker: &mut Tracking,
-  ) -> Result<bool, Box<dyn Error>> {
+  ) -> Result<bool, CheckError> {
     let track_end = tracker.check_task_read_resource(reading_task, &self.checker, &self.stamp);
     self.is_consistent(resource_state, tracker, track_end)
   }
+  #[inline]
+  fn describe_change(&self, resource_state: &mut TypeToAnyMap) -> Result<Option<Change>, Box<dyn Error>> {
+    Ok(self.checker.describe_change(&self.resource, resource_state, &self.stamp)?)
+  }
+
+  #[inline]
+  fn into_dependencies(self: Box<Self>) -> Vec<Box<dyn ResourceDependencyObj>> { vec![self] }
+}
+
+/// Internal type for multiple dependencies to the same resource with different checkers, which is consistent
+/// if and only if all its dependencies are consistent. Its [checker](ResourceDependencyObj::checker) is the checker of
+/// its first dependency, while its [stamps](ResourceDependencyObj::stamps) are the stamps of all its dependencies.
+#[derive(Clone, Debug)]
+pub struct MergedResourceDependency(Vec<Box<dyn ResourceDependencyObj>>);
+impl ResourceDependencyObj for MergedResourceDependency {
+  #[inline]
+  fn resource(&self) -> &dyn KeyObj { self.0[0].resource() }
+  #[inline]
+  fn checker(&self) -> &dyn KeyObj { self.0[0].checker() }
+  #[inline]
+  fn stamps(&self) -> Vec<&dyn ValueObj> { self.0.iter().flat_map(|d| d.stamps()).collect() }
+  #[inline]
+  fn checks_external_changes(&self) -> bool { self.0.iter().any(|d| d.checks_external_changes()) }
+
+  #[inline]
+  fn is_consistent_top_down(
+    &self,
+    resource_state: &mut TypeToAnyMap,
+    tracker: &mut Tracking,
+  ) -> Result<bool, CheckError> {
+    for dependency in &self.0 {
+      if !dependency.is_consistent_top_down(resource_state, tracker)? {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+  #[inline]
+  fn is_consistent_bottom_up(
+    &self,
+    resource_state: &mut TypeToAnyMap,
+    reading_task: &dyn TaskKeyObj,
+    tracker: &mut Tracking,
+  ) -> Result<bool, CheckError> {
+    for dependency in &self.0 {
+      if !dependency.is_consistent_bottom_up(resource_state, reading_task, tracker)? {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+  #[inline]
+  fn describe_change(&self, resource_state: &mut TypeToAnyMap) -> Result<Option<Change>, Box<dyn Error>> {
+    for dependency in &self.0 {
+      if let Some(change) = dependency.describe_change(resource_state)? {
+        return Ok(Some(change));
+      }
+    }
+    Ok(None)
+  }
+
+  #[inline]
+  fn into_dependencies(self: Box<Self>) -> Vec<Box<dyn ResourceDependencyObj>> { self.0 }
 }
 impl Clone for Box<dyn ResourceDependencyObj> {
   #[inline]
@@ -192,6 +356,26 @@ pub enum Dependency {
   Read(Box<dyn ResourceDependencyObj>),
   Write(Box<dyn ResourceDependencyObj>),
 }
+/// Kind of [`Dependency`], for indexing dependencies by kind in the dependency graph.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum DependencyKind {
+  ReservedRequire,
+  Require,
+  Read,
+  Write,
+}
+impl EdgeKind for Dependency {
+  type Kind = DependencyKind;
+  #[inline]
+  fn kind(&self) -> DependencyKind {
+    match self {
+      Self::ReservedRequire => DependencyKind::ReservedRequire,
+      Self::Require(_) => DependencyKind::Require,
+      Self::Read(_) => DependencyKind::Read,
+      Self::Write(_) => DependencyKind::Write,
+    }
+  }
+}
 impl<T: Task, C: OutputChecker<T::Output>> From<TaskDependency<T, C, C::Stamp>> for Dependency {
   #[inline]
   fn from(value: TaskDependency<T, C, C::Stamp>) -> Self { Self::Require(Box::new(value)) }
@@ -207,6 +391,53 @@ impl Dependency {
   }
 }
 
+impl Dependency {
+  /// Merges resource dependency `other` into resource dependency `self`, where both are dependencies from the same task
+  /// to the same resource, created during the same execution of that task. The stamps of the merged dependency are the
+  /// strictest ones, so that all changes to the resource since the earliest read are detected:
+  ///
+  /// - Dependencies with equal checkers are deduplicated. A read does not replace an earlier dependency, because the
+  ///   stamp of the earliest read detects all changes since then. A write does replace an earlier dependency, because
+  ///   the resource is changed by the write, and thus only the stamp of the latest write represents the current state
+  ///   of the resource.
+  /// - Dependencies with different checkers, including checkers of the same type with different values, are all kept,
+  ///   and are all checked.
+  ///
+  /// The merged dependency is a write dependency if either dependency is a write dependency.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `self` or `other` is not a resource dependency.
+  pub fn merge_resource(self, other: Dependency) -> Dependency {
+    let (is_write, dependency) = self.into_resource();
+    let (other_is_write, other_dependency) = other.into_resource();
+    let mut dependencies = dependency.into_dependencies();
+    for other_dependency in other_dependency.into_dependencies() {
+      let checker = other_dependency.checker().as_any();
+      match dependencies.iter().position(|d| d.checker().eq_any(checker)) {
+        Some(index) if other_is_write => dependencies[index] = other_dependency,
+        Some(_) => {}
+        None => dependencies.push(other_dependency),
+      }
+    }
+    let dependency = if dependencies.len() == 1 {
+      dependencies.pop().unwrap()
+    } else {
+      Box::new(MergedResourceDependency(dependencies))
+    };
+    if is_write || other_is_write { Self::Write(dependency) } else { Self::Read(dependency) }
+  }
+
+  #[inline]
+  fn into_resource(self) -> (bool, Box<dyn ResourceDependencyObj>) {
+    match self {
+      Self::Read(d) => (false, d),
+      Self::Write(d) => (true, d),
+      _ => panic!("BUG: attempt to merge non-resource dependency"),
+    }
+  }
+}
+
 // Note: this PartialEq implementation only checks the tasks and resources of dependencies.
 impl PartialEq for Dependency {
   fn eq(&self, other: &Self) -> bool {
@@ -16,17 +16,22 @@
 //!
 //! All user-implementable traits and corresponding associated types are bounded by [`Debug`] for debugging/logging.
 
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::borrow::Cow;
+use std::collections::{hash_map, HashMap};
 use std::error::Error;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 use std::hash::Hash;
+use std::marker::PhantomData;
+use std::time::Duration;
 
 use crate::tracker::Tracker;
-use crate::trait_object::KeyObj;
+use crate::trait_object::{KeyObj, ResourceCheckerObj, TaskCheckerObj, ValueObj};
 
 pub mod task;
 pub mod resource;
 pub mod tracker;
+pub mod plan;
 #[macro_use]
 pub mod trait_object;
 
@@ -34,6 +39,8 @@ mod pie;
 mod context;
 mod store;
 mod dependency;
+#[cfg(feature = "watch")]
+mod watch;
 
 /// Trait alias for types that are used as values: types that can be cloned, debug formatted, and contain no
 /// non-`'static` references. We use this as an alias for trait bounds and super-traits.
@@ -52,6 +59,46 @@ pub trait Task: Key {
 
   /// Execute the task under `context`, returning an output.
   fn execute<C: Context>(&self, context: &mut C) -> Self::Output;
+
+  /// Returns `true` if this task must be executed every time it is required top-down, skipping the check whether it is
+  /// consistent. Useful for tasks that wrap inherently non-deterministic work, such as generating a timestamp. Returns
+  /// `false` by default.
+  ///
+  /// Such a task still executes at most once per session. Tasks that depend on it are still checked as usual: they are
+  /// only re-executed when the output of this task is inconsistent with respect to their output checker. However, the
+  /// dependencies of this task are never checked, so this task may observe changes that incremental builds would not
+  /// otherwise react to, and every build that requires this task pays for its execution.
+  #[inline]
+  fn always_execute(&self) -> bool { false }
+  /// Checks the preconditions of this task under `context`, such as a required environment variable being set or a
+  /// directory existing, returning `Err(message)` describing the failed precondition. Returns `Ok(())` by default.
+  ///
+  /// The context checks preconditions immediately before executing this task, with this task as the current executing
+  /// task, so dependencies created here are dependencies of this task. When a precondition fails, this task is not
+  /// executed, and the context [raises](Session::try_require) a [`BuildViolation::PreconditionFailed`] violation
+  /// instead, failing fast with a clear error instead of failing in the middle of execution.
+  #[inline]
+  fn precondition<C: Context>(&self, _context: &mut C) -> Result<(), String> { Ok(()) }
+  /// Returns a concise description of this task, used by [trackers](tracker::Tracker) to label this task. Returns the
+  /// [`Debug`] representation of this task by default. Override this for tasks that are noisy to debug format, for
+  /// example because they have large fields.
+  #[inline]
+  fn describe(&self) -> Cow<'_, str> { Cow::Owned(format!("{:?}", self)) }
+  /// Returns the key that identifies this task for caching. Returns a boxed clone of this task by default. Override
+  /// this to normalize the identity of tasks, such as canonicalizing a path, so that distinct tasks with equal cache
+  /// keys share their cache entry: their output and dependencies. The first required task with a cache key is stored
+  /// as the task of that cache entry, which is executed by bottom-up builds and listed by [`Pie::tasks`]. Trackers and
+  /// [`Debug`] representations still show the original tasks as they are required.
+  ///
+  /// Tasks with equal cache keys must produce equal outputs, as they share one output. Cache keys of different task
+  /// types must not be equal, as their outputs have different types, which is best ensured by returning a normalized
+  /// clone of this task.
+  ///
+  /// The cache key is created on every lookup of this task in the store, which happens every time this task is
+  /// required. The default implementation therefore allocates a boxed clone per require, so keep tasks cheap to clone,
+  /// and cache keys cheap to create when overriding this.
+  #[inline]
+  fn cache_key(&self) -> Box<dyn KeyObj> { Box::new(self.clone()) }
 }
 
 /// Programmatic incremental build context, enabling tasks to require other tasks and read/write from/to resources,
@@ -71,6 +118,148 @@ pub trait Context {
   /// Requires `task` using `checker` for consistency checking, creating a task dependency and returning its consistent
   /// (i.e., most up-to-date) output value.
   fn require<T: Task, H: OutputChecker<T::Output>>(&mut self, task: &T, checker: H) -> T::Output;
+  /// Requires each task in `tasks` using `checker` for consistency checking, creating a task dependency to each task,
+  /// and folds their consistent output values into an accumulator starting at `init` using `f`.
+  ///
+  /// This is equivalent to calling [require](Self::require) for each task and folding the outputs manually.
+  #[inline]
+  fn require_fold<'t, T, H, B, F>(
+    &mut self,
+    tasks: impl IntoIterator<Item=&'t T>,
+    checker: H,
+    init: B,
+    mut f: F,
+  ) -> B where
+    T: Task,
+    H: OutputChecker<T::Output>,
+    F: FnMut(B, T::Output) -> B,
+  {
+    let mut accumulator = init;
+    for task in tasks {
+      let output = self.require(task, checker.clone());
+      accumulator = f(accumulator, output);
+    }
+    accumulator
+  }
+  /// Requires `task`, whose output is a [`Result`], using `checker` for consistency checking, creating a task
+  /// dependency and returning its consistent output. The dependency is created regardless of whether the output is
+  /// [`Ok`] or [`Err`].
+  ///
+  /// This is equivalent to [require](Self::require), but makes it explicit that the output is a result that can be
+  /// propagated with `?`, instead of matching on it.
+  #[inline]
+  fn require_ok<T, H, V, E>(&mut self, task: &T, checker: H) -> Result<V, E> where
+    T: Task<Output=Result<V, E>>,
+    H: OutputChecker<T::Output>,
+  {
+    self.require(task, checker)
+  }
+  /// Requires `task`, whose output is a [`Result`], using `checker` for consistency checking, creating a task
+  /// dependency and returning `Some(value)` if its consistent output is `Ok(value)`, or `None` if it is an error.
+  /// Useful for optional dependencies whose failure can be tolerated.
+  ///
+  /// The dependency is created regardless of whether the output is [`Ok`] or [`Err`], so that the requiring task is
+  /// re-executed when a failing dependency later succeeds (depending on `checker`).
+  #[inline]
+  fn require_optional<T, H, V, E>(&mut self, task: &T, checker: H) -> Option<V> where
+    T: Task<Output=Result<V, E>>,
+    H: OutputChecker<T::Output>,
+  {
+    self.require(task, checker).ok()
+  }
+  /// Requires `task` using `checker` for consistency checking, creating a task dependency and returning its consistent
+  /// output value, or `fallback` if making `task` consistent panicked. Useful for resilient builds where a panicking
+  /// dependency should not abort the entire build.
+  ///
+  /// A caught panic is reported with [`Tracker::execute_panicked`], `task` and the tasks interrupted by the panic are
+  /// reset, and the dependency is recorded as always inconsistent, so that the requiring task is re-executed, requiring
+  /// `task` again, in the next session. Panics that interrupt the build, such as those for
+  /// [build rule violations](BuildViolation) or an exceeded [maximum depth](Session::with_max_depth), are not caught.
+  ///
+  /// Uses [`catch_unwind`](std::panic::catch_unwind), so requires `task` to be [unwind safe](std::panic::UnwindSafe)
+  /// when borrowed.
+  #[cfg(feature = "panic_fallback")]
+  fn require_with_fallback<T, H>(&mut self, task: &T, checker: H, fallback: T::Output) -> T::Output where
+    T: Task + std::panic::RefUnwindSafe,
+    H: OutputChecker<T::Output>;
+  /// Requires `task` using `checker` for consistency checking, creating a task dependency and returning its output at
+  /// a fixpoint. Useful for iterative fixed-point computations such as dataflow analyses, where tasks (transitively)
+  /// require each other.
+  ///
+  /// When making `task` consistent requires `task` again, closing a cycle, the cyclic require returns a provisional
+  /// output instead of raising a [cyclic dependency violation](BuildViolation::CyclicDependency). The first provisional
+  /// output is `initial`. If a cycle was closed, the tasks in the cycle are re-executed with the output of `task` as
+  /// the next provisional output, until `checker` reports that the output is consistent with the provisional output, or
+  /// until `max_iters` iterations have been executed, after which the last output is returned.
+  ///
+  /// Convergence is the responsibility of the tasks in the cycle: they must be deterministic, and their outputs must
+  /// eventually stabilize when iterating from `initial`. For example, outputs of dataflow analyses should be monotone
+  /// over a lattice of finite height, with `initial` as its bottom element. A cyclic require creates no dependency, so
+  /// the cycle is iterated from `initial` again whenever one of its tasks is re-executed. Iterating executes the tasks
+  /// in the cycle more than once in the same build, which
+  /// [`DoubleExecutionGuardTracker`](tracker::double_execution::DoubleExecutionGuardTracker) reports.
+  ///
+  /// Tasks in the cycle should only be required through `task`, and `task` should only be required with this method.
+  /// Bottom-up builds do not iterate fixpoints when a resource change schedules a task in the cycle, so prefer
+  /// top-down builds for fixpoint computations.
+  fn require_fixpoint<T, H>(&mut self, task: &T, checker: H, initial: T::Output, max_iters: usize) -> T::Output where
+    T: Task,
+    H: OutputChecker<T::Output>;
+  /// Requires `key_task`, whose output is itself a task, then requires that task using `checker` for consistency
+  /// checking, returning its consistent output. This creates a task dependency to `key_task` using an
+  /// [`EqualsChecker`](task::EqualsChecker), and a task dependency to the task it outputs using `checker`.
+  ///
+  /// When the output of `key_task` changes, the requiring task is re-executed and requires the newly selected task
+  /// instead. The previously selected task is no longer a dependency.
+  #[inline]
+  fn require_indirect<K, T, H>(&mut self, key_task: &K, checker: H) -> T::Output where
+    K: Task<Output=T>,
+    T: Task,
+    H: OutputChecker<T::Output>,
+  {
+    let task = self.require(key_task, task::EqualsChecker);
+    self.require(&task, checker)
+  }
+  /// Requires `task` using `checker` for consistency checking, where the concrete types of `task` and `checker` are
+  /// only known as trait objects, creating a task dependency and returning its boxed consistent output. Create
+  /// `checker` with [`task_checker_obj`](trait_object::task_checker_obj).
+  ///
+  /// # Panics
+  ///
+  /// Panics if the type of `task` does not match the task type of `checker`.
+  fn require_dyn(&mut self, task: &dyn KeyObj, checker: Box<dyn TaskCheckerObj>) -> Box<dyn ValueObj>;
+  /// Requires each task in `tasks` using the checker returned by `checker_for` for the index of that task, where the
+  /// concrete types of tasks and checkers are only known as trait objects, creating a task dependency to each task and
+  /// returning their boxed consistent outputs in the same order as `tasks`.
+  ///
+  /// This is equivalent to calling [require_dyn](Self::require_dyn) for each task and collecting the outputs manually.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the type of a task does not match the task type of its checker.
+  #[inline]
+  fn require_all_dyn(
+    &mut self,
+    tasks: &[Box<dyn KeyObj>],
+    checker_for: impl Fn(usize) -> Box<dyn TaskCheckerObj>,
+  ) -> Vec<Box<dyn ValueObj>> {
+    tasks.iter().enumerate().map(|(i, task)| self.require_dyn(task.as_ref(), checker_for(i))).collect()
+  }
+  /// Returns `Some(output)` with the currently cached output of `task` if it has one, `None` otherwise. This does
+  /// *not* create a dependency to `task`, and does *not* execute nor check `task`.
+  ///
+  /// The returned output may be outdated or missing, and changes to it will not cause the current executing task to
+  /// be re-executed. Therefore, using this does *not* make the current executing task sound: only use it for
+  /// optimization hints (heuristics) where the result of the current executing task does not depend on the output.
+  fn peek_output<T: Task>(&self, task: &T) -> Option<T::Output>;
+  /// Returns the value memoized for `key` in the current session, first computing it with `compute` if no value of
+  /// type `V` was memoized for `key` yet. This does *not* create a task nor dependencies, avoiding their overhead for
+  /// tiny pure computations.
+  ///
+  /// The memoization map is session-local: it is shared by all tasks in the session, is cleared when the session ends,
+  /// and is not persisted. Because no dependencies are created, `compute` must be a pure function of `key`: it must not
+  /// read resources nor depend on other state that can change within a session.
+  fn memo_local<K: Key, V: Value>(&mut self, key: K, compute: impl FnOnce() -> V) -> V;
 
   /// Creates a read dependency to `resource` using `checker` for consistency checking, then returns a
   /// [reader](Resource::Reader) for reading the resource.
@@ -78,6 +267,23 @@ pub trait Context {
     T: ToOwned<Owned=R>,
     R: Resource,
     H: ResourceChecker<R>;
+  /// Creates a read dependency to each resource in `resources` using `checker` for consistency checking, then returns
+  /// [readers](Resource::Reader) for reading the resources, in the same order as `resources`. Stops at and returns the
+  /// first error.
+  ///
+  /// This is equivalent to calling [read](Self::read) for each resource and collecting the readers manually. Because
+  /// all readers are alive at the same time, this requires readers that do not borrow from the resource state.
+  #[inline]
+  fn read_many<R, H, D>(&mut self, resources: &[R], checker: H) -> Result<Vec<D>, H::Error> where
+    R: for<'rs> Resource<Reader<'rs>=D>,
+    H: ResourceChecker<R>,
+  {
+    let mut readers = Vec::with_capacity(resources.len());
+    for resource in resources {
+      readers.push(self.read(resource, checker.clone())?);
+    }
+    Ok(readers)
+  }
   /// Creates a [writer](Resource::Writer) for `resource`, runs `write_fn` with that writer, then creates a write
   /// dependency to `resource` using `checker` for consistency checking.
   fn write<T, R, H, F>(&mut self, resource: &T, checker: H, write_fn: F) -> Result<(), H::Error> where
@@ -85,6 +291,19 @@ pub trait Context {
     R: Resource,
     H: ResourceChecker<R>,
     F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>;
+  /// Writes to `resource` like [write](Self::write), returning whether the write changed `resource`:
+  /// [`WriteOutcome::Unchanged`] if the stamp of `resource` after writing is consistent with its stamp before writing
+  /// according to `checker`, [`WriteOutcome::Changed`] otherwise. The write dependency is created regardless. Useful
+  /// for writing tasks that want to avoid further work when a write was a no-op, such as writing identical content.
+  ///
+  /// Whether a write is a no-op depends on `checker`. For example, writing identical content to a file is a no-op for a
+  /// [hash checker](resource::file::hash_checker::HashChecker), but not for a
+  /// [modified checker](resource::file::ModifiedChecker) as writing changes the last modified date of the file.
+  fn try_write<T, R, H, F>(&mut self, resource: &T, checker: H, write_fn: F) -> Result<WriteOutcome, H::Error> where
+    T: ToOwned<Owned=R>,
+    R: Resource,
+    H: ResourceChecker<R>,
+    F: FnOnce(&mut R::Writer<'_>) -> Result<(), R::Error>;
 
   /// Creates a [writer](Resource::Writer) for `resource`. This does *not* create a dependency. After writing to the
   /// resource, call [written_to](Self::written_to) to create the dependency.
@@ -96,6 +315,24 @@ pub trait Context {
     T: ToOwned<Owned=R>,
     R: Resource,
     H: ResourceChecker<R>;
+  /// Creates a write dependency to `resource` using `checker` for consistency checking, where the concrete types of
+  /// `resource` and `checker` are only known as trait objects. Create `checker` with
+  /// [`resource_checker_obj`](trait_object::resource_checker_obj).
+  ///
+  /// # Panics
+  ///
+  /// Panics if the type of `resource` does not match the resource type of `checker`.
+  fn written_to_dyn(
+    &mut self,
+    resource: &dyn KeyObj,
+    checker: Box<dyn ResourceCheckerObj>,
+  ) -> Result<(), Box<dyn Error>>;
+
+  /// Runs `f` in a scope with `label`, grouping the dependencies created inside `f` in [trackers](tracker::Tracker),
+  /// for example for logging or visualization. Returns the output of `f`.
+  ///
+  /// Scopes are purely informative: they do not affect incrementality.
+  fn scope<R>(&mut self, label: &'static str, f: impl FnOnce(&mut Self) -> R) -> R;
 }
 
 /// Consistency checker for task outputs of type `O`, producing and checking output stamps. For example, the
@@ -125,6 +362,38 @@ pub trait Resource: Key {
   fn read<'rs, RS: ResourceState<Self>>(&self, state: &'rs mut RS) -> Result<Self::Reader<'rs>, Self::Error>;
   /// Creates a writer for this resource, with access to global mutable [resource `state`](ResourceState).
   fn write<'r, RS: ResourceState<Self>>(&'r self, state: &'r mut RS) -> Result<Self::Writer<'r>, Self::Error>;
+
+  /// Creates a reader like [read](Self::read), but makes another attempt after each failed attempt for which `retry`
+  /// returns `true`. The error of each failed attempt is first wrapped with `wrap`, and the wrapped error of the last
+  /// attempt is returned.
+  ///
+  /// Makes a single attempt by default, because a reader may borrow `state` for `'rs`, which prevents passing `state`
+  /// to another attempt. Resources whose readers do not borrow `state` should override this method with
+  /// [`resource::retry`], as [retry policies](Session::with_resource_retry) have no effect on them otherwise.
+  #[inline]
+  fn read_retrying<'rs, RS: ResourceState<Self>, W>(
+    &self,
+    state: &'rs mut RS,
+    wrap: impl FnMut(Self::Error) -> W,
+    _retry: impl FnMut(&W) -> bool,
+  ) -> Result<Self::Reader<'rs>, W> {
+    self.read(state).map_err(wrap)
+  }
+  /// Creates a writer like [write](Self::write), but makes another attempt after each failed attempt for which `retry`
+  /// returns `true`. The error of each failed attempt is first wrapped with `wrap`, and the wrapped error of the last
+  /// attempt is returned.
+  ///
+  /// Makes a single attempt by default, for the same reason as [read_retrying](Self::read_retrying). Resources whose
+  /// writers do not borrow `state` should override this method with [`resource::retry`].
+  #[inline]
+  fn write_retrying<'r, RS: ResourceState<Self>, W>(
+    &'r self,
+    state: &'r mut RS,
+    wrap: impl FnMut(Self::Error) -> W,
+    _retry: impl FnMut(&W) -> bool,
+  ) -> Result<Self::Writer<'r>, W> {
+    self.write(state).map_err(wrap)
+  }
 }
 
 /// Provides access to global mutable state for [resources](Resource) of type `R`. Each unique resource type `R` has
@@ -153,6 +422,56 @@ pub trait ResourceState<R> {
   /// Gets the mutable state as `S` or sets a default. If no state was set, or if it is not of type `S`, first sets the
   /// state to `S::default()`. Then returns the state as `&mut state`.
   fn get_or_set_default_mut<S: Default + Any>(&mut self) -> &mut S;
+
+  /// Gets the state entry of type `S` for in-place manipulation, mirroring [`HashMap::entry`].
+  ///
+  /// [`HashMap::entry`]: std::collections::HashMap::entry
+  fn entry<S: Any>(&mut self) -> StateEntry<'_, S>;
+}
+
+/// Entry for [resource state](ResourceState) of type `S`, obtained with [`ResourceState::entry`].
+pub struct StateEntry<'a, S> {
+  entry: hash_map::Entry<'a, TypeId, Box<dyn Any>>,
+  _state: PhantomData<S>,
+}
+impl<'a, S: Any> StateEntry<'a, S> {
+  #[inline]
+  pub(crate) fn new(entry: hash_map::Entry<'a, TypeId, Box<dyn Any>>) -> Self {
+    Self { entry, _state: PhantomData }
+  }
+
+  /// Ensures the state is set by setting it to `f()` if no state was set, or if it is not of type `S`. Then returns
+  /// the state as `&mut state`.
+  #[inline]
+  pub fn or_insert_with(self, f: impl FnOnce() -> S) -> &'a mut S {
+    let value = match self.entry {
+      hash_map::Entry::Occupied(entry) => {
+        let value = entry.into_mut();
+        if !value.as_ref().is::<S>() {
+          *value = Box::new(f());
+        }
+        value
+      }
+      hash_map::Entry::Vacant(entry) => entry.insert(Box::new(f())),
+    };
+    value.as_mut().downcast_mut::<S>().expect("BUG: state is not of the type it was just set to")
+  }
+  /// Ensures the state is set by setting it to `S::default()` if no state was set, or if it is not of type `S`. Then
+  /// returns the state as `&mut state`.
+  #[inline]
+  pub fn or_default(self) -> &'a mut S where S: Default {
+    self.or_insert_with(S::default)
+  }
+  /// Modifies the state with `f` if the state is set and of type `S`, then returns this entry.
+  #[inline]
+  pub fn and_modify(mut self, f: impl FnOnce(&mut S)) -> Self {
+    if let hash_map::Entry::Occupied(entry) = &mut self.entry {
+      if let Some(state) = entry.get_mut().as_mut().downcast_mut::<S>() {
+        f(state);
+      }
+    }
+    self
+  }
 }
 
 /// Consistency checker for resources, producing and checking resource stamps. For example, for filesystem resources, a
@@ -194,9 +513,62 @@ pub trait ResourceChecker<R: Resource>: Key {
     state: &mut RS,
     stamp: &Self::Stamp,
   ) -> Result<Option<impl Debug>, Self::Error>;
+  /// Checks whether `resource` is inconsistent w.r.t. `old_stamp`, with access to `state`. Returns `Some(change)`
+  /// describing the change from `old_stamp` to the inconsistency when inconsistent, `None` when consistent.
+  ///
+  /// The default implementation [checks](Self::check) `resource` and debug formats `old_stamp` and the inconsistency.
+  #[inline]
+  fn describe_change<RS: ResourceState<R>>(
+    &self,
+    resource: &R,
+    state: &mut RS,
+    old_stamp: &Self::Stamp,
+  ) -> Result<Option<Change>, Self::Error> {
+    let change = self.check(resource, state, old_stamp)?
+      .map(|inconsistency| Change { from: format!("{:?}", old_stamp), to: format!("{:?}", inconsistency) });
+    Ok(change)
+  }
+
+  /// Returns `true` if `resource` can be changed externally, outside of builds, and must therefore always be checked.
+  /// Returns `true` by default.
+  ///
+  /// Return `false` if resources checked by this checker can only change by being written to in a build, for example
+  /// for in-memory resources. Then, bottom-up builds skip checking read dependencies to resources that were not
+  /// written to in the current session, as they cannot have changed.
+  #[inline]
+  fn checks_external_changes(&self) -> bool { true }
 
   /// Wraps a [resource `error`](Resource::Error) into [`Self::Error`].
+  ///
+  /// Invoked for the error of every failed read or write attempt, not only for the error that is eventually returned,
+  /// so that the wrapped error can be [classified](Self::classify_error) to decide whether to
+  /// [retry](Session::with_resource_retry). Therefore, wrapping should be cheap and free of side effects.
   fn wrap_error(&self, error: R::Error) -> Self::Error;
+
+  /// Classifies `error` as [transient](ErrorKind::Transient) or [permanent](ErrorKind::Permanent). Reads and writes
+  /// are only [retried](Session::with_resource_retry) on transient errors, and errors produced during dependency
+  /// checks are tagged with their classification in
+  /// [`Session::classified_dependency_check_errors`].
+  ///
+  /// Returns [`ErrorKind::Transient`] by default, so that all errors are retried.
+  #[inline]
+  fn classify_error(&self, _error: &Self::Error) -> ErrorKind { ErrorKind::Transient }
+}
+
+/// Description of a change to a resource, found by [`ResourceChecker::describe_change`], for example for logging why a
+/// task was rebuilt.
+#[derive(Default, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Change {
+  /// Debug formatted stamp before the change.
+  pub from: String,
+  /// Debug formatted inconsistency (usually the new stamp) after the change.
+  pub to: String,
+}
+impl Display for Change {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} → {}", self.from, self.to)
+  }
 }
 
 
@@ -249,6 +621,63 @@ impl<A: Tracker> Pie<A> {
   pub fn resource_state_mut<R: Resource>(&mut self) -> &mut impl ResourceState<R> {
     self.0.resource_state_mut()
   }
+  /// Sets the [resource state](ResourceState) for [resource](Resource) type [`R`] to `state`, replacing any existing
+  /// state. Useful for configuring initial state, such as a mock clock or a seeded in-memory store, before the first
+  /// session.
+  #[inline]
+  #[must_use]
+  pub fn with_resource_state<R: Resource, S: Any>(mut self, state: S) -> Self {
+    self.resource_state_mut::<R>().set(state);
+    self
+  }
+
+  /// Resets all cached task outputs and removes all dependencies, forcing every task to be executed again when it is
+  /// next required. Unlike creating a new [`Pie`] instance, the [resource state](ResourceState) and tracker are
+  /// retained.
+  #[inline]
+  pub fn reset_outputs(&mut self) {
+    self.0.reset_outputs()
+  }
+
+  /// Gets all tasks in the dependency graph, sorted by their [`Debug`] representation so that the order is
+  /// reproducible across runs, for diagnostics.
+  #[inline]
+  pub fn tasks(&self) -> Vec<&dyn KeyObj> {
+    self.0.tasks()
+  }
+  /// Gets all resources in the dependency graph, sorted by their [`Debug`] representation so that the order is
+  /// reproducible across runs, for diagnostics.
+  #[inline]
+  pub fn resources(&self) -> Vec<&dyn KeyObj> {
+    self.0.resources()
+  }
+
+  /// Gets the [statistics](PieStats) accumulated over all sessions that have ended.
+  #[inline]
+  #[must_use]
+  pub fn statistics(&self) -> PieStats {
+    self.0.statistics()
+  }
+
+  /// Verifies that `plan`, [exported from a session](Session::export_plan) of this instance, would be reproduced by the
+  /// dependency graph of this instance in the current environment, returning the [divergences](plan::PlanDivergence)
+  /// from `plan`. Returns an empty vector if `plan` would be reproduced.
+  ///
+  /// Each step of `plan` is compared against the task with the same [`Debug`] representation in the dependency graph
+  /// of this instance: its dependencies must be the same, with the same stamps. Furthermore, resource dependencies are
+  /// checked against the current environment, reporting resources that have changed since their dependency was
+  /// created. Task dependencies are not checked directly, as they are verified by the steps of the required tasks.
+  ///
+  /// Verification does not execute tasks nor change the dependency graph, and plans only contain [`Debug`]
+  /// representations which cannot be turned back into tasks and resources. Therefore, plans can only be verified by the
+  /// instance they were exported from, or an instance with the same dependency graph. Verifying a plan with another
+  /// instance, such as a new instance on another machine, reports an
+  /// [unknown task divergence](plan::PlanDivergence::UnknownTask) for every step. To verify a build on another machine,
+  /// build there and compare the exported plans instead.
+  #[inline]
+  pub fn verify_plan(&mut self, plan: &plan::BuildPlan) -> Vec<plan::PlanDivergence> {
+    self.0.verify_plan(plan)
+  }
 }
 
 /// A session in which builds are executed.
@@ -260,6 +689,100 @@ impl<'p> Session<'p> {
   pub fn require<T: Task>(&mut self, task: &T) -> T::Output {
     self.0.require(task)
   }
+  /// Requires `task`, returning `Ok(output)` with its consistent output, or `Err(violation)` if a
+  /// [build rule was violated](BuildViolation), instead of panicking like [require](Self::require).
+  ///
+  /// When a violation is returned, tasks that were executing are interrupted, and reset so that they are executed again
+  /// when required. Other panics, such as those of tasks themselves, are propagated as usual.
+  #[inline]
+  pub fn try_require<T: Task>(&mut self, task: &T) -> Result<T::Output, BuildViolation> {
+    self.0.try_require(task)
+  }
+  /// Requires `task` like [require](Self::require), returning `(output, changed)` with its consistent output, and
+  /// whether `output` differs from `previous`. Useful for callers that cache the previous output externally, for
+  /// example to only update a UI when the output has changed.
+  #[inline]
+  pub fn require_if_changed<T: Task>(&mut self, task: &T, previous: &T::Output) -> (T::Output, bool) where
+    T::Output: Eq
+  {
+    let output = self.0.require(task);
+    let changed = output != *previous;
+    (output, changed)
+  }
+
+  /// Requires `task` like [require](Self::require), but with a budget of `max_executions` task executions, returning
+  /// [`RequireOutcome::Complete`] with its consistent output if `task` was made consistent within budget, or
+  /// [`RequireOutcome::Partial`] if the budget was exhausted before that. Useful for bounding the cost of rebuilds, for
+  /// example per frame in an interactive application.
+  ///
+  /// Executions are counted when they finish. Once `max_executions` executions have finished, the budget is exhausted,
+  /// and the next task that needs to be executed is deferred instead: tasks that are still executing are interrupted,
+  /// and reset so that they are executed again when required. Because required tasks finish before the tasks that
+  /// require them, the number of executions can exceed `max_executions` by the number of tasks that were executing when
+  /// the budget was exhausted, but every session with a non-zero budget finishes at least one execution.
+  ///
+  /// Tasks that finished executing keep their new outputs, so requiring `task` again in a later session continues with
+  /// the deferred work. Requiring `task` with a budget of `0` only completes if `task` is already consistent.
+  ///
+  /// Tasks are interrupted by unwinding, so tasks that catch panics (e.g., with [`catch_unwind`]) must resume unwinding
+  /// for payloads they do not handle.
+  ///
+  /// [`catch_unwind`]: std::panic::catch_unwind
+  #[inline]
+  pub fn require_with_budget<T: Task>(&mut self, task: &T, max_executions: usize) -> RequireOutcome<T::Output> {
+    self.0.require_with_budget(task, max_executions)
+  }
+
+  /// Requires each task of `tasks`, returning an iterator that yields `(index, output)` with the index of the task in
+  /// `tasks` and its consistent output, as soon as that task is made consistent. Useful for displaying results
+  /// progressively, for example in a UI.
+  ///
+  /// Tasks are required lazily, in order of `tasks`, as the iterator is advanced. Therefore, outputs are yielded in
+  /// completion order, which is the order of `tasks` in this sequential top-down build. Tasks that are not yet yielded
+  /// when the iterator is dropped are not required. Each task is required as in [require](Self::require).
+  #[inline]
+  pub fn require_stream<'s, T: Task>(
+    &'s mut self,
+    tasks: &'s [T],
+  ) -> impl Iterator<Item=(usize, T::Output)> + use<'p, 's, T> {
+    tasks.iter().enumerate().map(move |(index, task)| (index, self.0.require(task)))
+  }
+
+  /// Requires all tasks of `graph` in topological order of its intended dependencies, returning their consistent
+  /// outputs by task identifier. Outputs can be downcast with [`as_any`](ValueObj::as_any).
+  ///
+  /// The graph is only a scheduling hint: tasks are required as usual and thus still checked incrementally, and
+  /// dependencies that tasks create during execution are still tracked. Consequently, a task that requires a task that
+  /// is not yet consistent due to a missing intended dependency still gets a consistent output.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the task type of a task checker in `graph` does not match its task.
+  #[inline]
+  pub fn require_graph(&mut self, graph: &task::TaskGraph) -> HashMap<task::TaskId, Box<dyn ValueObj>> {
+    self.0.require_graph(graph)
+  }
+
+  /// Invalidates `task` by removing its cached output while keeping its dependencies, forcing it to be executed when
+  /// it is required next, even if all its dependencies are consistent. Does nothing if `task` was never required.
+  ///
+  /// Use this when it is known that the output of `task` is stale due to a change that is not tracked by its
+  /// dependencies, such as an external signal. Dependents of `task` are not invalidated, but are checked against the
+  /// new output of `task` as usual when they are required. Bottom-up builds do not schedule invalidated tasks, but
+  /// execute them when they are required by scheduled tasks.
+  ///
+  /// Invalidating a task that is actually consistent does not break incrementality: it only causes redundant work.
+  #[inline]
+  pub fn invalidate<T: Task>(&mut self, task: &T) {
+    self.0.invalidate(task)
+  }
+  /// [Invalidates](Self::invalidate) all tasks that were ever required for which `predicate` returns `true`, returning
+  /// the number of invalidated tasks. Useful for invalidating a family of tasks, such as all tasks of a type or all
+  /// tasks that process files under a directory.
+  #[inline]
+  pub fn invalidate_matching(&mut self, predicate: impl Fn(&dyn KeyObj) -> bool) -> usize {
+    self.0.invalidate_matching(predicate)
+  }
 
   /// Creates a bottom-up build. Call [schedule_tasks_affected_by](BottomUpBuild::schedule_tasks_affected_by) for each
   /// changed resource to schedule tasks affected by changed resources.
@@ -280,7 +803,219 @@ impl<'p> Session<'p> {
   pub fn dependency_check_errors(&self) -> impl Iterator<Item=&dyn Error> + ExactSizeIterator {
     self.0.dependency_check_errors()
   }
+  /// Gets all errors produced during dependency checks, along with their
+  /// [classification](ResourceChecker::classify_error).
+  #[inline]
+  pub fn classified_dependency_check_errors(&self) -> impl ExactSizeIterator<Item=(&dyn Error, ErrorKind)> {
+    self.0.classified_dependency_check_errors()
+  }
+
+  /// Gets the resources that were detected to be inconsistent (changed) while checking dependencies in this session.
+  ///
+  /// Only resources that were actually checked in this session are included. Resources that were not checked, for
+  /// example because a task was already found to be inconsistent due to an earlier dependency, or because checking
+  /// the resource failed with an error, are not included even if they have changed.
+  #[inline]
+  #[must_use]
+  pub fn changed_resources(&self) -> Vec<Box<dyn KeyObj>> {
+    self.0.changed_resources()
+  }
+
+  /// Exports the [build plan](plan::BuildPlan) of this session: the tasks that were executed in this session, in the
+  /// order they finished executing, with the stamps of the dependencies they created. Tasks that were consistent are
+  /// not included. Use [`Pie::verify_plan`] to verify that the plan would be reproduced.
+  #[inline]
+  #[must_use]
+  pub fn export_plan(&self) -> plan::BuildPlan {
+    self.0.export_plan()
+  }
+
+  /// Sets the retry `policy` for [reading](Resource::read) and [writing](Resource::write) resources in this session.
+  /// Failing reads and writes are retried according to `policy` before giving up and returning the error of the last
+  /// attempt. Errors that the checker [classifies](ResourceChecker::classify_error) as
+  /// [permanent](ErrorKind::Permanent) are returned immediately without retrying.
+  ///
+  /// Only resources that implement [`Resource::read_retrying`] and [`Resource::write_retrying`] are retried, such as
+  /// filesystem paths. Resources whose readers or writers borrow the resource state, such as map resources, always
+  /// make a single attempt.
+  #[inline]
+  #[must_use]
+  pub fn with_resource_retry(mut self, policy: RetryPolicy) -> Self {
+    self.0.resource_retry = policy;
+    self
+  }
+
+  /// Sets the maximum depth of nested task requires and checks in top-down builds of this session to `max_depth`. By
+  /// default, the depth is unlimited, meaning that deep task graphs can overflow the stack.
+  ///
+  /// When the maximum depth is exceeded, a [`BuildViolation::DepthExceeded`] violation is raised instead of overflowing
+  /// the stack: [require](Self::require) panics, and [try_require](Self::try_require) returns it as an error.
+  #[inline]
+  #[must_use]
+  pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+    self.0.max_depth = Some(max_depth);
+    self
+  }
+
+  /// Sets the require `interceptor` of this session, which is called with the task of every require in this session,
+  /// including the tasks required through this session itself. When `interceptor` returns `Some(output)`, the require
+  /// is short-circuited to `output` without checking nor executing the task, for example to stub the outputs of the
+  /// dependencies of a task to test that task in isolation. When it returns `None`, the task is required as usual.
+  ///
+  /// A dependency to an intercepted task is still created, with a stamp of `output`, for reporting. The cached output
+  /// of the intercepted task is not changed.
+  ///
+  /// This is a testing aid that can produce unsound results if misused: outputs of tasks that depend on stubbed
+  /// outputs are cached as if they depend on the real outputs. Dependency checks in later sessions compare against the
+  /// real outputs, but only if the checker of the dependency detects the difference between the stubbed output and the
+  /// real output.
+  ///
+  /// # Panics
+  ///
+  /// Requiring a task in this session panics if `interceptor` returns an output whose type does not match the output
+  /// type of the task.
+  #[inline]
+  #[must_use]
+  pub fn with_require_interceptor(
+    mut self,
+    interceptor: impl FnMut(&dyn KeyObj) -> Option<Box<dyn ValueObj>> + 'static,
+  ) -> Self {
+    self.0.require_interceptor = Some(Box::new(interceptor));
+    self
+  }
+}
+
+/// Outcome of [requiring a task with an execution budget](Session::require_with_budget).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum RequireOutcome<O> {
+  /// The required task was made consistent within the execution budget, with its consistent output.
+  Complete(O),
+  /// The execution budget was exhausted before the required task was made consistent. The remaining work is deferred.
+  Partial {
+    /// Whether the execution budget was exhausted, which is currently always `true`.
+    budget_exhausted: bool,
+  },
+}
+
+/// Outcome of [writing to a resource](Context::try_write), determined by comparing the stamps of the resource before
+/// and after writing.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum WriteOutcome {
+  /// The write changed the resource.
+  Changed,
+  /// The write did not change the resource, for example because identical content was written.
+  Unchanged,
+}
+
+/// Policy for retrying [resource](Resource) [reads](Resource::read) and [writes](Resource::write) that fail, for
+/// example due to transient network or filesystem errors.
+///
+/// The default policy makes a single attempt, thus never retrying.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct RetryPolicy {
+  /// Maximum number of attempts, including the first one. Both `0` and `1` disable retrying.
+  pub max_attempts: usize,
+  /// Duration to wait before the first retry, which is doubled before each subsequent retry.
+  pub backoff: Duration,
+}
+impl Default for RetryPolicy {
+  #[inline]
+  fn default() -> Self { Self { max_attempts: 1, backoff: Duration::ZERO } }
+}
+
+/// Classification of [resource checker errors](ResourceChecker::classify_error).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ErrorKind {
+  /// The error may not occur again when retrying, for example because a file was temporarily locked or a request
+  /// timed out.
+  Transient,
+  /// The error will occur again when retrying, for example because a file does not exist or permission was denied.
+  Permanent,
+}
+
+/// Statistics accumulated over the lifetime of a [`Pie`] instance. The statistics of a [session](Session) are
+/// accumulated when the session ends, that is, when it is dropped.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PieStats {
+  /// Number of sessions that have ended.
+  pub session_count: u64,
+  /// Number of task executions, in both top-down and bottom-up builds.
+  pub total_executions: u64,
+  /// Number of times a required task was found consistent, reusing its cached output instead of executing it, in both
+  /// top-down and bottom-up builds.
+  pub total_cache_hits: u64,
+}
+
+/// Violation of a build rule, returned by [`Session::try_require`] instead of panicking.
+#[derive(Clone, Debug)]
+pub enum BuildViolation {
+  /// A task requires a task that directly or indirectly requires it.
+  CyclicDependency {
+    /// Task that was executing when the cycle was detected.
+    requiring_task: Box<dyn KeyObj>,
+    /// Task that was required, closing the cycle.
+    required_task: Box<dyn KeyObj>,
+  },
+  /// A task reads from a resource that is written to by another task, without a (transitive) dependency from the
+  /// reading task to the writing task.
+  HiddenDependency {
+    /// Resource that is read from and written to.
+    resource: Box<dyn KeyObj>,
+    /// Task that reads from the resource.
+    reading_task: Box<dyn KeyObj>,
+    /// Task that writes to the resource.
+    writing_task: Box<dyn KeyObj>,
+  },
+  /// A task writes to a resource that was previously written to by another task.
+  OverlappingWrite {
+    /// Resource that is written to by both tasks.
+    resource: Box<dyn KeyObj>,
+    /// Task that was executing when the overlapping write was detected.
+    writing_task: Box<dyn KeyObj>,
+    /// Task that previously wrote to the resource.
+    previous_writing_task: Box<dyn KeyObj>,
+  },
+  /// A [precondition](Task::precondition) of a task failed, so it was not executed.
+  PreconditionFailed {
+    /// Task whose precondition failed.
+    task: Box<dyn KeyObj>,
+    /// Message describing the failed precondition.
+    message: String,
+  },
+  /// Making a task consistent exceeded the [maximum depth](Session::with_max_depth) of nested task requires and checks.
+  DepthExceeded {
+    /// Task that was being made consistent when the maximum depth was exceeded.
+    task: Box<dyn KeyObj>,
+    /// The maximum depth that was exceeded.
+    max_depth: usize,
+  },
+}
+impl Display for BuildViolation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BuildViolation::CyclicDependency { requiring_task, required_task } => {
+        write!(f, "Cyclic task dependency; current executing task '{:?}' is requiring task '{:?}' which directly or \
+          indirectly requires the current executing task", requiring_task, required_task)
+      }
+      BuildViolation::HiddenDependency { resource, reading_task, writing_task } => {
+        write!(f, "Hidden dependency; resource '{:?}' is read by task '{:?}' and written to by task '{:?}', without a \
+          dependency from the reading task to the writing task", resource, reading_task, writing_task)
+      }
+      BuildViolation::OverlappingWrite { resource, writing_task, previous_writing_task } => {
+        write!(f, "Overlapping write; resource '{:?}' is written to by the current executing task '{:?}' that was \
+          previously written to by task: {:?}", resource, writing_task, previous_writing_task)
+      }
+      BuildViolation::PreconditionFailed { task, message } => {
+        write!(f, "Precondition failed; task '{:?}' was not executed: {}", task, message)
+      }
+      BuildViolation::DepthExceeded { task, max_depth } => {
+        write!(f, "Maximum depth exceeded; making task '{:?}' consistent exceeds the maximum depth of {} nested task \
+          requires and checks", task, max_depth)
+      }
+    }
+  }
 }
+impl Error for BuildViolation {}
 
 #[repr(transparent)]
 pub struct BottomUpBuild<'p, 's>(pie::BottomUpBuildInternal<'p, 's>);
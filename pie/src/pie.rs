@@ -1,22 +1,29 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
 
-use crate::{Context, OutputChecker, Resource, ResourceChecker, ResourceState, Session, Task};
+use crate::{BuildViolation, Change, Context, ErrorKind, OutputChecker, PieStats, RequireOutcome, Resource,
+  ResourceChecker, ResourceState, RetryPolicy, Session, Task};
+use crate::context::{BudgetUnwind, ChangedResources, Fixpoint, Violations, ViolationUnwind};
 use crate::context::bottom_up::BottomUpContext;
 use crate::context::top_down::TopDownContext;
-use crate::store::{Store, TaskNode};
-use crate::task::AlwaysConsistent;
+use crate::dependency::Dependency;
+use crate::plan::{BuildPlan, PlanDependency, PlanDependencyKind, PlanDivergence, PlanStep};
+use crate::store::{ResourceNode, Store, TaskNode};
+use crate::task::{AlwaysConsistent, TaskGraph, TaskId};
 use crate::tracker::Tracker;
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
 use crate::trait_object::collection::TypeToAnyMap;
+use crate::trait_object::task::TaskObj;
 
 /// Internals for [Pie](crate::Pie).
 pub struct PieInternal<A> {
   store: Store,
   tracker: A,
   resource_state: TypeToAnyMap,
+  statistics: PieStats,
 }
 impl Default for PieInternal<()> {
   #[inline]
@@ -29,6 +36,7 @@ impl<A: Tracker> PieInternal<A> {
       store: Store::default(),
       tracker,
       resource_state: TypeToAnyMap::default(),
+      statistics: PieStats::default(),
     }
   }
 
@@ -46,8 +54,96 @@ impl<A: Tracker> PieInternal<A> {
   pub fn resource_state<R: Resource>(&self) -> &impl ResourceState<R> { &self.resource_state }
   #[inline]
   pub fn resource_state_mut<R: Resource>(&mut self) -> &mut impl ResourceState<R> { &mut self.resource_state }
+
+  #[inline]
+  pub fn reset_outputs(&mut self) { self.store.reset_all_tasks() }
+
+  #[inline]
+  pub fn tasks(&self) -> Vec<&dyn KeyObj> {
+    self.store.get_tasks_sorted().into_iter().map(|t| t.as_key_obj()).collect()
+  }
+  #[inline]
+  pub fn resources(&self) -> Vec<&dyn KeyObj> { self.store.get_resources_sorted() }
+
+  #[inline]
+  pub fn statistics(&self) -> PieStats { self.statistics }
+
+  pub fn verify_plan(&mut self, plan: &BuildPlan) -> Vec<PlanDivergence> {
+    let nodes: HashMap<String, TaskNode> = self.store.get_tasks_sorted().into_iter()
+      .filter_map(|t| self.store.get_task_node(t).map(|n| (format!("{:?}", t), n)))
+      .collect();
+    let mut divergences = Vec::new();
+    for step in &plan.steps {
+      let Some(node) = nodes.get(&step.task) else {
+        divergences.push(PlanDivergence::UnknownTask { task: step.task.clone() });
+        continue;
+      };
+      let dependencies: Vec<_> = self.store.get_dependencies_from_task(node)
+        .filter_map(|d| plan_dependency(d).map(|p| (p, d)))
+        .collect();
+      for planned in &step.dependencies {
+        let task = || step.task.clone();
+        let dependency = || planned.clone();
+        let Some((current, dependency_obj)) = dependencies.iter()
+          .find(|(p, _)| p.kind == planned.kind && p.target == planned.target) else {
+          divergences.push(PlanDivergence::MissingDependency { task: task(), dependency: dependency() });
+          continue;
+        };
+        if current.stamp != planned.stamp {
+          let current = current.stamp.clone();
+          divergences.push(PlanDivergence::ChangedStamp { task: task(), dependency: dependency(), current });
+          continue;
+        }
+        let (Dependency::Read(d) | Dependency::Write(d)) = dependency_obj else {
+          continue;
+        };
+        match d.describe_change(&mut self.resource_state) {
+          Ok(None) => {}
+          Ok(Some(change)) => {
+            let current = change.to;
+            divergences.push(PlanDivergence::ChangedStamp { task: task(), dependency: dependency(), current });
+          }
+          Err(error) => {
+            let error = error.to_string();
+            divergences.push(PlanDivergence::CheckFailed { task: task(), dependency: dependency(), error });
+          }
+        }
+      }
+      for (current, _) in dependencies {
+        if !step.dependencies.iter().any(|p| p.kind == current.kind && p.target == current.target) {
+          divergences.push(PlanDivergence::UnplannedDependency { task: step.task.clone(), dependency: current });
+        }
+      }
+    }
+    divergences
+  }
+}
+
+/// Converts `dependency` into a [plan dependency](PlanDependency), returning `None` for reserved dependencies. The
+/// stamp of a merged resource dependency is formatted as the list of the stamps of all its dependencies.
+#[inline]
+fn plan_dependency(dependency: &Dependency) -> Option<PlanDependency> {
+  let (kind, target, stamp) = match dependency {
+    Dependency::ReservedRequire => return None,
+    Dependency::Require(d) => (PlanDependencyKind::Require, d.task(), format!("{:?}", d.stamp())),
+    Dependency::Read(d) => (PlanDependencyKind::Read, d.resource(), format_stamps(d.stamps())),
+    Dependency::Write(d) => (PlanDependencyKind::Write, d.resource(), format_stamps(d.stamps())),
+  };
+  Some(PlanDependency { kind, target: format!("{:?}", target), stamp })
 }
 
+/// Formats `stamps` of a resource dependency: as the single stamp if there is one, or as a list otherwise.
+#[inline]
+fn format_stamps(stamps: Vec<&dyn ValueObj>) -> String {
+  match stamps.as_slice() {
+    [stamp] => format!("{:?}", stamp),
+    stamps => format!("{:?}", stamps),
+  }
+}
+
+/// Interceptor of requires, returning `Some(output)` to short-circuit a require of a task to `output`.
+pub type RequireInterceptor = Box<dyn FnMut(&dyn KeyObj) -> Option<Box<dyn ValueObj>>>;
+
 /// Internals for [`Session`].
 pub struct SessionInternal<'p> {
   pub store: &'p mut Store,
@@ -55,7 +151,20 @@ pub struct SessionInternal<'p> {
   pub tracker: Tracking<'p>,
   pub current_executing_task: Option<TaskNode>,
   pub consistent: HashSet<TaskNode>,
-  pub dependency_check_errors: Vec<Box<dyn Error>>,
+  pub dependency_check_errors: Vec<(Box<dyn Error>, ErrorKind)>,
+  pub resource_retry: RetryPolicy,
+  pub changed_resources: ChangedResources,
+  pub written_resources: HashSet<ResourceNode>,
+  pub executed_tasks: Vec<TaskNode>,
+  pub max_depth: Option<usize>,
+  pub depth: usize,
+  pub execution_budget: Option<usize>,
+  pub require_interceptor: Option<RequireInterceptor>,
+  pub memo: HashMap<Box<dyn KeyObj>, Box<dyn ValueObj>>,
+  pub violations: Violations,
+  pub fixpoints: Vec<Fixpoint>,
+  pub statistics: PieStats,
+  pie_statistics: &'p mut PieStats,
 }
 impl<'p> SessionInternal<'p> {
   #[inline]
@@ -67,6 +176,19 @@ impl<'p> SessionInternal<'p> {
       current_executing_task: None,
       consistent: HashSet::default(),
       dependency_check_errors: Vec::default(),
+      resource_retry: RetryPolicy::default(),
+      changed_resources: ChangedResources::default(),
+      written_resources: HashSet::default(),
+      executed_tasks: Vec::default(),
+      max_depth: None,
+      depth: 0,
+      execution_budget: None,
+      require_interceptor: None,
+      memo: HashMap::default(),
+      violations: Violations::default(),
+      fixpoints: Vec::default(),
+      statistics: PieStats::default(),
+      pie_statistics: &mut pie.statistics,
     }
   }
 
@@ -81,6 +203,95 @@ impl<'p> SessionInternal<'p> {
     output
   }
 
+  pub fn try_require<T: Task>(&mut self, task: &T) -> Result<T::Output, BuildViolation> {
+    self.current_executing_task = None;
+    self.violations.as_errors = true;
+
+    let build_end = self.tracker.build();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+      let mut context = TopDownContext::new(self);
+      context.require(task, AlwaysConsistent)
+    }));
+    build_end(&mut self.tracker);
+
+    self.violations.as_errors = false;
+    match result {
+      Ok(output) => Ok(output),
+      Err(payload) if payload.is::<ViolationUnwind>() => {
+        self.reset_after_unwind();
+        Err(self.violations.raised.take().expect("BUG: unwound for a violation without a raised violation"))
+      }
+      Err(payload) => resume_unwind(payload),
+    }
+  }
+
+  pub fn require_with_budget<T: Task>(&mut self, task: &T, max_executions: usize) -> RequireOutcome<T::Output> {
+    self.current_executing_task = None;
+    self.execution_budget = Some(max_executions);
+
+    let build_end = self.tracker.build();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+      let mut context = TopDownContext::new(self);
+      context.require(task, AlwaysConsistent)
+    }));
+    build_end(&mut self.tracker);
+
+    self.execution_budget = None;
+    match result {
+      Ok(output) => RequireOutcome::Complete(output),
+      Err(payload) if payload.is::<BudgetUnwind>() => {
+        self.reset_after_unwind();
+        RequireOutcome::Partial { budget_exhausted: true }
+      }
+      Err(payload) => resume_unwind(payload),
+    }
+  }
+
+  /// Unwinding interrupted all executing tasks: reset them and their partially created dependencies, and abandon their
+  /// fixpoint iterations.
+  fn reset_after_unwind(&mut self) {
+    self.current_executing_task = None;
+    self.depth = 0;
+    for fixpoint in self.fixpoints.drain(..) {
+      for src in &fixpoint.stale_requirers {
+        self.store.add_cyclic_requirer(src, &fixpoint.node);
+      }
+    }
+    self.store.reset_tasks_without_output();
+  }
+
+  pub fn require_graph(&mut self, graph: &TaskGraph) -> HashMap<TaskId, Box<dyn ValueObj>> {
+    self.current_executing_task = None;
+
+    let build_end = self.tracker.build();
+    let mut context = TopDownContext::new(self);
+    let outputs = graph.iter_topo()
+      .map(|(id, task, checker)| (id, checker.require_top_down(&mut context, task)))
+      .collect();
+    build_end(&mut self.tracker);
+    outputs
+  }
+
+  #[inline]
+  pub fn invalidate(&mut self, task: &dyn TaskObj) {
+    if let Some(node) = self.store.get_task_node(task) {
+      self.store.remove_task_output(&node);
+      self.consistent.remove(&node);
+    }
+  }
+
+  #[inline]
+  pub fn invalidate_matching(&mut self, predicate: impl Fn(&dyn KeyObj) -> bool) -> usize {
+    let nodes: Vec<_> = self.store.get_task_nodes()
+      .filter(|node| predicate(self.store.get_task(node).as_key_obj()))
+      .collect();
+    for node in &nodes {
+      self.store.remove_task_output(node);
+      self.consistent.remove(node);
+    }
+    nodes.len()
+  }
+
   #[inline]
   pub fn create_bottom_up_build<'s>(&'s mut self) -> BottomUpBuildInternal<'p, 's> {
     BottomUpBuildInternal(BottomUpContext::new(self))
@@ -88,7 +299,35 @@ impl<'p> SessionInternal<'p> {
 
   #[inline]
   pub fn dependency_check_errors(&self) -> impl Iterator<Item=&dyn Error> + ExactSizeIterator {
-    self.dependency_check_errors.iter().map(|e| e.as_ref())
+    self.dependency_check_errors.iter().map(|(e, _)| e.as_ref())
+  }
+  #[inline]
+  pub fn classified_dependency_check_errors(&self) -> impl ExactSizeIterator<Item=(&dyn Error, ErrorKind)> {
+    self.dependency_check_errors.iter().map(|(e, k)| (e.as_ref(), *k))
+  }
+
+  #[inline]
+  pub fn changed_resources(&self) -> Vec<Box<dyn KeyObj>> {
+    self.changed_resources.ordered.clone()
+  }
+
+  pub fn export_plan(&self) -> BuildPlan {
+    let steps = self.executed_tasks.iter()
+      .map(|node| PlanStep {
+        task: format!("{:?}", self.store.get_task(node)),
+        dependencies: self.store.get_dependencies_from_task(node).filter_map(plan_dependency).collect(),
+      })
+      .collect();
+    BuildPlan { steps }
+  }
+}
+impl Drop for SessionInternal<'_> {
+  /// Accumulates the statistics of this session into the statistics of its [`Pie`] instance when the session ends.
+  #[inline]
+  fn drop(&mut self) {
+    self.pie_statistics.session_count += 1;
+    self.pie_statistics.total_executions += self.statistics.total_executions;
+    self.pie_statistics.total_cache_hits += self.statistics.total_cache_hits;
   }
 }
 
@@ -171,23 +410,30 @@ impl Tracking<'_> {
     resource: &'a R,
     checker: &'a C,
     stamp: &'a C::Stamp,
-  ) -> impl FnOnce(&mut Tracking, Result<Option<&dyn Debug>, &dyn Error>) + 'a {
+  ) -> impl FnOnce(&mut Tracking, Result<Option<&Change>, &dyn Error>) + 'a {
     self.0.check_resource_start(resource, checker, stamp);
     |tracking, inconsistency| tracking.0.check_resource_end(resource, checker, stamp, inconsistency)
   }
 
   #[inline]
   #[must_use]
-  pub fn execute<'a>(&mut self, task: &'a dyn KeyObj) -> impl FnOnce(&mut Tracking, &dyn ValueObj) + 'a {
+  pub fn execute<'a>(&mut self, task: &'a dyn TaskKeyObj) -> impl FnOnce(&mut Tracking, &dyn ValueObj) + 'a {
     self.0.execute_start(task);
     |tracking, output| tracking.0.execute_end(task, output)
   }
 
+  #[inline]
+  #[must_use]
+  pub fn scope(&mut self, label: &'static str) -> impl FnOnce(&mut Tracking) {
+    self.0.scope_start(label);
+    move |tracking| tracking.0.scope_end(label)
+  }
+
   #[inline]
   #[must_use]
   pub fn schedule_affected_by_task<'a>(
     &mut self,
-    task: &'a dyn KeyObj,
+    task: &'a dyn TaskKeyObj,
   ) -> impl FnOnce(&mut Tracking) + 'a {
     self.0.schedule_affected_by_task_start(task);
     |tracking| tracking.0.schedule_affected_by_task_end(task)
@@ -196,7 +442,7 @@ impl Tracking<'_> {
   #[must_use]
   pub fn check_task_require_task<'a>(
     &mut self,
-    requiring_task: &'a dyn KeyObj,
+    requiring_task: &'a dyn TaskKeyObj,
     checker: &'a dyn ValueObj,
     stamp: &'a dyn ValueObj,
   ) -> impl FnOnce(&mut Tracking, Option<&dyn Debug>) + 'a {
@@ -217,10 +463,10 @@ impl Tracking<'_> {
   #[must_use]
   pub fn check_task_read_resource<'a>(
     &mut self,
-    reading_task: &'a dyn KeyObj,
+    reading_task: &'a dyn TaskKeyObj,
     checker: &'a dyn ValueObj,
     stamp: &'a dyn ValueObj,
-  ) -> impl FnOnce(&mut Tracking, Result<Option<&dyn Debug>, &dyn Error>) + 'a {
+  ) -> impl FnOnce(&mut Tracking, Result<Option<&Change>, &dyn Error>) + 'a {
     self.0.check_task_read_resource_start(reading_task, checker, stamp);
     |tracking, inconsistency| tracking.0.check_task_read_resource_end(reading_task, checker, stamp, inconsistency)
   }
@@ -0,0 +1,122 @@
+//! Build plans: the tasks executed in a session in order, with their dependency stamps, for verifying that builds are
+//! reproducible.
+//!
+//! Tasks, resources, and stamps are recorded as their [`Debug`] representation, as they are type-erased and can
+//! therefore not be serialized and deserialized in general. Consequently, [`Debug`] implementations of tasks,
+//! resources, and stamps must be deterministic for plans to be comparable. This also means that plans cannot be
+//! replayed: they can only be [verified](crate::Pie::verify_plan) by the instance they were exported from, and compared
+//! with plans exported elsewhere.
+
+use std::fmt::{Display, Formatter};
+
+/// Build plan [exported from a session](crate::Session::export_plan), containing the tasks that were executed in that
+/// session in the order they finished executing, with the stamps of their dependencies.
+///
+/// With the `serde` feature, build plans can be (de)serialized, for example to store them alongside build artifacts,
+/// and to [verify them](crate::Pie::verify_plan) later with the same instance.
+#[derive(Default, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuildPlan {
+  /// Steps of the plan, in the order their tasks finished executing.
+  pub steps: Vec<PlanStep>,
+}
+
+/// Step of a [build plan](BuildPlan): an executed task with the dependencies it created during execution.
+#[derive(Default, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlanStep {
+  /// Debug formatted task.
+  pub task: String,
+  /// Dependencies of the task, in the order they were created.
+  pub dependencies: Vec<PlanDependency>,
+}
+
+/// Dependency of a [plan step](PlanStep).
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlanDependency {
+  /// Kind of dependency.
+  pub kind: PlanDependencyKind,
+  /// Debug formatted task or resource that is depended on.
+  pub target: String,
+  /// Debug formatted stamp of the dependency. For a resource dependency that merges reads and writes with different
+  /// checkers, this is the list of the stamps of all its checkers.
+  pub stamp: String,
+}
+
+/// Kind of [plan dependency](PlanDependency).
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlanDependencyKind {
+  /// Require dependency to a task.
+  Require,
+  /// Read dependency to a resource.
+  Read,
+  /// Write dependency to a resource.
+  Write,
+}
+
+/// Divergence between a [build plan](BuildPlan) and the current state, returned by
+/// [`Pie::verify_plan`](crate::Pie::verify_plan).
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PlanDivergence {
+  /// The task of a step is not in the dependency graph.
+  UnknownTask {
+    /// Debug formatted task.
+    task: String,
+  },
+  /// A dependency of a step does not exist in the dependency graph.
+  MissingDependency {
+    /// Debug formatted task.
+    task: String,
+    /// Planned dependency.
+    dependency: PlanDependency,
+  },
+  /// A dependency in the dependency graph does not exist in a step.
+  UnplannedDependency {
+    /// Debug formatted task.
+    task: String,
+    /// Dependency in the dependency graph.
+    dependency: PlanDependency,
+  },
+  /// The stamp of a dependency differs from the stamp of the step, either in the dependency graph, or because the
+  /// resource it depends on has changed.
+  ChangedStamp {
+    /// Debug formatted task.
+    task: String,
+    /// Planned dependency.
+    dependency: PlanDependency,
+    /// Debug formatted current stamp, or the inconsistency if the resource it depends on has changed.
+    current: String,
+  },
+  /// Checking a resource dependency of a step failed.
+  CheckFailed {
+    /// Debug formatted task.
+    task: String,
+    /// Planned dependency.
+    dependency: PlanDependency,
+    /// Formatted error.
+    error: String,
+  },
+}
+impl Display for PlanDivergence {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::UnknownTask { task } => write!(f, "task '{}' is unknown", task),
+      Self::MissingDependency { task, dependency } => {
+        write!(f, "task '{}' is missing {:?} dependency to '{}'", task, dependency.kind, dependency.target)
+      }
+      Self::UnplannedDependency { task, dependency } => {
+        write!(f, "task '{}' has unplanned {:?} dependency to '{}'", task, dependency.kind, dependency.target)
+      }
+      Self::ChangedStamp { task, dependency, current } => {
+        write!(f, "stamp of {:?} dependency from task '{}' to '{}' changed: {} → {}", dependency.kind, task,
+          dependency.target, dependency.stamp, current)
+      }
+      Self::CheckFailed { task, dependency, error } => {
+        write!(f, "checking {:?} dependency from task '{}' to '{}' failed: {}", dependency.kind, task,
+          dependency.target, error)
+      }
+    }
+  }
+}
@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use crate::{Key, Resource, resource, ResourceState};
+
+/// Resource identified by key `K`, for which readers of type `R` and writers of type `W` are opened by closures,
+/// failing with errors of type `E`.
+///
+/// Closures cannot be used as [keys](Key), so the open logic is not stored in this resource. Instead, the closures are
+/// [registered](Self::register) as the [resource state](ResourceState) of this resource type, and are given the key of
+/// the resource to open.
+pub struct ClosureResource<K, R, W, E>(pub K, PhantomData<Types<R, W, E>>);
+/// Reader, writer, and error types of a [`ClosureResource`] as a function pointer, so that the resource is `Send` and
+/// `Sync` regardless of these types.
+type Types<R, W, E> = fn() -> (R, W, E);
+
+impl<K, R, W, E> ClosureResource<K, R, W, E> {
+  /// Creates a new closure resource identified by `key`.
+  #[inline]
+  pub fn new(key: K) -> Self { Self(key, PhantomData) }
+
+  /// Gets the key of this resource.
+  #[inline]
+  pub fn key(&self) -> &K { &self.0 }
+}
+impl<K: Key, R: 'static, W: 'static, E: Error + 'static> ClosureResource<K, R, W, E> {
+  /// Registers the `read` and `write` closures that open readers and writers for all closure resources of this type, by
+  /// setting them as the resource `state` for this type. Replaces previously registered closures.
+  #[inline]
+  pub fn register(
+    state: &mut impl ResourceState<Self>,
+    read: impl Fn(&K) -> Result<R, E> + 'static,
+    write: impl Fn(&K) -> Result<W, E> + 'static,
+  ) {
+    state.set(ClosureOpeners::<K, R, W, E> { read: Box::new(read), write: Box::new(write) });
+  }
+}
+
+/// Closures that open readers and writers for [closure resources](ClosureResource), stored in the resource state.
+struct ClosureOpeners<K, R, W, E> {
+  read: OpenFn<K, R, E>,
+  write: OpenFn<K, W, E>,
+}
+/// Boxed closure that opens a reader or writer of type `T` given a key of type `K`, failing with errors of type `E`.
+type OpenFn<K, T, E> = Box<dyn Fn(&K) -> Result<T, E>>;
+
+impl<K: Key, R: 'static, W: 'static, E: Error + 'static> ClosureOpeners<K, R, W, E> {
+  /// Gets the registered closures from `state`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if no closures were [registered](ClosureResource::register) for this closure resource type.
+  #[inline]
+  fn get(state: &impl ResourceState<ClosureResource<K, R, W, E>>) -> &Self {
+    state.get::<Self>()
+      .expect("no closures were registered for this closure resource type; call `ClosureResource::register` first")
+  }
+}
+
+impl<K: Key, R: 'static, W: 'static, E: Error + 'static> Resource for ClosureResource<K, R, W, E> {
+  type Reader<'rs> = R;
+  type Writer<'r> = W;
+  type Error = E;
+
+  /// Opens this resource for reading by calling the registered read closure with the key of this resource.
+  ///
+  /// # Panics
+  ///
+  /// Panics if no closures were [registered](ClosureResource::register) for this closure resource type.
+  #[inline]
+  fn read<RS: ResourceState<Self>>(&self, state: &mut RS) -> Result<R, E> {
+    (ClosureOpeners::get(state).read)(&self.0)
+  }
+  /// Opens this resource for writing by calling the registered write closure with the key of this resource.
+  ///
+  /// # Panics
+  ///
+  /// Panics if no closures were [registered](ClosureResource::register) for this closure resource type.
+  #[inline]
+  fn write<RS: ResourceState<Self>>(&self, state: &mut RS) -> Result<W, E> {
+    (ClosureOpeners::get(state).write)(&self.0)
+  }
+
+  /// Makes attempts to [read](Self::read) this closure resource until one succeeds or `retry` returns `false`.
+  #[inline]
+  fn read_retrying<RS: ResourceState<Self>, WE>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(E) -> WE,
+    retry: impl FnMut(&WE) -> bool,
+  ) -> Result<R, WE> {
+    resource::retry(|| self.read(&mut *state), wrap, retry)
+  }
+
+  /// Makes attempts to [write](Self::write) this closure resource until one succeeds or `retry` returns `false`.
+  #[inline]
+  fn write_retrying<RS: ResourceState<Self>, WE>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(E) -> WE,
+    retry: impl FnMut(&WE) -> bool,
+  ) -> Result<W, WE> {
+    resource::retry(|| self.write(&mut *state), wrap, retry)
+  }
+}
+
+// Manual implementations: derives would require `R`, `W`, and `E` to implement these traits as well.
+impl<K: Clone, R, W, E> Clone for ClosureResource<K, R, W, E> {
+  #[inline]
+  fn clone(&self) -> Self { Self::new(self.0.clone()) }
+}
+impl<K: PartialEq, R, W, E> PartialEq for ClosureResource<K, R, W, E> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool { self.0 == other.0 }
+}
+impl<K: Eq, R, W, E> Eq for ClosureResource<K, R, W, E> {}
+impl<K: Hash, R, W, E> Hash for ClosureResource<K, R, W, E> {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) { self.0.hash(state) }
+}
+impl<K: Debug, R, W, E> Debug for ClosureResource<K, R, W, E> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("ClosureResource").field(&self.0).finish()
+  }
+}
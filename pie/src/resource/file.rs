@@ -3,13 +3,27 @@ use std::error::Error;
 use std::fmt::{Debug, Display, Formatter};
 use std::fs::{self, File, Metadata, OpenOptions};
 use std::io::{self, BufReader, Seek};
+use std::ops::{BitOr, BitOrAssign};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
-use crate::{Resource, ResourceChecker, ResourceState};
+use crate::{ErrorKind, Resource, resource, ResourceChecker, ResourceState};
 
+/// Implements [`ResourceChecker::classify_error`] for filesystem resource checkers whose error is [`FsError`], by
+/// [classifying](FsError::classify) the error.
+macro_rules! classify_fs_error {
+  () => {
+    #[inline]
+    fn classify_error(&self, error: &FsError) -> ErrorKind { error.classify() }
+  };
+}
+
+pub mod cached_checker;
 #[cfg(feature = "file_hash_checker")]
 pub mod hash_checker;
+#[cfg(feature = "file_hash_checker")]
+pub mod normalized_text_checker;
+pub mod two_phase_checker;
 
 /// Filesystem resource implementation. Files and directories can be opened for reading. Only files can be opened for
 /// writing.
@@ -47,6 +61,28 @@ impl Resource for PathBuf {
     let file = OpenOptions::new().write(true).create(true).truncate(true).read(true).open(self)?;
     Ok(file)
   }
+
+  /// Makes attempts to [read](Self::read) this path until one succeeds or `retry` returns `false`.
+  #[inline]
+  fn read_retrying<RS: ResourceState<Self>, W>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(FsError) -> W,
+    retry: impl FnMut(&W) -> bool,
+  ) -> Result<OpenRead, W> {
+    resource::retry(|| self.read(&mut *state), wrap, retry)
+  }
+
+  /// Makes attempts to [write](Self::write) this path until one succeeds or `retry` returns `false`.
+  #[inline]
+  fn write_retrying<RS: ResourceState<Self>, W>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(FsError) -> W,
+    retry: impl FnMut(&W) -> bool,
+  ) -> Result<File, W> {
+    resource::retry(|| self.write(&mut *state), wrap, retry)
+  }
 }
 
 /// A potentially opened filesystem path for reading, representing:
@@ -208,6 +244,20 @@ impl OpenRead {
 #[repr(transparent)]
 pub struct FsError(io::ErrorKind);
 
+impl FsError {
+  /// Classifies this error as [transient](ErrorKind::Transient) if it is [interrupted](io::ErrorKind::Interrupted),
+  /// [would block](io::ErrorKind::WouldBlock), [timed out](io::ErrorKind::TimedOut), or
+  /// [resource busy](io::ErrorKind::ResourceBusy), and as [permanent](ErrorKind::Permanent) otherwise.
+  #[inline]
+  pub fn classify(&self) -> ErrorKind {
+    match self.0 {
+      io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::ResourceBusy =>
+        ErrorKind::Transient,
+      _ => ErrorKind::Permanent,
+    }
+  }
+}
+
 impl Error for FsError {}
 
 impl From<io::ErrorKind> for FsError {
@@ -283,8 +333,150 @@ impl ResourceChecker<PathBuf> for ModifiedChecker {
 
   #[inline]
   fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
+/// Filesystem [resource checker](ResourceChecker) for optional files, that compares file last modified dates where a
+/// missing file is a valid state rather than an error.
+///
+/// The stamp is `None` when no file is present at the path, and `Some(modified)` otherwise. No file is present when
+/// the path does not exist or when it points to a directory. Checking is consistent when:
+///
+/// - no file was present when stamping, and no file is present now (absent then absent);
+/// - a file was present when stamping, and a file with the same last modified date is present now (present
+///   unchanged).
+///
+/// Checking is inconsistent when a file appears, when a file disappears, or when the last modified date of a present
+/// file changes.
+///
+/// For files, this behaves like [`ModifiedChecker`]. It differs in that directories are treated as absent instead of
+/// being stamped, so that replacing an optional file with a directory, or changing the contents of such a directory,
+/// does not affect tasks that depend on the optional file.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct OptionalModifiedChecker;
+
+impl OptionalModifiedChecker {
+  #[inline]
+  fn file_modified(path: &PathBuf) -> Result<Option<SystemTime>, io::Error> {
+    match fs::metadata(path) {
+      Ok(m) if m.is_file() => Ok(Some(m.modified()?)),
+      Ok(_) => Ok(None),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+}
+
+impl ResourceChecker<PathBuf> for OptionalModifiedChecker {
+  /// `Some(modified)` if a file is present, `None` otherwise.
+  type Stamp = Option<SystemTime>;
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, _state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    Ok(Self::file_modified(path)?)
+  }
+  #[inline]
+  fn stamp_reader(&self, _path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    let modified = match open_read {
+      OpenRead::File(_, metadata) => Some(metadata.modified()?),
+      _ => None,
+    };
+    Ok(modified)
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, _file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: we cannot use `_file.metadata()`, as it returns stale metadata if `_file` was removed.
+    Ok(Self::file_modified(path)?)
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    _state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let modified = Self::file_modified(path)?;
+    let inconsistency = if modified != *stamp {
+      Some(modified)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
+/// Filesystem [resource checker](ResourceChecker) that compares file or directory last modified dates and creation
+/// dates. On filesystems where the resolution of last modified dates is coarse, also comparing creation dates reduces
+/// missed changes, for example when a file is replaced by a new file within the resolution of last modified dates.
+///
+/// On platforms or filesystems that do not support creation dates, the creation date is `None`, and this checker
+/// behaves like [`ModifiedChecker`].
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ModifiedCreatedChecker;
+
+impl ModifiedCreatedChecker {
+  #[inline]
+  fn modified_created_stamp(metadata: &Metadata) -> Result<(Option<SystemTime>, Option<SystemTime>), io::Error> {
+    Ok((Some(metadata.modified()?), metadata.created().ok()))
+  }
+}
+
+impl ResourceChecker<PathBuf> for ModifiedCreatedChecker {
+  /// `(modified, created)` dates, where `modified` is `Some(date)` if a file or directory exists, `None` otherwise;
+  /// and `created` is `Some(date)` if a file or directory exists and creation dates are supported, `None` otherwise.
+  type Stamp = (Option<SystemTime>, Option<SystemTime>);
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, _state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    let stamp = metadata(path)?.map(|m| Self::modified_created_stamp(&m)).transpose()?;
+    Ok(stamp.unwrap_or_default())
+  }
+  #[inline]
+  fn stamp_reader(&self, _path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    let stamp = open_read.as_metadata().map(Self::modified_created_stamp).transpose()?;
+    Ok(stamp.unwrap_or_default())
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: we first need to confirm `file` still exists. If `file` does not exist, `file.metadata()` returns stale
+    //       metadata instead of returning an error, resulting in an inconsistent stamp.
+    if !exists(path)? {
+      return Ok((None, None));
+    }
+    Ok(Self::modified_created_stamp(&file.metadata()?)?)
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    _state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let modified_created = metadata(path)?.map(|m| Self::modified_created_stamp(&m)).transpose()?.unwrap_or_default();
+    let inconsistency = if modified_created != *stamp {
+      Some(modified_created)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
 }
 
+
 /// Filesystem [resource checker](ResourceChecker) that compares whether a file or directory exists.
 #[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct ExistsChecker;
@@ -329,9 +521,377 @@ impl ResourceChecker<PathBuf> for ExistsChecker {
 
   #[inline]
   fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
+
+/// Filesystem [resource checker](ResourceChecker) that compares the target of symbolic links, without following them.
+/// Useful for tasks that resolve links that are retargeted, such as a link to the `current` version of a toolchain.
+///
+/// Only the link itself is checked: retargeting the link is a change even if the file it targets is unchanged, but
+/// changes to the targeted file are not. Depend on the path with another checker as well to also detect those.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SymlinkTargetChecker;
+
+/// Stamp of [`SymlinkTargetChecker`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum SymlinkStamp {
+  /// Nothing exists at the path.
+  NonExistent,
+  /// A file or directory that is not a symbolic link exists at the path.
+  NotSymlink,
+  /// A symbolic link exists at the path, with given target as read by [`fs::read_link`]. The target is not resolved,
+  /// and may not exist.
+  Symlink(PathBuf),
+}
+
+impl SymlinkTargetChecker {
+  #[inline]
+  fn symlink_stamp(path: &PathBuf) -> Result<SymlinkStamp, io::Error> {
+    let metadata = match fs::symlink_metadata(path) {
+      Ok(metadata) => metadata,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(SymlinkStamp::NonExistent),
+      Err(e) => return Err(e),
+    };
+    let stamp = if metadata.is_symlink() {
+      SymlinkStamp::Symlink(fs::read_link(path)?)
+    } else {
+      SymlinkStamp::NotSymlink
+    };
+    Ok(stamp)
+  }
+}
+
+impl ResourceChecker<PathBuf> for SymlinkTargetChecker {
+  type Stamp = SymlinkStamp;
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, _state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    Ok(Self::symlink_stamp(path)?)
+  }
+  #[inline]
+  fn stamp_reader(&self, path: &PathBuf, _open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    // Note: `_open_read` follows symbolic links, so we need to get the link from `path` instead.
+    Ok(Self::symlink_stamp(path)?)
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, _file: File) -> Result<Self::Stamp, Self::Error> {
+    Ok(Self::symlink_stamp(path)?)
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    _state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let symlink_stamp = Self::symlink_stamp(path)?;
+    let inconsistency = if symlink_stamp != *stamp {
+      Some(symlink_stamp)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
+
+/// Filesystem [resource checker](ResourceChecker) that compares file or directory permissions.
+///
+/// On Unix, the permission bits (mode) are compared. On other platforms, only the read-only flag is compared.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct PermissionsChecker;
+
+/// Permissions stamp of [`PermissionsChecker`]: the permission bits (mode) of a file or directory.
+#[cfg(unix)]
+pub type PermissionsStamp = u32;
+/// Permissions stamp of [`PermissionsChecker`]: whether a file or directory is read-only.
+#[cfg(not(unix))]
+pub type PermissionsStamp = bool;
+
+impl PermissionsChecker {
+  #[inline]
+  fn permissions_stamp(metadata: &Metadata) -> PermissionsStamp {
+    #[cfg(unix)] {
+      use std::os::unix::fs::PermissionsExt;
+      metadata.permissions().mode()
+    }
+    #[cfg(not(unix))] {
+      metadata.permissions().readonly()
+    }
+  }
+}
+
+impl ResourceChecker<PathBuf> for PermissionsChecker {
+  /// `Some(permissions)` if a file or directory exists, `None` otherwise.
+  type Stamp = Option<PermissionsStamp>;
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, _state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    let permissions = metadata(path)?.map(|m| Self::permissions_stamp(&m));
+    Ok(permissions)
+  }
+  #[inline]
+  fn stamp_reader(&self, _path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    let permissions = open_read.as_metadata().map(Self::permissions_stamp);
+    Ok(permissions)
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: we first need to confirm `file` still exists. If `file` does not exist, `file.metadata()` returns stale
+    //       metadata instead of returning an error, resulting in an inconsistent stamp.
+    if !exists(path)? {
+      return Ok(None);
+    }
+    Ok(Some(Self::permissions_stamp(&file.metadata()?)))
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    _state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let permissions = metadata(path)?.map(|m| Self::permissions_stamp(&m));
+    let inconsistency = if permissions != *stamp {
+      Some(permissions)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
+
+/// Filesystem [resource checker](ResourceChecker) that compares a configurable set of file or directory [metadata
+/// fields](MetadataFields). Only the selected fields are stamped and compared.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct MetadataChecker {
+  /// Metadata fields to stamp and compare.
+  pub fields: MetadataFields,
+}
+
+impl MetadataChecker {
+  /// Creates a new metadata checker that stamps and compares `fields`.
+  #[inline]
+  pub fn new(fields: MetadataFields) -> Self { Self { fields } }
+
+  #[inline]
+  fn metadata_stamp(&self, metadata: &Metadata) -> Result<MetadataStamp, io::Error> {
+    let size = self.fields.contains(MetadataFields::SIZE).then_some(metadata.len());
+    let modified = self.fields.contains(MetadataFields::MODIFIED).then(|| metadata.modified()).transpose()?;
+    let permissions = self.fields.contains(MetadataFields::PERMISSIONS)
+      .then(|| PermissionsChecker::permissions_stamp(metadata));
+    Ok(MetadataStamp { size, modified, permissions })
+  }
+}
+
+/// Set of metadata fields to stamp and compare in a [`MetadataChecker`]. Combine fields with `|`.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[repr(transparent)]
+pub struct MetadataFields(u8);
+
+impl MetadataFields {
+  /// No fields: only compares whether a file or directory exists.
+  pub const NONE: Self = Self(0);
+  /// Size in bytes, as returned by [`Metadata::len`].
+  pub const SIZE: Self = Self(1 << 0);
+  /// Alias of [`SIZE`](Self::SIZE).
+  pub const LEN: Self = Self::SIZE;
+  /// Last modified date.
+  pub const MODIFIED: Self = Self(1 << 1);
+  /// Permissions, as compared by [`PermissionsChecker`].
+  pub const PERMISSIONS: Self = Self(1 << 2);
+  /// All fields.
+  pub const ALL: Self = Self(Self::SIZE.0 | Self::MODIFIED.0 | Self::PERMISSIONS.0);
+
+  /// Returns `true` if all fields in `other` are in `self`, `false` otherwise.
+  #[inline]
+  pub const fn contains(self, other: Self) -> bool { self.0 & other.0 == other.0 }
+  /// Returns `true` if `self` contains no fields, `false` otherwise.
+  #[inline]
+  pub const fn is_empty(self) -> bool { self.0 == 0 }
+}
+
+impl BitOr for MetadataFields {
+  type Output = Self;
+  #[inline]
+  fn bitor(self, rhs: Self) -> Self { Self(self.0 | rhs.0) }
+}
+impl BitOrAssign for MetadataFields {
+  #[inline]
+  fn bitor_assign(&mut self, rhs: Self) { self.0 |= rhs.0 }
+}
+
+/// Metadata stamp of [`MetadataChecker`]. Fields are `Some` if they were selected, `None` otherwise.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct MetadataStamp {
+  /// Size in bytes.
+  pub size: Option<u64>,
+  /// Last modified date.
+  pub modified: Option<SystemTime>,
+  /// Permissions.
+  pub permissions: Option<PermissionsStamp>,
+}
+
+impl ResourceChecker<PathBuf> for MetadataChecker {
+  /// `Some(metadata)` if a file or directory exists, `None` otherwise.
+  type Stamp = Option<MetadataStamp>;
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, _state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    let stamp = metadata(path)?.map(|m| self.metadata_stamp(&m)).transpose()?;
+    Ok(stamp)
+  }
+  #[inline]
+  fn stamp_reader(&self, _path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    let stamp = open_read.as_metadata().map(|m| self.metadata_stamp(m)).transpose()?;
+    Ok(stamp)
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: we first need to confirm `file` still exists. If `file` does not exist, `file.metadata()` returns stale
+    //       metadata instead of returning an error, resulting in an inconsistent stamp.
+    if !exists(path)? {
+      return Ok(None);
+    }
+    Ok(Some(self.metadata_stamp(&file.metadata()?)?))
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    _state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let new_stamp = metadata(path)?.map(|m| self.metadata_stamp(&m)).transpose()?;
+    let inconsistency = if new_stamp != *stamp {
+      Some(new_stamp)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
 }
 
 
+/// Filesystem [resource checker](ResourceChecker) that dispatches to one of the filesystem checkers, selected at
+/// runtime. Useful for storing a checker choice in a field of a task, for example when a task reads some files by hash
+/// and others by last modified date.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum FileChecker {
+  /// Dispatches to [`ModifiedChecker`].
+  #[default]
+  Modified,
+  /// Dispatches to [`ExistsChecker`].
+  Exists,
+  /// Dispatches to [`HashChecker`](hash_checker::HashChecker).
+  #[cfg(feature = "file_hash_checker")]
+  Hash,
+  /// Dispatches to [`PermissionsChecker`].
+  Permissions,
+}
+
+/// Stamp of [`FileChecker`], wrapping the stamp of the checker it dispatched to.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub enum FileStamp {
+  /// Stamp of [`ModifiedChecker`].
+  Modified(Option<SystemTime>),
+  /// Stamp of [`ExistsChecker`].
+  Exists(bool),
+  /// Stamp of [`HashChecker`](hash_checker::HashChecker).
+  #[cfg(feature = "file_hash_checker")]
+  Hash(Option<[u8; 32]>),
+  /// Stamp of [`PermissionsChecker`].
+  Permissions(Option<PermissionsStamp>),
+}
+
+impl ResourceChecker<PathBuf> for FileChecker {
+  type Stamp = FileStamp;
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    let stamp = match self {
+      Self::Modified => FileStamp::Modified(ModifiedChecker.stamp(path, state)?),
+      Self::Exists => FileStamp::Exists(ExistsChecker.stamp(path, state)?),
+      #[cfg(feature = "file_hash_checker")]
+      Self::Hash => FileStamp::Hash(hash_checker::HashChecker.stamp(path, state)?),
+      Self::Permissions => FileStamp::Permissions(PermissionsChecker.stamp(path, state)?),
+    };
+    Ok(stamp)
+  }
+  #[inline]
+  fn stamp_reader(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    let stamp = match self {
+      Self::Modified => FileStamp::Modified(ModifiedChecker.stamp_reader(path, open_read)?),
+      Self::Exists => FileStamp::Exists(ExistsChecker.stamp_reader(path, open_read)?),
+      #[cfg(feature = "file_hash_checker")]
+      Self::Hash => FileStamp::Hash(hash_checker::HashChecker.stamp_reader(path, open_read)?),
+      Self::Permissions => FileStamp::Permissions(PermissionsChecker.stamp_reader(path, open_read)?),
+    };
+    Ok(stamp)
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, Self::Error> {
+    let stamp = match self {
+      Self::Modified => FileStamp::Modified(ModifiedChecker.stamp_writer(path, file)?),
+      Self::Exists => FileStamp::Exists(ExistsChecker.stamp_writer(path, file)?),
+      #[cfg(feature = "file_hash_checker")]
+      Self::Hash => FileStamp::Hash(hash_checker::HashChecker.stamp_writer(path, file)?),
+      Self::Permissions => FileStamp::Permissions(PermissionsChecker.stamp_writer(path, file)?),
+    };
+    Ok(stamp)
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let inconsistency = match (self, stamp) {
+      (Self::Modified, FileStamp::Modified(stamp)) =>
+        ModifiedChecker.check(path, state, stamp)?.map(FileStamp::Modified),
+      (Self::Exists, FileStamp::Exists(stamp)) => ExistsChecker.check(path, state, stamp)?.map(FileStamp::Exists),
+      #[cfg(feature = "file_hash_checker")]
+      (Self::Hash, FileStamp::Hash(stamp)) => hash_checker::HashChecker.check(path, state, stamp)?.map(FileStamp::Hash),
+      (Self::Permissions, FileStamp::Permissions(stamp)) =>
+        PermissionsChecker.check(path, state, stamp)?.map(FileStamp::Permissions),
+      // Stamp was created by a different checker: always inconsistent.
+      _ => Some(self.stamp(path, state)?),
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
 /// Gets the metadata for given `path`, returning:
 ///
 /// - `Ok(Some(metadata))` if a file or directory exists at given path,
@@ -579,6 +1139,29 @@ mod test {
   }
 
 
+  #[test]
+  fn test_describe_change() -> Result<(), io::Error> {
+    let checker = ModifiedChecker;
+    let temp_path = create_temp_file()?.into_temp_path();
+    let path = temp_path.to_path_buf();
+    let mut state = TypeToAnyMap::default();
+
+    let stamp = checker.stamp(&path, &mut state)?;
+    assert_matches!(checker.describe_change(&path, &mut state, &stamp)?, None);
+
+    write_until_modified(&path, "Hello, World!")?;
+    let new_stamp = checker.stamp(&path, &mut state)?;
+    assert_matches!(checker.describe_change(&path, &mut state, &new_stamp)?, None);
+    assert_matches!(checker.describe_change(&path, &mut state, &stamp)?, Some(change) => {
+      assert_eq!(change.from, format!("{:?}", stamp));
+      assert_eq!(change.to, format!("{:?}", new_stamp));
+      assert_eq!(change.to_string(), format!("{:?} → {:?}", stamp, new_stamp));
+    });
+
+    Ok(())
+  }
+
+
   #[test]
   fn test_metadata() -> Result<(), io::Error> {
     let file = create_temp_file()?;
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::trait_object::{KeyObj, ValueObj};
+
+use super::*;
+
+/// Filesystem [resource checker](ResourceChecker) that caches the stamps of the inner checker `H` by last modified
+/// date, only invoking the inner checker when the last modified date (a cheap freshness signal) of a file or directory
+/// has changed. Useful for wrapping checkers with expensive stamping, such as
+/// [`HashChecker`](super::hash_checker::HashChecker).
+///
+/// The stamp of this checker includes the last modified date at the time of stamping, so checking a file or directory
+/// whose last modified date is unchanged does not invoke the inner checker. When the last modified date changed, the
+/// [`StampCache`] stored in the [resource state](ResourceState) is consulted, so that a file or directory that was
+/// modified without changing its inner stamp (e.g., writing the same file contents) is only stamped by the inner
+/// checker once, instead of in every session.
+///
+/// Changes made within the resolution of last modified dates of the filesystem are missed, just like with
+/// [`ModifiedChecker`].
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct CachedChecker<H>(pub H);
+impl<H> CachedChecker<H> {
+  /// Creates a new cached checker that caches the stamps of `checker`.
+  #[inline]
+  pub fn new(checker: H) -> Self { Self(checker) }
+}
+
+impl<H: ResourceChecker<PathBuf, Error=FsError>> CachedChecker<H> where H::Stamp: Eq {
+  /// Stamps `path` with the inner checker, unless the cache has a stamp for the current `modified` date.
+  #[inline]
+  fn cached_stamp<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    modified: Option<SystemTime>,
+  ) -> Result<H::Stamp, FsError> {
+    let Some(modified) = modified else { // Not existing: nothing to cache.
+      return self.0.stamp(path, state);
+    };
+    if let Some(stamp) = state.get_or_set_default::<StampCache>().get(path, &self.0, modified) {
+      return Ok(stamp.clone());
+    }
+    let stamp = self.0.stamp(path, state)?;
+    state.get_or_set_default_mut::<StampCache>().insert(path, &self.0, modified, stamp.clone());
+    Ok(stamp)
+  }
+}
+
+impl<H: ResourceChecker<PathBuf, Error=FsError>> ResourceChecker<PathBuf> for CachedChecker<H> where H::Stamp: Eq {
+  /// `(modified, stamp)`, where `modified` is `Some(date)` if a file or directory exists, `None` otherwise; and `stamp`
+  /// is the stamp of the inner checker.
+  type Stamp = (Option<SystemTime>, H::Stamp);
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    let modified = metadata(path)?.map(|m| m.modified()).transpose()?;
+    Ok((modified, self.cached_stamp(path, state, modified)?))
+  }
+  #[inline]
+  fn stamp_reader(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    // Note: the cache cannot be consulted as there is no access to the resource state here.
+    let modified = open_read.as_metadata().map(|m| m.modified()).transpose()?;
+    Ok((modified, self.0.stamp_reader(path, open_read)?))
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: we first need to confirm `file` still exists. If `file` does not exist, `file.metadata()` returns stale
+    //       metadata instead of returning an error, resulting in an inconsistent stamp.
+    let modified = if exists(path)? { Some(file.metadata()?.modified()?) } else { None };
+    Ok((modified, self.0.stamp_writer(path, file)?))
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let modified = metadata(path)?.map(|m| m.modified()).transpose()?;
+    if modified.is_some() && modified == stamp.0 {
+      return Ok(None);
+    }
+    let inner_stamp = self.cached_stamp(path, state, modified)?;
+    let inconsistency = if inner_stamp != stamp.1 {
+      Some((modified, inner_stamp))
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  #[inline]
+  fn classify_error(&self, error: &FsError) -> ErrorKind { self.0.classify_error(error) }
+}
+
+/// Cache of stamps of filesystem [resource checkers](ResourceChecker) by path, checker, and last modified date, for
+/// checkers that want to skip expensive stamping when the last modified date of a file or directory is unchanged.
+///
+/// Stored in the [resource state](ResourceState) of [`PathBuf`] with
+/// [`get_or_set_default_mut`](ResourceState::get_or_set_default_mut), and thus retained across sessions.
+#[derive(Default, Debug)]
+pub struct StampCache {
+  stamps: HashMap<StampCacheKey, (SystemTime, Box<dyn ValueObj>)>,
+}
+/// Key of [`StampCache`]: a path and the checker that stamped it.
+type StampCacheKey = (PathBuf, Box<dyn KeyObj>);
+impl StampCache {
+  /// Gets the stamp cached for `path` and `checker`, returning `Some(stamp)` if it was cached for last modified date
+  /// `modified`, `None` otherwise.
+  #[inline]
+  pub fn get<H: ResourceChecker<PathBuf>>(&self, path: &Path, checker: &H, modified: SystemTime) -> Option<&H::Stamp> {
+    let key = (path.to_path_buf(), Box::new(checker.clone()) as Box<dyn KeyObj>);
+    self.stamps.get(&key)
+      .filter(|(cached_modified, _)| *cached_modified == modified)
+      .and_then(|(_, stamp)| stamp.as_ref().as_any().downcast_ref())
+  }
+  /// Caches `stamp` for `path` and `checker` at last modified date `modified`, replacing the previously cached stamp.
+  #[inline]
+  pub fn insert<H: ResourceChecker<PathBuf>>(
+    &mut self,
+    path: &Path,
+    checker: &H,
+    modified: SystemTime,
+    stamp: H::Stamp,
+  ) {
+    let key = (path.to_path_buf(), Box::new(checker.clone()) as Box<dyn KeyObj>);
+    self.stamps.insert(key, (modified, Box::new(stamp)));
+  }
+}
@@ -54,6 +54,7 @@ impl ResourceChecker<PathBuf> for HashChecker {
 
   #[inline]
   fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
 }
 
 impl HashChecker {
@@ -70,7 +71,7 @@ impl HashChecker {
     io::copy(file, &mut hasher)?;
     Ok(hasher.finalize().into())
   }
-  fn hash_directory(&self, path: &PathBuf) -> Result<[u8; 32], FsError> {
+  pub(super) fn hash_directory(&self, path: &PathBuf) -> Result<[u8; 32], FsError> {
     let mut hasher = Sha256::new();
     for entry in fs::read_dir(path)?.into_iter() {
       hasher.update(entry?.file_name().as_encoded_bytes());
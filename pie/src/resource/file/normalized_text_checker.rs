@@ -0,0 +1,127 @@
+use std::fmt::Debug;
+use std::io::{BufRead, Seek};
+
+use sha2::{Digest, Sha256};
+
+use super::*;
+use super::hash_checker::HashChecker;
+
+/// Filesystem [resource checker](ResourceChecker) that hashes normalized text file contents and compares hashes. Text
+/// is normalized by trimming trailing whitespace from each line, which also removes differences in line endings (CRLF
+/// vs LF). Directory listings are hashed like [`HashChecker`] does.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct NormalizedTextChecker;
+
+impl ResourceChecker<PathBuf> for NormalizedTextChecker {
+  type Stamp = Option<[u8; 32]>;
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    self.hash(path, &mut path.read(state)?)
+  }
+  #[inline]
+  fn stamp_reader(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    let hash = self.hash(path, open_read);
+    open_read.rewind()?; // Rewind to restore the file (if any) into a fresh state.
+    hash
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, mut file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: we cannot assume `file` exists because it could have been removed before passing it to this method.
+    if !exists(path)? {
+      return Ok(None);
+    }
+
+    file.rewind()?; // Rewind to restore the file into a fresh state.
+    let hash = self.hash_file(&mut BufReader::new(file))?;
+    Ok(Some(hash))
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let hash = self.hash(path, &mut path.read(state)?)?;
+    let inconsistency = if hash != *stamp {
+      Some(hash)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  classify_fs_error!();
+}
+
+impl NormalizedTextChecker {
+  fn hash(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Option<[u8; 32]>, FsError> {
+    let hash = match open_read {
+      OpenRead::File(ref mut file, _) => Some(self.hash_file(file)?),
+      OpenRead::Directory(_) => Some(HashChecker.hash_directory(path)?),
+      OpenRead::NonExistent => None
+    };
+    Ok(hash)
+  }
+  fn hash_file(&self, file: &mut BufReader<File>) -> Result<[u8; 32], FsError> {
+    let mut hasher = Sha256::new();
+    let mut line = Vec::new();
+    while file.read_until(b'\n', &mut line)? != 0 {
+      // Note: trimming trailing ASCII whitespace also trims the line ending, including the `\r` of a CRLF line ending.
+      hasher.update(line.trim_ascii_end());
+      hasher.update(b"\n");
+      line.clear();
+    }
+    Ok(hasher.finalize().into())
+  }
+}
+
+
+#[cfg(test)]
+mod test {
+  use std::fs::write;
+
+  use assert_matches::assert_matches;
+  use testresult::TestResult;
+
+  use dev_util::create_temp_file;
+
+  use crate::trait_object::collection::TypeToAnyMap;
+
+  use super::*;
+
+  #[test]
+  fn test_normalized_text_checker() -> TestResult {
+    let checker = NormalizedTextChecker;
+    let temp_path = create_temp_file()?.into_temp_path();
+    let path = temp_path.to_path_buf();
+    let mut state = TypeToAnyMap::default();
+
+    write(&path, "Hello, \r\nWorld!\r\n")?;
+    let stamp = checker.stamp(&path, &mut state)?;
+    assert_matches!(checker.check(&path, &mut state, &stamp)?, None);
+
+    // Different line endings and trailing whitespace: consistent.
+    write(&path, "Hello,\nWorld!  \t\n")?;
+    assert_matches!(checker.check(&path, &mut state, &stamp)?, None);
+    let new_stamp = checker.stamp_reader(&path, &mut path.read(&mut state)?)?;
+    assert_eq!(new_stamp, stamp);
+    let new_stamp = checker.stamp_writer(&path, File::open(&path)?)?;
+    assert_eq!(new_stamp, stamp);
+
+    // Different content: inconsistent.
+    write(&path, "Hello,\nNormalized World!\n")?;
+    assert_matches!(checker.check(&path, &mut state, &stamp)?, Some(s) if s != stamp);
+    // Leading whitespace is not trimmed: inconsistent.
+    write(&path, "  Hello,\nWorld!\n")?;
+    assert_matches!(checker.check(&path, &mut state, &stamp)?, Some(s) if s != stamp);
+
+    Ok(())
+  }
+}
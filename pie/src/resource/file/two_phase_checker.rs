@@ -0,0 +1,74 @@
+use std::fmt::Debug;
+
+use super::*;
+
+/// Filesystem [resource checker](ResourceChecker) that checks in two phases: first with fast checker `F`, and only if
+/// that reports an inconsistency, with slow checker `S` to confirm it. A file or directory is only inconsistent if both
+/// checkers report an inconsistency. Useful for combining a fast but imprecise checker with a slow but precise one,
+/// such as [`ModifiedChecker`] with [`HashChecker`](super::hash_checker::HashChecker), giving the precision of the slow
+/// checker with the speed of the fast checker in the common case where nothing changed.
+///
+/// The stamp of this checker contains the stamps of both checkers, so stamping invokes both checkers. Therefore, `F`
+/// must report an inconsistency whenever `S` would, otherwise changes are missed.
+///
+/// When the fast checker reports an inconsistency that the slow checker rescues, the dependency stays consistent and
+/// is therefore not re-stamped, so every subsequent check invokes the slow checker until the dependency is re-created.
+/// Use [`CachedChecker`](super::cached_checker::CachedChecker) to also cache the slow stamps in that case.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct TwoPhaseChecker<F, S>(pub F, pub S);
+impl<F, S> TwoPhaseChecker<F, S> {
+  /// Creates a new two-phase checker that first checks with `fast`, then confirms inconsistencies with `slow`.
+  #[inline]
+  pub fn new(fast: F, slow: S) -> Self { Self(fast, slow) }
+}
+
+impl<F, S> ResourceChecker<PathBuf> for TwoPhaseChecker<F, S> where
+  F: ResourceChecker<PathBuf, Error=FsError>,
+  S: ResourceChecker<PathBuf, Error=FsError>,
+{
+  /// `(fast, slow)`, where `fast` is the stamp of the fast checker, and `slow` the stamp of the slow checker.
+  type Stamp = (F::Stamp, S::Stamp);
+  type Error = FsError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, state: &mut RS) -> Result<Self::Stamp, Self::Error> {
+    Ok((self.0.stamp(path, state)?, self.1.stamp(path, state)?))
+  }
+  #[inline]
+  fn stamp_reader(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, Self::Error> {
+    Ok((self.0.stamp_reader(path, open_read)?, self.1.stamp_reader(path, open_read)?))
+  }
+  #[inline]
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, Self::Error> {
+    // Note: both checkers take ownership of a file, so give the fast checker a clone of the file descriptor. Checkers
+    //       restore files into a fresh state before reading from them, so the shared file position is not an issue.
+    let fast = self.0.stamp_writer(path, file.try_clone()?)?;
+    Ok((fast, self.1.stamp_writer(path, file)?))
+  }
+
+  #[inline]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<impl Debug>, Self::Error> {
+    if self.0.check(path, state, &stamp.0)?.is_none() {
+      return Ok(None);
+    }
+    let inconsistency = self.1.check(path, state, &stamp.1)?;
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: FsError) -> Self::Error { error }
+  /// Classifies `error` as [transient](ErrorKind::Transient) if either checker does, as it can originate from either
+  /// checker.
+  #[inline]
+  fn classify_error(&self, error: &FsError) -> ErrorKind {
+    match self.0.classify_error(error) {
+      ErrorKind::Transient => ErrorKind::Transient,
+      ErrorKind::Permanent => self.1.classify_error(error),
+    }
+  }
+}
@@ -0,0 +1,201 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Read};
+
+pub use url::Url;
+
+use crate::{Resource, resource, ResourceChecker, ResourceState};
+
+/// HTTP resource identified by a [`Url`]. Can be opened for reading, which performs a `GET` request. Cannot be opened
+/// for writing.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[repr(transparent)]
+pub struct HttpResource(pub Url);
+
+impl Resource for HttpResource {
+  type Reader<'rs> = HttpReader;
+  type Writer<'r> = Infallible;
+  type Error = HttpError;
+
+  /// Performs a `GET` request for this URL, returning a [reader](HttpReader) of the response body.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the request failed, or if the server responded with an error status code.
+  #[inline]
+  fn read<RS: ResourceState<Self>>(&self, _state: &mut RS) -> Result<HttpReader, HttpError> {
+    let response = ureq::request_url("GET", &self.0).call()?;
+    let validator = HttpValidator::from_response(&response);
+    Ok(HttpReader { validator, body: response.into_reader() })
+  }
+
+  /// Makes attempts to [read](Self::read) this HTTP resource until one succeeds or `retry` returns `false`.
+  #[inline]
+  fn read_retrying<RS: ResourceState<Self>, W>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(HttpError) -> W,
+    retry: impl FnMut(&W) -> bool,
+  ) -> Result<HttpReader, W> {
+    resource::retry(|| self.read(&mut *state), wrap, retry)
+  }
+
+  /// Always returns [`HttpError::WriteUnsupported`], as HTTP resources cannot be written to.
+  #[inline]
+  fn write<RS: ResourceState<Self>>(&self, _state: &mut RS) -> Result<Infallible, HttpError> {
+    Err(HttpError::WriteUnsupported)
+  }
+}
+
+/// Reader of the body of a `GET` response for a [`HttpResource`], along with the [validator](HttpValidator) of that
+/// response.
+pub struct HttpReader {
+  validator: Option<HttpValidator>,
+  body: Box<dyn Read + Send + Sync + 'static>,
+}
+impl HttpReader {
+  /// Gets the validator of the response, returning `Some(&validator)` if the server sent an `ETag` or `Last-Modified`
+  /// header, `None` otherwise.
+  #[inline]
+  pub fn validator(&self) -> Option<&HttpValidator> { self.validator.as_ref() }
+}
+impl Read for HttpReader {
+  #[inline]
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.body.read(buf) }
+}
+impl Debug for HttpReader {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("HttpReader").field("validator", &self.validator).finish_non_exhaustive()
+  }
+}
+
+/// Validator of an HTTP response, used to determine whether the resource has changed through conditional requests.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum HttpValidator {
+  /// Value of the `ETag` header.
+  ETag(String),
+  /// Value of the `Last-Modified` header, used when the server did not send an `ETag` header.
+  LastModified(String),
+}
+impl HttpValidator {
+  #[inline]
+  fn from_response(response: &ureq::Response) -> Option<Self> {
+    if let Some(etag) = response.header("ETag") {
+      Some(Self::ETag(etag.to_string()))
+    } else {
+      response.header("Last-Modified").map(|last_modified| Self::LastModified(last_modified.to_string()))
+    }
+  }
+}
+
+/// HTTP resource error.
+///
+/// # Implementation Notes
+///
+/// Like [`FsError`](crate::resource::file::FsError), this error is `Clone` so that it can be used in task outputs.
+/// Therefore, transport errors are stored as their formatted message, as [`ureq::Error`] is not `Clone`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub enum HttpError {
+  /// The server responded with an error status code.
+  Status(u16),
+  /// The request failed due to a transport (e.g., DNS, connection, or I/O) error, described by the message.
+  Transport(String),
+  /// Writing to an HTTP resource was attempted, which is not supported.
+  WriteUnsupported,
+}
+
+impl Error for HttpError {}
+
+impl From<ureq::Error> for HttpError {
+  #[inline]
+  fn from(value: ureq::Error) -> Self {
+    match value {
+      ureq::Error::Status(code, _) => Self::Status(code),
+      ureq::Error::Transport(transport) => Self::Transport(transport.to_string()),
+    }
+  }
+}
+impl Display for HttpError {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Status(code) => write!(f, "server responded with status code {}", code),
+      Self::Transport(message) => write!(f, "request failed: {}", message),
+      Self::WriteUnsupported => write!(f, "writing to HTTP resources is not supported"),
+    }
+  }
+}
+
+
+/// HTTP [resource checker](ResourceChecker) that compares the `ETag` header, or the `Last-Modified` header if the
+/// server does not send an `ETag`, of a resource. Checks are performed with a conditional `HEAD` request, for which a
+/// `304 Not Modified` response indicates that the resource is consistent.
+///
+/// Resources without either header are always inconsistent, as there is no way to determine whether they changed.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct HttpETagChecker;
+
+impl HttpETagChecker {
+  #[inline]
+  fn head(url: &Url, validator: Option<&HttpValidator>) -> Result<ureq::Response, HttpError> {
+    let request = ureq::request_url("HEAD", url);
+    let request = match validator {
+      Some(HttpValidator::ETag(etag)) => request.set("If-None-Match", etag),
+      Some(HttpValidator::LastModified(last_modified)) => request.set("If-Modified-Since", last_modified),
+      None => request,
+    };
+    Ok(request.call()?)
+  }
+}
+
+impl ResourceChecker<HttpResource> for HttpETagChecker {
+  type Stamp = Option<HttpValidator>;
+  type Error = HttpError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<HttpResource>>(
+    &self,
+    resource: &HttpResource,
+    _state: &mut RS,
+  ) -> Result<Self::Stamp, Self::Error> {
+    let response = Self::head(&resource.0, None)?;
+    Ok(HttpValidator::from_response(&response))
+  }
+  #[inline]
+  fn stamp_reader(&self, _resource: &HttpResource, reader: &mut HttpReader) -> Result<Self::Stamp, Self::Error> {
+    Ok(reader.validator.clone())
+  }
+  #[inline]
+  fn stamp_writer(&self, _resource: &HttpResource, writer: Infallible) -> Result<Self::Stamp, Self::Error> {
+    match writer {}
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<HttpResource>>(
+    &self,
+    resource: &HttpResource,
+    _state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let Some(validator) = stamp else {
+      return Ok(Some(None));
+    };
+    let response = Self::head(&resource.0, Some(validator))?;
+    if response.status() == 304 {
+      return Ok(None);
+    }
+    let new_validator = HttpValidator::from_response(&response);
+    let inconsistency = if new_validator.as_ref() != Some(validator) {
+      Some(new_validator)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: HttpError) -> Self::Error { error }
+}
@@ -1,2 +1,30 @@
 pub mod file;
 pub mod map;
+pub mod closure;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "process")]
+pub mod process;
+
+/// Makes attempts with `attempt` until one succeeds, or until `retry` returns `false` for the error of a failed attempt
+/// after wrapping it with `wrap`. Returns the result of the last attempt.
+///
+/// Used to implement [`Resource::read_retrying`](crate::Resource::read_retrying) and
+/// [`Resource::write_retrying`](crate::Resource::write_retrying) for resources whose readers or writers do not borrow
+/// the resource state.
+#[inline]
+pub fn retry<T, E, W>(
+  mut attempt: impl FnMut() -> Result<T, E>,
+  mut wrap: impl FnMut(E) -> W,
+  mut retry: impl FnMut(&W) -> bool,
+) -> Result<T, W> {
+  loop {
+    let error = match attempt() {
+      Ok(output) => return Ok(output),
+      Err(error) => wrap(error),
+    };
+    if !retry(&error) {
+      return Err(error);
+    }
+  }
+}
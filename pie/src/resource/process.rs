@@ -0,0 +1,163 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{self, Cursor};
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+
+use crate::{Resource, resource, ResourceChecker, ResourceState};
+
+/// Process output resource: the captured standard output of running `program` with `args`. Can be opened for reading,
+/// which runs the process to completion. Cannot be opened for writing.
+///
+/// # Non-determinism
+///
+/// Reading this resource runs an external process, every time it is read or checked. Builds are only incremental and
+/// correct if the output of the process is deterministic: identical as long as nothing it depends on changed. Processes
+/// whose output includes timestamps, random values, process IDs, or anything else that changes on every run, will
+/// always be inconsistent and cause their reading tasks to always execute. Conversely, changes to things the process
+/// depends on that do not influence its output (e.g., its environment variables or working directory) are not detected.
+/// Furthermore, the process is run with the environment and working directory of the build, which may be different
+/// across builds.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ProcessOutputResource {
+  /// Program to run, resolved with the `PATH` environment variable if it is not a path.
+  pub program: String,
+  /// Arguments passed to `program`.
+  pub args: Vec<String>,
+}
+impl ProcessOutputResource {
+  /// Creates a process output resource for running `program` with `args`.
+  #[inline]
+  pub fn new(program: impl Into<String>, args: impl IntoIterator<Item=impl Into<String>>) -> Self {
+    Self { program: program.into(), args: args.into_iter().map(Into::into).collect() }
+  }
+}
+
+impl Resource for ProcessOutputResource {
+  type Reader<'rs> = Cursor<Vec<u8>>;
+  type Writer<'r> = Infallible;
+  type Error = ProcessError;
+
+  /// Runs the process to completion, returning a reader over its captured standard output.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if the process could not be run, or if it exited unsuccessfully.
+  #[inline]
+  fn read<RS: ResourceState<Self>>(&self, _state: &mut RS) -> Result<Cursor<Vec<u8>>, ProcessError> {
+    let output = Command::new(&self.program).args(&self.args).output()?;
+    if !output.status.success() {
+      return Err(ProcessError::ExitStatus(output.status.code()));
+    }
+    Ok(Cursor::new(output.stdout))
+  }
+
+  /// Makes attempts to [read](Self::read) this process output resource until one succeeds or `retry` returns `false`.
+  #[inline]
+  fn read_retrying<RS: ResourceState<Self>, W>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(ProcessError) -> W,
+    retry: impl FnMut(&W) -> bool,
+  ) -> Result<Cursor<Vec<u8>>, W> {
+    resource::retry(|| self.read(&mut *state), wrap, retry)
+  }
+
+  /// Always returns [`ProcessError::WriteUnsupported`], as process output resources cannot be written to.
+  #[inline]
+  fn write<RS: ResourceState<Self>>(&self, _state: &mut RS) -> Result<Infallible, ProcessError> {
+    Err(ProcessError::WriteUnsupported)
+  }
+}
+
+/// Process output resource error.
+///
+/// Like [`FsError`](crate::resource::file::FsError), this error is `Clone` so that it can be used in task outputs.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ProcessError {
+  /// The process could not be run due to an I/O error of this kind.
+  Io(io::ErrorKind),
+  /// The process exited unsuccessfully with this exit code, or `None` if it was terminated by a signal.
+  ExitStatus(Option<i32>),
+  /// Writing to a process output resource was attempted, which is not supported.
+  WriteUnsupported,
+}
+
+impl Error for ProcessError {}
+
+impl From<io::Error> for ProcessError {
+  #[inline]
+  fn from(value: io::Error) -> Self { Self::Io(value.kind()) }
+}
+impl Display for ProcessError {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Io(kind) => write!(f, "failed to run process: {}", kind),
+      Self::ExitStatus(Some(code)) => write!(f, "process exited unsuccessfully with code {}", code),
+      Self::ExitStatus(None) => write!(f, "process was terminated by a signal"),
+      Self::WriteUnsupported => write!(f, "writing to process output resources is not supported"),
+    }
+  }
+}
+
+
+/// Process output [resource checker](ResourceChecker) that hashes the standard output of a process and compares
+/// hashes. Checking runs the process again, see the [non-determinism](ProcessOutputResource#non-determinism) notes.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ProcessOutputHashChecker;
+
+impl ProcessOutputHashChecker {
+  #[inline]
+  fn hash(output: &[u8]) -> [u8; 32] {
+    Sha256::digest(output).into()
+  }
+}
+
+impl ResourceChecker<ProcessOutputResource> for ProcessOutputHashChecker {
+  type Stamp = [u8; 32];
+  type Error = ProcessError;
+
+  #[inline]
+  fn stamp<RS: ResourceState<ProcessOutputResource>>(
+    &self,
+    resource: &ProcessOutputResource,
+    state: &mut RS,
+  ) -> Result<Self::Stamp, Self::Error> {
+    Ok(Self::hash(resource.read(state)?.get_ref()))
+  }
+  #[inline]
+  fn stamp_reader(
+    &self,
+    _resource: &ProcessOutputResource,
+    reader: &mut Cursor<Vec<u8>>,
+  ) -> Result<Self::Stamp, Self::Error> {
+    Ok(Self::hash(reader.get_ref()))
+  }
+  #[inline]
+  fn stamp_writer(&self, _resource: &ProcessOutputResource, writer: Infallible) -> Result<Self::Stamp, Self::Error> {
+    match writer {}
+  }
+
+  #[inline]
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<ProcessOutputResource>>(
+    &self,
+    resource: &ProcessOutputResource,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, Self::Error> {
+    let hash = self.stamp(resource, state)?;
+    let inconsistency = if hash != *stamp {
+      Some(hash)
+    } else {
+      None
+    };
+    Ok(inconsistency)
+  }
+
+  #[inline]
+  fn wrap_error(&self, error: ProcessError) -> Self::Error { error }
+}
@@ -3,23 +3,29 @@ use std::collections::HashMap;
 
 use pie_graph::{DAG, Node};
 
-use crate::dependency::{Dependency, ResourceDependencyObj, TaskDependencyObj};
+use crate::dependency::{Dependency, DependencyKind, ResourceDependencyObj, TaskDependencyObj};
 use crate::trait_object::{KeyObj, ValueObj};
 use crate::trait_object::task::TaskObj;
 
 pub struct Store {
   graph: DAG<NodeData, Dependency>,
-  task_to_node: HashMap<Box<dyn TaskObj>, TaskNode>,
+  /// Maps [cache keys](crate::Task::cache_key) of tasks to their task nodes.
+  task_to_node: HashMap<Box<dyn KeyObj>, TaskNode>,
   resource_to_node: HashMap<Box<dyn KeyObj>, ResourceNode>,
+  /// Maps fixpoint tasks to the tasks that required them in a cycle, which have no dependency in the graph.
+  cyclic_requirers: HashMap<TaskNode, Vec<TaskNode>>,
 }
 
 impl Default for Store {
   #[inline]
   fn default() -> Self {
+    let mut graph = DAG::default();
+    graph.index_edge_kinds(); // Resources can have many readers: index dependencies by kind for fast filtering.
     Self {
-      graph: DAG::default(),
+      graph,
       task_to_node: HashMap::default(),
       resource_to_node: HashMap::default(),
+      cyclic_requirers: HashMap::default(),
     }
   }
 }
@@ -49,10 +55,12 @@ impl Borrow<Node> for &ResourceNode {
 }
 
 impl Store {
-  /// Gets the task node for `task`, or creates a task node by adding it to the dependency graph.
+  /// Gets the task node for the [cache key](crate::Task::cache_key) of `task`, or creates a task node by adding `task`
+  /// to the dependency graph.
   #[inline]
   pub fn get_or_create_task_node(&mut self, task: &dyn TaskObj) -> TaskNode {
-    if let Some(node) = self.task_to_node.get(task) {
+    let cache_key = task.cache_key_obj();
+    if let Some(node) = self.task_to_node.get(cache_key.as_ref()) {
       *node
     } else {
       let node = self.graph.add_node(NodeData::Task {
@@ -60,10 +68,16 @@ impl Store {
         output: None,
       });
       let node = TaskNode(node);
-      self.task_to_node.insert(task.to_owned(), node);
+      self.task_to_node.insert(cache_key, node);
       node
     }
   }
+  /// Gets the task node for the [cache key](crate::Task::cache_key) of `task`, returning `Some(node)` if it exists in
+  /// the dependency graph, `None` otherwise.
+  #[inline]
+  pub fn get_task_node(&self, task: &dyn TaskObj) -> Option<TaskNode> {
+    self.task_to_node.get(task.cache_key_obj().as_ref()).copied()
+  }
   /// Gets the task for `node`.
   ///
   /// # Panics
@@ -77,6 +91,20 @@ impl Store {
     task.as_ref()
   }
 
+  /// Gets all tasks in the dependency graph, sorted by their debug representation. Unlike iterating over the tasks
+  /// directly, the order is reproducible across runs, making it suitable for diagnostics.
+  #[inline]
+  pub fn get_tasks_sorted(&self) -> Vec<&dyn TaskObj> {
+    let mut tasks: Vec<_> = self.task_to_node.values().map(|n| self.get_task(n)).collect();
+    tasks.sort_by_cached_key(|t| format!("{:?}", t));
+    tasks
+  }
+  /// Gets all task nodes in the dependency graph, in no particular order.
+  #[inline]
+  pub fn get_task_nodes(&self) -> impl Iterator<Item=TaskNode> + '_ {
+    self.task_to_node.values().copied()
+  }
+
 
   /// Gets the resource node for `resource`, or creates a resource node by adding it to the dependency graph.
   #[inline]
@@ -102,6 +130,14 @@ impl Store {
     };
     resource.as_ref()
   }
+  /// Gets all resources in the dependency graph, sorted by their debug representation. Unlike iterating over the
+  /// resources directly, the order is reproducible across runs, making it suitable for diagnostics.
+  #[inline]
+  pub fn get_resources_sorted(&self) -> Vec<&dyn KeyObj> {
+    let mut resources: Vec<_> = self.resource_to_node.keys().map(|r| r.as_ref()).collect();
+    resources.sort_by_cached_key(|r| format!("{:?}", r));
+    resources
+  }
 
 
   /// Gets the output for task `node`.
@@ -129,6 +165,18 @@ impl Store {
     // OPTO: try to clone output into existing allocation for output. Also requires `reset_task` to not remove that.
     output.replace(new_output);
   }
+  /// Removes the output of task `node`, keeping its dependencies.
+  ///
+  /// # Panics
+  ///
+  /// Panics if task `node` was not found in the dependency graph.
+  #[inline]
+  pub fn remove_task_output(&mut self, node: &TaskNode) {
+    let Some(NodeData::Task { output, .. }) = self.graph.get_node_data_mut(node) else {
+      panic!("BUG: {:?} was not found in the dependency graph", node);
+    };
+    *output = None;
+  }
 
   /// Compare task `node_a` and  task `node_b`, topographically.
   ///
@@ -162,8 +210,7 @@ impl Store {
   #[inline]
   pub fn get_tasks_reading_from_resource<'a>(&'a self, dst: &'a ResourceNode) -> impl Iterator<Item=TaskNode> + 'a {
     debug_assert!(self.graph.contains_node(dst), "BUG: {:?} was not found in the dependency graph", dst);
-    self.graph.get_incoming_edges(dst)
-      .filter_map(|(n, d)| matches!(d, Dependency::Read(_)).then(|| TaskNode(*n)))
+    self.graph.get_incoming_edges_of_kind(dst, DependencyKind::Read).map(|(n, _)| TaskNode(*n))
   }
   /// Get the task node that writes to resource `dst`, or `None` if there is none.
   ///
@@ -173,9 +220,7 @@ impl Store {
   #[inline]
   pub fn get_task_writing_to_resource(&self, dst: &ResourceNode) -> Option<TaskNode> {
     debug_assert!(self.graph.contains_node(dst), "BUG: {:?} was not found in the dependency graph", dst);
-    self.graph.get_incoming_edges(dst)
-      .filter_map(|(n, d)| matches!(d, Dependency::Write(_)).then(|| TaskNode(*n)))
-      .next()
+    self.graph.get_incoming_edges_of_kind(dst, DependencyKind::Write).map(|(n, _)| TaskNode(*n)).next()
   }
   /// Get all task nodes and corresponding dependencies that read from to resource `dst`.
   ///
@@ -185,7 +230,7 @@ impl Store {
   #[inline]
   pub fn get_read_dependencies_to_resource<'a>(&'a self, dst: &'a ResourceNode) -> impl Iterator<Item=(TaskNode, &dyn ResourceDependencyObj)> + 'a {
     debug_assert!(self.graph.contains_node(dst), "BUG: {:?} was not found in the dependency graph", dst);
-    self.graph.get_incoming_edges(dst).filter_map(|(n, d)| match d {
+    self.graph.get_incoming_edges_of_kind(dst, DependencyKind::Read).filter_map(|(n, d)| match d {
       Dependency::Read(rd) => Some((TaskNode(*n), rd.as_ref())),
       _ => None,
     })
@@ -220,7 +265,7 @@ impl Store {
   #[inline]
   pub fn get_require_dependencies_to_task<'a>(&'a self, dst: &'a TaskNode) -> impl Iterator<Item=(TaskNode, &dyn TaskDependencyObj)> + 'a {
     debug_assert!(self.graph.contains_node(dst), "BUG: {:?} was not found in the dependency graph", dst);
-    self.graph.get_incoming_edges(dst)
+    self.graph.get_incoming_edges_of_kind(dst, DependencyKind::Require)
       .filter_map(|(n, d)| match d {
         Dependency::Require(td) => Some((TaskNode(*n), td.as_ref())),
         _ => None
@@ -257,20 +302,33 @@ impl Store {
       _ => Ok(()),
     }
   }
-  /// Gets the mutable `dependency` from `src` to `dst`.
+  /// Adds resource `dependency` from `src` to `dst`. If a resource dependency from `src` to `dst` already exists,
+  /// `dependency` is [merged](Dependency::merge_resource) into it instead.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `src` or `dst` was not found in the dependency graph, or if `dependency` or the existing dependency is
+  /// not a resource dependency.
+  pub fn add_resource_dependency(&mut self, src: &TaskNode, dst: &ResourceNode, dependency: Dependency) {
+    if let Some(existing) = self.graph.replace_edge_data(src, dst, Dependency::ReservedRequire) {
+      self.graph.replace_edge_data(src, dst, existing.merge_resource(dependency));
+    } else {
+      let _ = self.add_dependency(src, dst, dependency);
+    }
+  }
+  /// Sets the dependency from `src` to `dst` to `dependency`.
   ///
   /// # Panics
   ///
   /// Panics if `src` or `dst` were not found in the dependency graph, or if the dependency from `src` to `dst` was not
   /// found in the dependency graph.
   #[inline]
-  pub fn get_dependency_mut(&mut self, src: impl Borrow<Node>, dst: impl Borrow<Node>) -> &mut Dependency {
+  pub fn set_dependency(&mut self, src: impl Borrow<Node>, dst: impl Borrow<Node>, dependency: Dependency) {
     let src = src.borrow();
     let dst = dst.borrow();
-    let Some(dependency) = self.graph.get_edge_data_mut(src, dst) else {
+    if self.graph.replace_edge_data(src, dst, dependency).is_none() {
       panic!("BUG: no task dependency was found between source {:?} and destination {:?}", src, dst)
-    };
-    dependency
+    }
   }
 
 
@@ -288,6 +346,82 @@ impl Store {
     }
     self.graph.remove_outgoing_edges_of_node(src);
   }
+  /// Reset all tasks, removing their outputs and removing all their outgoing dependencies. Task and resource nodes are
+  /// retained.
+  #[inline]
+  pub fn reset_all_tasks(&mut self) {
+    let task_nodes: Vec<_> = self.task_to_node.values().copied().collect();
+    for task_node in task_nodes {
+      self.reset_task(&task_node);
+    }
+  }
+  /// Reset all tasks without an output, removing all their outgoing dependencies. Tasks whose execution was
+  /// interrupted have no output, but may have partially created dependencies.
+  #[inline]
+  pub fn reset_tasks_without_output(&mut self) {
+    let task_nodes: Vec<_> = self.task_to_node.values()
+      .filter(|n| self.get_task_output(n).is_none())
+      .copied()
+      .collect();
+    for task_node in task_nodes {
+      self.reset_task(&task_node);
+    }
+  }
+  /// Reset task `src` and all tasks without an output that `src` (transitively) depends on, removing all their
+  /// outgoing dependencies. Tasks whose execution was interrupted while making `src` consistent have no output, but
+  /// may have partially created dependencies. The output of `src` is also removed, as it may be stale.
+  ///
+  /// # Panics
+  ///
+  /// Panics if task `src` was not found in the dependency graph.
+  #[inline]
+  pub fn reset_tasks_without_output_from(&mut self, src: &TaskNode) {
+    let task_nodes: Vec<_> = self.graph.descendants(src)
+      .expect("BUG: node was not found in the dependency graph")
+      .filter(|n| matches!(self.graph.get_node_data(n), Some(NodeData::Task { output: None, .. })))
+      .map(TaskNode)
+      .collect();
+    self.reset_task(src);
+    for task_node in task_nodes {
+      self.reset_task(&task_node);
+    }
+  }
+
+  /// Gets the tasks that required fixpoint task `dst` in a cycle.
+  #[inline]
+  pub fn get_cyclic_requirers(&self, dst: &TaskNode) -> &[TaskNode] {
+    self.cyclic_requirers.get(dst).map_or(&[], |requirers| requirers.as_slice())
+  }
+  /// Adds task `src` as a task that required fixpoint task `dst` in a cycle, if it is not in there yet.
+  #[inline]
+  pub fn add_cyclic_requirer(&mut self, src: &TaskNode, dst: &TaskNode) {
+    let requirers = self.cyclic_requirers.entry(*dst).or_default();
+    if !requirers.contains(src) {
+      requirers.push(*src);
+    }
+  }
+  /// Removes and returns the tasks that required fixpoint task `dst` in a cycle.
+  #[inline]
+  pub fn take_cyclic_requirers(&mut self, dst: &TaskNode) -> Vec<TaskNode> {
+    self.cyclic_requirers.remove(dst).unwrap_or_default()
+  }
+  /// Gets task `src`, tasks `dsts`, and all tasks on dependency paths from `src` to `dsts`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if task `src` was not found in the dependency graph.
+  #[inline]
+  pub fn get_tasks_on_paths(&self, src: &TaskNode, dsts: &[TaskNode]) -> Vec<TaskNode> {
+    let mut task_nodes: Vec<_> = self.graph.descendants(src)
+      .expect("BUG: node was not found in the dependency graph")
+      .filter(|n| matches!(self.graph.get_node_data(n), Some(NodeData::Task { .. })))
+      .map(TaskNode)
+      .filter(|n| !dsts.contains(n) && dsts.iter().any(|dst| self.graph.contains_transitive_edge(n, dst)))
+      .collect();
+    task_nodes.push(*src);
+    task_nodes.extend(dsts.iter().filter(|dst| *dst != src));
+    task_nodes
+  }
 }
 
 
@@ -423,6 +557,11 @@ mod test {
     store.set_task_output(&node_b, Box::new(output_b));
     assert_eq!(store.get_task_output(&node_a).map(|v| v.as_str()), Some(output_a));
     assert_eq!(store.get_task_output(&node_b).map(|v| v.as_str()), Some(output_b));
+
+    // Remove output for task A, assert that A has no output but B is unchanged.
+    store.remove_task_output(&node_a);
+    assert_matches!(store.get_task_output(&node_a), None);
+    assert_eq!(store.get_task_output(&node_b).map(|v| v.as_str()), Some(output_b));
   }
 
   #[test]
@@ -525,7 +664,7 @@ mod test {
 
     // Update task dependency from task B to task A.
     let require_b2a = TaskDependency::new(task_a, EqualsChecker, output_a).into_require();
-    *store.get_dependency_mut(&node_b, &node_a) = require_b2a.clone();
+    store.set_dependency(&node_b, &node_a, require_b2a.clone());
     assert!(!store.contains_transitive_task_dependency(&node_a, &node_b));
     assert!(store.contains_transitive_task_dependency(&node_b, &node_a));
     let reads_from_c: Vec<_> = store.get_tasks_reading_from_resource(&node_c).collect();
@@ -589,6 +728,46 @@ mod test {
     assert_eq!(result, Err(())); // Creates a cycle: error
   }
 
+  #[test]
+  fn test_add_resource_dependency() {
+    let mut store = Store::default();
+    let node_a = store.get_or_create_task_node(&"Hello");
+    let path_b = PathBuf::from("hello.txt");
+    let node_b = store.get_or_create_resource_node(&path_b);
+    let stamp = |store: &Store| -> Option<bool> {
+      let Some(Dependency::Read(d) | Dependency::Write(d)) = store.get_dependencies_from_task(&node_a).next() else {
+        panic!("expected a resource dependency");
+      };
+      d.stamps()[0].as_any().downcast_ref::<bool>().copied()
+    };
+
+    // Read twice with equal checkers: deduplicated, keeping the stamp of the first read.
+    let exists = |stamp| ResourceDependency::new(path_b.clone(), ExistsChecker, stamp);
+    store.add_resource_dependency(&node_a, &node_b, exists(false).into_read());
+    store.add_resource_dependency(&node_a, &node_b, exists(true).into_read());
+    assert_eq!(store.get_dependencies_from_task(&node_a).count(), 1);
+    assert_matches!(store.get_dependencies_from_task(&node_a).next(), Some(Dependency::Read(_)));
+    assert_eq!(stamp(&store), Some(false));
+
+    // Write with an equal checker: deduplicated into a write dependency, keeping the stamp of the write.
+    store.add_resource_dependency(&node_a, &node_b, exists(true).into_write());
+    assert_eq!(store.get_dependencies_from_task(&node_a).count(), 1);
+    assert_matches!(store.get_dependencies_from_task(&node_a).next(), Some(Dependency::Write(_)));
+    assert_eq!(stamp(&store), Some(true));
+    assert_eq!(store.get_task_writing_to_resource(&node_b), Some(node_a));
+
+    // Read with a different checker: merged into a single write dependency that keeps both checkers.
+    let modified = ResourceDependency::new(path_b.clone(), ModifiedChecker, None);
+    store.add_resource_dependency(&node_a, &node_b, modified.into_read());
+    assert_eq!(store.get_dependencies_from_task(&node_a).count(), 1);
+    assert_matches!(store.get_dependencies_from_task(&node_a).next(), Some(Dependency::Write(_)));
+    assert_eq!(stamp(&store), Some(true));
+    assert_matches!(store.get_dependencies_from_task(&node_a).next(), Some(Dependency::Write(d)) => {
+      assert_eq!(d.stamps().len(), 2);
+    });
+    assert_eq!(store.get_task_writing_to_resource(&node_b), Some(node_a));
+  }
+
   #[test]
   #[should_panic(expected = "was not found in the dependency graph")]
   fn test_contains_transitive_task_dependency_panics() {
@@ -717,6 +896,37 @@ mod test {
     assert_eq!(deps_of_b.get(1), None);
   }
 
+  #[test]
+  fn test_reset_all_tasks() {
+    let mut store = Store::default();
+    let output_a = "Hello";
+    let task_a = output_a;
+    let task_a_node = store.get_or_create_task_node(&task_a);
+    let output_b = "World";
+    let task_b = output_b;
+    let task_b_node = store.get_or_create_task_node(&task_b);
+    let path = PathBuf::from("hello.txt");
+    let resource_node = store.get_or_create_resource_node(&path);
+
+    store.set_task_output(&task_a_node, Box::new(output_a));
+    store.set_task_output(&task_b_node, Box::new(output_b));
+    let require_dep = TaskDependency::new(task_b, EqualsChecker, output_b).into_require();
+    assert_eq!(store.add_dependency(&task_a_node, &task_b_node, require_dep), Ok(()));
+    let read_dep = ResourceDependency::new(path.clone(), ExistsChecker, true).into_read();
+    assert_eq!(store.add_dependency(&task_b_node, &resource_node, read_dep), Ok(()));
+
+    store.reset_all_tasks();
+    // Assert that all outputs and dependencies are removed.
+    assert_matches!(store.get_task_output(&task_a_node), None);
+    assert_matches!(store.get_task_output(&task_b_node), None);
+    assert_eq!(store.get_dependencies_from_task(&task_a_node).next(), None);
+    assert_eq!(store.get_dependencies_from_task(&task_b_node).next(), None);
+    // Assert that nodes are retained.
+    assert_eq!(store.get_task_node(&task_a), Some(task_a_node));
+    assert_eq!(store.get_task_node(&task_b), Some(task_b_node));
+    assert_eq!(store.get_or_create_resource_node(&path), resource_node);
+  }
+
   #[test]
   #[should_panic(expected = "was not found in the dependency graph")]
   fn test_reset_task_panics() {
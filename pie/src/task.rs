@@ -1,10 +1,17 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::convert::Infallible;
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::hash::{BuildHasher, DefaultHasher, Hash, Hasher};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::sync::atomic::{self, AtomicU64};
+use std::time::{Duration, Instant};
 
-use crate::{Context, OutputChecker, Task, Value};
+use pie_graph::{DAG, Node};
+
+use crate::{Context, Key, OutputChecker, Task, Value};
+use crate::trait_object::{KeyObj, task_checker_obj, TaskCheckerObj};
 
 /// [Task output checker](OutputChecker) that checks by equality.
 #[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
@@ -108,6 +115,482 @@ impl<O> OutputChecker<O> for AlwaysConsistent {
 }
 
 
+/// [Task output checker](OutputChecker) that checks by equality of hashes. The stamp is a `u64` hash of the output,
+/// so the stored stamp is small and constant in size regardless of the size of the output, trading a tiny risk of hash
+/// collisions (missing a change) for memory. Useful for large outputs such as big strings or vectors.
+///
+/// Outputs are hashed with [`DefaultHasher`], which is deterministic within a single build of a program, but not
+/// guaranteed to be stable across Rust versions.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct HashOutputChecker;
+impl HashOutputChecker {
+  #[inline]
+  fn hash<O: Hash>(output: &O) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    output.hash(&mut hasher);
+    hasher.finish()
+  }
+}
+impl<O: Hash> OutputChecker<O> for HashOutputChecker {
+  type Stamp = u64;
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    Self::hash(output)
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let hash = Self::hash(output);
+    if hash != *stamp {
+      Some(hash)
+    } else {
+      None
+    }
+  }
+}
+
+/// [Task output checker](OutputChecker) that checks by equality of serializations, for outputs that implement
+/// [`Serialize`](serde::Serialize) but not [`Eq`], such as outputs that contain floats, or [`Hash`], such as outputs that
+/// contain [`HashMap`s](std::collections::HashMap). The stamp is the serialized output as canonical JSON bytes.
+///
+/// Outputs are first serialized into a [`serde_json::Value`], whose maps are sorted by key, which canonicalizes map
+/// ordering. Therefore, maps with the same entries have equal serializations, regardless of their iteration order.
+/// However, the following caveats apply:
+///
+/// - Sequences are not canonicalized, so the iteration order of sequence-like collections such as
+///   [`HashSet`](std::collections::HashSet) affects the serialization. Two `HashSet`s with the same elements may still
+///   be inconsistent.
+/// - Map keys must serialize to strings or numbers, as required by JSON.
+/// - Non-finite floats (`NaN` and infinities) serialize to `null`, and are therefore considered equal to each other.
+/// - Custom [`Serialize`](serde::Serialize) implementations must be deterministic.
+///
+/// If the output fails to serialize, for example when a map has keys that do not serialize to strings or numbers, the
+/// stamp is [`None`], which is always inconsistent. Such outputs therefore never cause a requiring task to be
+/// considered consistent.
+#[cfg(feature = "serde")]
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct SerdeEqChecker;
+#[cfg(feature = "serde")]
+impl SerdeEqChecker {
+  #[inline]
+  fn serialize<O: serde::Serialize>(output: &O) -> Option<Vec<u8>> {
+    let value = serde_json::to_value(output).ok()?;
+    serde_json::to_vec(&value).ok()
+  }
+}
+#[cfg(feature = "serde")]
+impl<O: serde::Serialize> OutputChecker<O> for SerdeEqChecker {
+  type Stamp = Option<Vec<u8>>;
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    Self::serialize(output)
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let serialized = Self::serialize(output);
+    let consistent = matches!((&serialized, stamp), (Some(serialized), Some(stamp)) if serialized == stamp);
+    if !consistent {
+      Some(serialized.map(|serialized| String::from_utf8_lossy(&serialized).into_owned()))
+    } else {
+      None
+    }
+  }
+}
+
+
+/// [Task output checker](OutputChecker) combinator that rate-limits rebuilds of dependents with high-churn outputs:
+/// inconsistencies reported by inner checker `H` are suppressed (reported as consistent) if less than `min_interval`
+/// has elapsed since the output was stamped, which is the last time the requiring task observed a change. Thus, the
+/// requiring task is re-executed due to changes to the output at most once per `min_interval`.
+///
+/// This trades staleness for fewer rebuilds: within `min_interval`, the requiring task keeps its output that was
+/// created from an outdated output of the required task. A suppressed change is only picked up when the output is
+/// checked again after `min_interval` has elapsed, and if the output changed back in the meantime, it is not picked up
+/// at all. Therefore, do not use this checker when the requiring task must always produce up-to-date outputs.
+///
+/// The current time is read from clock `C`, which is [`SystemClock`] by default.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DebounceChecker<H, C = SystemClock> {
+  /// Inner checker that checks outputs.
+  pub inner: H,
+  /// Minimum duration between stamping an output and reporting an inconsistency of it.
+  pub min_interval: Duration,
+  /// Clock that provides the current time.
+  pub clock: C,
+}
+impl<H> DebounceChecker<H> {
+  /// Creates a new debounce checker that suppresses inconsistencies of `inner` for `min_interval` after stamping.
+  #[inline]
+  pub fn new(inner: H, min_interval: Duration) -> Self { Self::with_clock(inner, min_interval, SystemClock) }
+}
+impl<H, C> DebounceChecker<H, C> {
+  /// Creates a new debounce checker that suppresses inconsistencies of `inner` for `min_interval` after stamping, as
+  /// measured by `clock`.
+  #[inline]
+  pub fn with_clock(inner: H, min_interval: Duration, clock: C) -> Self { Self { inner, min_interval, clock } }
+}
+impl<O, H: OutputChecker<O>, C: Clock> OutputChecker<O> for DebounceChecker<H, C> {
+  /// `(stamp, stamped_at)` where `stamp` is the stamp of the inner checker, and `stamped_at` the time of stamping.
+  type Stamp = (H::Stamp, Instant);
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    (self.inner.stamp(output), self.clock.now())
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let (stamp, stamped_at) = stamp;
+    if self.clock.now().saturating_duration_since(*stamped_at) < self.min_interval {
+      return None;
+    }
+    self.inner.check(output, stamp)
+  }
+}
+
+/// Source of the current time for time-based checkers such as [`DebounceChecker`]. Implement this to control time, for
+/// example in tests.
+pub trait Clock: Key {
+  /// Returns the current time.
+  fn now(&self) -> Instant;
+}
+/// [Clock] that returns the current time of the system with [`Instant::now`].
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct SystemClock;
+impl Clock for SystemClock {
+  #[inline]
+  fn now(&self) -> Instant { Instant::now() }
+}
+
+
+/// [Task output checker](OutputChecker) that checks floating-point outputs by approximate equality: outputs are
+/// consistent when they are within `epsilon` of the stamp. Useful for outputs of non-deterministic computations that
+/// differ slightly between executions.
+///
+/// The stamp is the raw output. Because dependencies are only re-stamped when the requiring task executes, the stamp
+/// is the first-seen value, so small differences do not accumulate into a large drift.
+///
+/// `NaN` is never approximately equal to anything, so changes to or from `NaN` are inconsistent, and a `NaN` output is
+/// always inconsistent.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct ApproxEqualsChecker {
+  /// Maximum absolute difference between an output and its stamp for them to be considered equal.
+  pub epsilon: f64,
+}
+impl ApproxEqualsChecker {
+  /// Creates a new approximate equality checker with given `epsilon`.
+  #[inline]
+  pub fn new(epsilon: f64) -> Self { Self { epsilon } }
+}
+impl<O: Value + AsF64> OutputChecker<O> for ApproxEqualsChecker {
+  type Stamp = O;
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    output.clone()
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    // Note: compare with `<=` so that a NaN output or stamp, for which every comparison is false, is inconsistent.
+    if (output.as_f64() - stamp.as_f64()).abs() <= self.epsilon {
+      None
+    } else {
+      Some(output)
+    }
+  }
+}
+// Manual implementations: `f64` implements neither `Eq` nor `Hash`, so compare and hash the bits of `epsilon`.
+impl PartialEq for ApproxEqualsChecker {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool { self.epsilon.to_bits() == other.epsilon.to_bits() }
+}
+impl Eq for ApproxEqualsChecker {}
+impl Hash for ApproxEqualsChecker {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) { self.epsilon.to_bits().hash(state) }
+}
+
+/// Conversion into `f64`, for [`ApproxEqualsChecker`].
+pub trait AsF64 {
+  /// Converts `self` into an `f64`.
+  fn as_f64(&self) -> f64;
+}
+impl AsF64 for f64 {
+  #[inline]
+  fn as_f64(&self) -> f64 { *self }
+}
+impl AsF64 for f32 {
+  #[inline]
+  fn as_f64(&self) -> f64 { *self as f64 }
+}
+
+/// [Task output checker](OutputChecker) that checks collection outputs only by whether they contain the element of
+/// interest. Useful when a dependent only cares about the presence of a specific element, and not about changes to
+/// other elements.
+#[derive(Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct ContainsChecker<E>(pub E);
+impl<E> ContainsChecker<E> {
+  /// Creates a new contains checker that checks whether outputs contain `element`.
+  #[inline]
+  pub fn new(element: E) -> Self { Self(element) }
+}
+impl<E: Key, O: Contains<E>> OutputChecker<O> for ContainsChecker<E> {
+  type Stamp = bool;
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    output.contains_element(&self.0)
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let contains = output.contains_element(&self.0);
+    if contains != *stamp {
+      Some(contains)
+    } else {
+      None
+    }
+  }
+}
+
+/// Collections that can be queried for containing an element, for [`ContainsChecker`].
+pub trait Contains<E> {
+  /// Returns `true` if `self` contains `element`, `false` otherwise.
+  fn contains_element(&self, element: &E) -> bool;
+}
+impl<E: PartialEq> Contains<E> for Vec<E> {
+  #[inline]
+  fn contains_element(&self, element: &E) -> bool { self.contains(element) }
+}
+impl<E: Eq + Hash, S: BuildHasher> Contains<E> for HashSet<E, S> {
+  #[inline]
+  fn contains_element(&self, element: &E) -> bool { self.contains(element) }
+}
+impl<E: Ord> Contains<E> for BTreeSet<E> {
+  #[inline]
+  fn contains_element(&self, element: &E) -> bool { self.contains(element) }
+}
+
+
+/// [Task output checker](OutputChecker) that checks [indexable](Indexable) outputs, such as tuples and [`Vec`s](Vec),
+/// only by equality of their element at index `I`. Useful when a task bundles multiple results in its output, and a
+/// dependent only cares about changes to one of them.
+///
+/// Outputs without an element at index `I`, such as [`Vec`s](Vec) that are too short, are consistent with each other.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct IndexChecker<const I: usize>;
+impl<O: Indexable<I>, const I: usize> OutputChecker<O> for IndexChecker<I> where
+  O::Element: Value + Eq
+{
+  type Stamp = Option<O::Element>;
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    output.element().cloned()
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let element = output.element();
+    if element != stamp.as_ref() {
+      Some(element)
+    } else {
+      None
+    }
+  }
+}
+
+/// Outputs that have an element at index `I`, for [`IndexChecker`].
+pub trait Indexable<const I: usize> {
+  /// Type of the element at index `I`.
+  type Element;
+  /// Returns `Some(&element)` with the element at index `I`, or `None` if there is no element at index `I`.
+  fn element(&self) -> Option<&Self::Element>;
+}
+impl<T, const I: usize> Indexable<I> for Vec<T> {
+  type Element = T;
+  #[inline]
+  fn element(&self) -> Option<&T> { self.get(I) }
+}
+macro_rules! impl_indexable_tuple {
+  ($index:tt, $element:ident, ($($t:ident),+)) => {
+    impl<$($t),+> Indexable<$index> for ($($t,)+) {
+      type Element = $element;
+      #[inline]
+      fn element(&self) -> Option<&$element> { Some(&self.$index) }
+    }
+  };
+}
+impl_indexable_tuple!(0, A, (A));
+impl_indexable_tuple!(0, A, (A, B));
+impl_indexable_tuple!(1, B, (A, B));
+impl_indexable_tuple!(0, A, (A, B, C));
+impl_indexable_tuple!(1, B, (A, B, C));
+impl_indexable_tuple!(2, C, (A, B, C));
+impl_indexable_tuple!(0, A, (A, B, C, D));
+impl_indexable_tuple!(1, B, (A, B, C, D));
+impl_indexable_tuple!(2, C, (A, B, C, D));
+impl_indexable_tuple!(3, D, (A, B, C, D));
+
+/// [Task output checker](OutputChecker) that checks outputs [with a length](HasLen), such as [`Vec`s](Vec), only by
+/// equality of their length. Useful when a dependent only cares about the number of elements in an output, not the
+/// elements themselves.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct LenChecker;
+impl<O: HasLen> OutputChecker<O> for LenChecker {
+  type Stamp = usize;
+  #[inline]
+  fn stamp(&self, output: &O) -> Self::Stamp {
+    output.len()
+  }
+
+  #[inline]
+  fn check(&self, output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let len = output.len();
+    if len != *stamp {
+      Some(len)
+    } else {
+      None
+    }
+  }
+}
+
+/// Outputs that have a length, for [`LenChecker`].
+pub trait HasLen {
+  /// Returns the length.
+  fn len(&self) -> usize;
+  /// Returns whether the length is 0.
+  #[inline]
+  fn is_empty(&self) -> bool { self.len() == 0 }
+}
+impl<T> HasLen for Vec<T> {
+  #[inline]
+  fn len(&self) -> usize { Vec::len(self) }
+}
+impl HasLen for String {
+  #[inline]
+  fn len(&self) -> usize { String::len(self) }
+}
+impl<K, V, S> HasLen for HashMap<K, V, S> {
+  #[inline]
+  fn len(&self) -> usize { HashMap::len(self) }
+}
+
+/// [Task output checker](OutputChecker) that ignores outputs, and instead checks the value of a shared generation
+/// counter: dependents are inconsistent when the generation has advanced since they were stamped. Useful for
+/// invalidating dependents by an external event, such as a schema version bump, independently of output content.
+///
+/// Checkers are equal if they share the same counter. The counter is only checked when a dependency is checked, so in
+/// bottom-up builds, advancing the generation only affects dependents of tasks that are executed.
+#[derive(Default, Clone, Debug)]
+pub struct GenerationChecker(Arc<AtomicU64>);
+impl GenerationChecker {
+  /// Creates a new generation checker that checks `generation`.
+  #[inline]
+  pub fn new(generation: Arc<AtomicU64>) -> Self { Self(generation) }
+  /// Gets the current generation.
+  #[inline]
+  pub fn generation(&self) -> u64 { self.0.load(atomic::Ordering::SeqCst) }
+  /// Advances the generation, making all dependents that were stamped with this checker inconsistent. Returns the new
+  /// generation.
+  #[inline]
+  pub fn advance(&self) -> u64 { self.0.fetch_add(1, atomic::Ordering::SeqCst) + 1 }
+}
+impl<O> OutputChecker<O> for GenerationChecker {
+  type Stamp = u64;
+  #[inline]
+  fn stamp(&self, _output: &O) -> Self::Stamp {
+    self.generation()
+  }
+
+  #[inline]
+  fn check(&self, _output: &O, stamp: &Self::Stamp) -> Option<impl Debug> {
+    let generation = self.generation();
+    if generation != *stamp {
+      Some(generation)
+    } else {
+      None
+    }
+  }
+}
+// Manual implementations: `AtomicU64` implements neither `Eq` nor `Hash`, so compare and hash the counter identity.
+impl PartialEq for GenerationChecker {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool { Arc::ptr_eq(&self.0, &other.0) }
+}
+impl Eq for GenerationChecker {}
+impl Hash for GenerationChecker {
+  #[inline]
+  fn hash<H: Hasher>(&self, state: &mut H) { Arc::as_ptr(&self.0).hash(state) }
+}
+
+/// Explicit task graph of [boxed tasks](KeyObj), their [checkers](TaskCheckerObj), and their intended dependencies, for
+/// [requiring in topological order](crate::Session::require_graph). Useful for build systems generated from a manifest,
+/// which already have an explicit task graph.
+///
+/// The graph is only a scheduling hint: tasks still create their own dependencies when they execute, and are still
+/// checked incrementally.
+#[derive(Default)]
+pub struct TaskGraph {
+  graph: DAG<TaskGraphNode, ()>,
+}
+type TaskGraphNode = (Box<dyn KeyObj>, Box<dyn TaskCheckerObj>);
+/// Identifier of a task in a [`TaskGraph`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[repr(transparent)]
+pub struct TaskId(Node);
+impl TaskGraph {
+  /// Creates a new empty task graph.
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Adds `task` to this graph, which is required with `checker`, returning its identifier.
+  #[inline]
+  pub fn add_task<T: Task, H: OutputChecker<T::Output>>(&mut self, task: T, checker: H) -> TaskId {
+    self.add_task_dyn(Box::new(task), task_checker_obj::<T, H>(checker))
+  }
+  /// Adds boxed `task` to this graph, which is required with boxed `checker`, returning its identifier. The task type
+  /// of `checker` must match the type of `task`, or else [requiring the graph](crate::Session::require_graph) panics.
+  #[inline]
+  pub fn add_task_dyn(&mut self, task: Box<dyn KeyObj>, checker: Box<dyn TaskCheckerObj>) -> TaskId {
+    TaskId(self.graph.add_node((task, checker)))
+  }
+
+  /// Adds an intended dependency from `task` to `dependency`, so that `dependency` is required before `task`. Returns
+  /// `true` if the dependency was added, or `false` if it already existed, or if it would create a cycle, in which case
+  /// it is not added.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `task` or `dependency` is not in this graph.
+  #[inline]
+  pub fn add_dependency(&mut self, task: TaskId, dependency: TaskId) -> bool {
+    match self.graph.add_edge(dependency.0, task.0, ()) {
+      Ok(added) => added,
+      Err(pie_graph::Error::CycleDetected) => false,
+      Err(_) => panic!("task or dependency is not in this task graph"),
+    }
+  }
+
+  /// Gets the boxed task for `id`, or `None` if it is not in this graph.
+  #[inline]
+  pub fn get_task(&self, id: TaskId) -> Option<&dyn KeyObj> {
+    self.graph.get_node_data(id.0).map(|(task, _)| task.as_ref())
+  }
+  /// Returns the number of tasks in this graph.
+  #[inline]
+  pub fn len(&self) -> usize { self.graph.len() }
+  /// Returns `true` if this graph has no tasks.
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.graph.is_empty() }
+
+  /// Returns an iterator over tasks in this graph in topological order: dependencies before the tasks that depend on
+  /// them.
+  #[inline]
+  pub(crate) fn iter_topo(&self) -> impl Iterator<Item=(TaskId, &dyn KeyObj, &dyn TaskCheckerObj)> + '_ {
+    self.graph.iter_nodes_topo().map(|(node, (task, checker))| (TaskId(node), task.as_ref(), checker.as_ref()))
+  }
+}
+
+
 /// Implement task for `()` that does nothing and just returns `()`.
 impl Task for () {
   type Output = ();
@@ -117,27 +600,91 @@ impl Task for () {
   }
 }
 
-/// Implement task for [`Box`] wrapped tasks.
+/// Returns the [cache key](Task::cache_key) of `task`, wrapped with `wrap` if it is a (normalized) task of type `T`,
+/// so that the cache keys of wrapped tasks are not equal to those of unwrapped tasks. Cache keys of other types are
+/// returned as is, in which case wrapped and unwrapped tasks share their cache entry, which is sound as their outputs
+/// have the same type.
+#[inline]
+fn wrap_cache_key<T: Task, W: Task>(task: &T, wrap: impl FnOnce(T) -> W) -> Box<dyn KeyObj> {
+  let cache_key = task.cache_key();
+  match cache_key.as_ref().as_any().downcast_ref::<T>() {
+    Some(task) => Box::new(wrap(task.clone())),
+    None => cache_key,
+  }
+}
+
+/// Implement task for [`Box`] wrapped tasks. The [cache key](Task::cache_key) of the wrapped task is wrapped into
+/// a `Box`.
 impl<T: Task> Task for Box<T> {
   type Output = T::Output;
   #[inline]
+  fn precondition<C: Context>(&self, context: &mut C) -> Result<(), String> {
+    self.as_ref().precondition(context)
+  }
+  #[inline]
   fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
     self.as_ref().execute(context)
   }
+  #[inline]
+  fn always_execute(&self) -> bool {
+    self.as_ref().always_execute()
+  }
+  #[inline]
+  fn describe(&self) -> Cow<'_, str> {
+    self.as_ref().describe()
+  }
+  #[inline]
+  fn cache_key(&self) -> Box<dyn KeyObj> {
+    wrap_cache_key(self.as_ref(), Box::new)
+  }
 }
-/// Implement task for [`Rc`] wrapped tasks.
+/// Implement task for [`Rc`] wrapped tasks. The [cache key](Task::cache_key) of the wrapped task is wrapped into
+/// an `Rc`.
 impl<T: Task> Task for Rc<T> {
   type Output = T::Output;
   #[inline]
+  fn precondition<C: Context>(&self, context: &mut C) -> Result<(), String> {
+    self.as_ref().precondition(context)
+  }
+  #[inline]
   fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
     self.as_ref().execute(context)
   }
+  #[inline]
+  fn always_execute(&self) -> bool {
+    self.as_ref().always_execute()
+  }
+  #[inline]
+  fn describe(&self) -> Cow<'_, str> {
+    self.as_ref().describe()
+  }
+  #[inline]
+  fn cache_key(&self) -> Box<dyn KeyObj> {
+    wrap_cache_key(self.as_ref(), Rc::new)
+  }
 }
-/// Implement task for [`Arc`] wrapped tasks.
+/// Implement task for [`Arc`] wrapped tasks. The [cache key](Task::cache_key) of the wrapped task is wrapped into
+/// an `Arc`.
 impl<T: Task> Task for Arc<T> {
   type Output = T::Output;
   #[inline]
+  fn precondition<C: Context>(&self, context: &mut C) -> Result<(), String> {
+    self.as_ref().precondition(context)
+  }
+  #[inline]
   fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
     self.as_ref().execute(context)
   }
+  #[inline]
+  fn always_execute(&self) -> bool {
+    self.as_ref().always_execute()
+  }
+  #[inline]
+  fn describe(&self) -> Cow<'_, str> {
+    self.as_ref().describe()
+  }
+  #[inline]
+  fn cache_key(&self) -> Box<dyn KeyObj> {
+    wrap_cache_key(self.as_ref(), Arc::new)
+  }
 }
@@ -0,0 +1,62 @@
+use std::error::Error;
+
+use crate::Change;
+use crate::tracker::Tracker;
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
+
+/// A [`Tracker`] that captures errors produced while checking dependencies, together with the identifier of what
+/// produced each error. Unlike [`Session::dependency_check_errors`](crate::Session::dependency_check_errors), this
+/// retains which dependency triggered each error:
+///
+/// - In top-down builds, errors are attributed to the resource that failed to be checked.
+/// - In bottom-up builds, errors are attributed to the task whose read dependency failed to be checked.
+///
+/// Errors are accumulated over all builds until [cleared](Self::clear).
+#[derive(Clone, Default, Debug)]
+pub struct CheckErrorTracker {
+  errors: Vec<(Box<dyn KeyObj>, String)>,
+}
+
+impl CheckErrorTracker {
+  /// Gets the captured errors as `(identifier, error)` pairs, where `error` is the display formatted error, in the
+  /// order they were produced.
+  #[inline]
+  pub fn errors(&self) -> &[(Box<dyn KeyObj>, String)] { &self.errors }
+  /// Converts this tracker into its captured errors.
+  #[inline]
+  pub fn into_errors(self) -> Vec<(Box<dyn KeyObj>, String)> { self.errors }
+  /// Clears all captured errors.
+  #[inline]
+  pub fn clear(&mut self) { self.errors.clear() }
+
+  #[inline]
+  fn push_error(&mut self, key: &dyn KeyObj, inconsistency: Result<Option<&Change>, &dyn Error>) {
+    if let Err(error) = inconsistency {
+      self.errors.push((key.to_owned(), error.to_string()));
+    }
+  }
+}
+
+impl Tracker for CheckErrorTracker {
+  #[inline]
+  fn check_resource_end(
+    &mut self,
+    resource: &dyn KeyObj,
+    _checker: &dyn ValueObj,
+    _stamp: &dyn ValueObj,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
+  ) {
+    self.push_error(resource, inconsistency);
+  }
+
+  #[inline]
+  fn check_task_read_resource_end(
+    &mut self,
+    reading_task: &dyn TaskKeyObj,
+    _checker: &dyn ValueObj,
+    _stamp: &dyn ValueObj,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
+  ) {
+    self.push_error(reading_task, inconsistency);
+  }
+}
@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::fmt::Debug;
+use std::io::Write;
+use std::time::Instant;
+
+use crate::Change;
+use crate::tracker::Tracker;
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
+
+/// A [`Tracker`] that records task executions as begin/end events in the Chrome Trace Event Format, for
+/// flame-graph-style visualization of build times in tools such as `chrome://tracing` or Perfetto.
+///
+/// Events are buffered during a build, and written to the [`Write`] instance as a JSON array at the end of the build.
+/// Every build writes a separate JSON array, so use a separate writer per build to get valid JSON files.
+#[derive(Clone, Debug)]
+pub struct ChromeTraceTracker<W> {
+  writer: W,
+  track_checks: bool,
+  build_start: Option<Instant>,
+  events: Vec<TraceEvent>,
+}
+
+impl<W: Write> ChromeTraceTracker<W> {
+  /// Creates a new [`ChromeTraceTracker`] that writes to `writer`, only recording task executions.
+  #[inline]
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      track_checks: false,
+      build_start: None,
+      events: Vec::new(),
+    }
+  }
+
+  /// Sets whether task and resource consistency checks are recorded as well, in addition to task executions.
+  #[inline]
+  pub fn with_check_events(mut self, track_checks: bool) -> Self {
+    self.track_checks = track_checks;
+    self
+  }
+
+  /// Gets the writer of this tracker.
+  #[inline]
+  pub fn writer(&self) -> &W { &self.writer }
+  /// Gets the mutable writer of this tracker.
+  #[inline]
+  pub fn writer_mut(&mut self) -> &mut W { &mut self.writer }
+  /// Converts this tracker into its writer.
+  #[inline]
+  pub fn into_writer(self) -> W { self.writer }
+}
+
+impl<W: Write> ChromeTraceTracker<W> {
+  #[inline]
+  fn begin(&mut self, name: String, category: &'static str) {
+    self.push(name, category, 'B');
+  }
+  #[inline]
+  fn end(&mut self, name: String, category: &'static str) {
+    self.push(name, category, 'E');
+  }
+  #[inline]
+  fn push(&mut self, name: String, category: &'static str, phase: char) {
+    let timestamp = self.build_start.get_or_insert_with(Instant::now).elapsed().as_micros();
+    self.events.push(TraceEvent { name, category, phase, timestamp });
+  }
+
+  fn write_events(&mut self) {
+    let _ = write!(&mut self.writer, "[");
+    for (i, event) in self.events.iter().enumerate() {
+      if i != 0 {
+        let _ = write!(&mut self.writer, ",");
+      }
+      let _ = write!(&mut self.writer, "\n{{\"name\":");
+      write_json_string(&mut self.writer, &event.name);
+      let _ = write!(&mut self.writer, ",\"cat\":\"{}\",\"ph\":\"{}\",\"ts\":{},\"pid\":1,\"tid\":1}}",
+        event.category, event.phase, event.timestamp);
+    }
+    let _ = writeln!(&mut self.writer, "\n]");
+    let _ = self.writer.flush();
+  }
+}
+
+impl<W: Write + 'static> Tracker for ChromeTraceTracker<W> {
+  #[inline]
+  fn build_start(&mut self) {
+    self.build_start = Some(Instant::now());
+    self.events.clear();
+  }
+  #[inline]
+  fn build_end(&mut self) {
+    self.write_events();
+    self.events.clear();
+    self.build_start = None;
+  }
+
+  #[inline]
+  fn check_task_start(&mut self, task: &dyn TaskKeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
+    if self.track_checks {
+      self.begin(task.describe_obj().into_owned(), "check")
+    }
+  }
+  #[inline]
+  fn check_task_end(
+    &mut self,
+    task: &dyn TaskKeyObj,
+    _checker: &dyn ValueObj,
+    _stamp: &dyn ValueObj,
+    _inconsistency: Option<&dyn Debug>,
+  ) {
+    if self.track_checks {
+      self.end(task.describe_obj().into_owned(), "check")
+    }
+  }
+  #[inline]
+  fn check_resource_start(&mut self, resource: &dyn KeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
+    if self.track_checks {
+      self.begin(format!("{:?}", resource), "check")
+    }
+  }
+  #[inline]
+  fn check_resource_end(
+    &mut self,
+    resource: &dyn KeyObj,
+    _checker: &dyn ValueObj,
+    _stamp: &dyn ValueObj,
+    _inconsistency: Result<Option<&Change>, &dyn Error>,
+  ) {
+    if self.track_checks {
+      self.end(format!("{:?}", resource), "check")
+    }
+  }
+
+  #[inline]
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {
+    self.begin(task.describe_obj().into_owned(), "execute");
+  }
+  #[inline]
+  fn execute_end(&mut self, task: &dyn TaskKeyObj, _output: &dyn ValueObj) {
+    self.end(task.describe_obj().into_owned(), "execute");
+  }
+
+  #[inline]
+  fn scope_start(&mut self, label: &'static str) {
+    self.push(label.to_string(), "scope", 'B');
+  }
+  #[inline]
+  fn scope_end(&mut self, label: &'static str) {
+    self.push(label.to_string(), "scope", 'E');
+  }
+}
+
+/// Begin or end event in the Chrome Trace Event Format.
+#[derive(Clone, Debug)]
+struct TraceEvent {
+  name: String,
+  category: &'static str,
+  phase: char,
+  /// Microseconds since the start of the build.
+  timestamp: u128,
+}
+
+/// Writes `string` to `writer` as a JSON string literal, escaping it as needed.
+fn write_json_string(writer: &mut impl Write, string: &str) {
+  let _ = write!(writer, "\"");
+  for c in string.chars() {
+    let _ = match c {
+      '"' => write!(writer, "\\\""),
+      '\\' => write!(writer, "\\\\"),
+      '\n' => write!(writer, "\\n"),
+      '\r' => write!(writer, "\\r"),
+      '\t' => write!(writer, "\\t"),
+      c if c.is_control() => write!(writer, "\\u{:04x}", c as u32),
+      c => write!(writer, "{}", c),
+    };
+  }
+  let _ = write!(writer, "\"");
+}
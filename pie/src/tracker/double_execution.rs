@@ -0,0 +1,50 @@
+use std::collections::HashSet;
+
+use crate::tracker::Tracker;
+use crate::trait_object::{KeyObj, TaskKeyObj};
+
+/// A [`Tracker`] that guards against tasks being executed more than once within the same build, which indicates a bug
+/// such as outputs being reset in the middle of a build. Calls the callback with the task each time a task is executed
+/// again within the same build.
+///
+/// Executed tasks are tracked per build: they are cleared at the start of each build. Tasks in a cycle that is iterated
+/// to a fixpoint by [`Context::require_fixpoint`](crate::Context::require_fixpoint) are intentionally executed more
+/// than once in the same build, and are therefore also reported.
+#[derive(Clone, Debug)]
+pub struct DoubleExecutionGuardTracker<F> {
+  executed: HashSet<Box<dyn KeyObj>>,
+  on_double_execution: F,
+}
+
+impl<F: FnMut(&dyn TaskKeyObj)> DoubleExecutionGuardTracker<F> {
+  /// Creates a new [`DoubleExecutionGuardTracker`] that calls `on_double_execution` with the task each time a task is
+  /// executed again within the same build.
+  #[inline]
+  pub fn new(on_double_execution: F) -> Self {
+    Self { executed: HashSet::default(), on_double_execution }
+  }
+}
+impl DoubleExecutionGuardTracker<fn(&dyn TaskKeyObj)> {
+  /// Creates a new [`DoubleExecutionGuardTracker`] that writes a message to standard error each time a task is executed
+  /// again within the same build.
+  #[inline]
+  pub fn with_stderr() -> Self {
+    Self::new(|task| eprintln!("task {} was executed more than once in the same build", task.describe_obj()))
+  }
+}
+
+impl<F: FnMut(&dyn TaskKeyObj)> Tracker for DoubleExecutionGuardTracker<F> {
+  #[inline]
+  fn build_start(&mut self) {
+    self.executed.clear();
+  }
+
+  #[inline]
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {
+    if self.executed.contains(task.as_key_obj()) {
+      (self.on_double_execution)(task);
+    } else {
+      self.executed.insert(task.as_key_obj().to_owned());
+    }
+  }
+}
@@ -3,7 +3,7 @@ use std::ops::RangeInclusive;
 
 use crate::Task;
 use crate::tracker::Tracker;
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
 
 /// A [`Tracker`] that stores [`Event`]s in a [`Vec`], useful in testing to assert that a context implementation is
 /// incremental and correct.
@@ -35,6 +35,9 @@ pub enum Event {
 
   ExecuteStart(ExecuteStart),
   ExecuteEnd(ExecuteEnd),
+
+  ScopeStart(Scope),
+  ScopeEnd(Scope),
 }
 
 /// Trait for access to tasks in specific kinds of [`Event`]s.
@@ -110,6 +113,12 @@ impl TaskAccess for ExecuteEnd {
   #[inline]
   fn task(&self) -> &dyn KeyObj { self.task.as_ref() }
 }
+/// Start/end: scope with `label`.
+#[derive(Clone, Debug)]
+pub struct Scope {
+  pub label: &'static str,
+  pub index: usize,
+}
 
 impl Tracker for EventTracker {
   #[inline]
@@ -125,9 +134,9 @@ impl Tracker for EventTracker {
   }
 
   #[inline]
-  fn require_start(&mut self, task: &dyn KeyObj, checker: &dyn ValueObj) {
+  fn require_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj) {
     let data = RequireStart {
-      task: task.to_owned(),
+      task: task.as_key_obj().to_owned(),
       checker: checker.to_owned(),
       index: self.events.len(),
     };
@@ -136,13 +145,13 @@ impl Tracker for EventTracker {
   #[inline]
   fn require_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     output: &dyn ValueObj,
   ) {
     let data = RequireEnd {
-      task: task.to_owned(),
+      task: task.as_key_obj().to_owned(),
       checker: checker.to_owned(),
       stamp: stamp.to_owned(),
       output: output.to_owned(),
@@ -192,22 +201,33 @@ impl Tracker for EventTracker {
   }
 
   #[inline]
-  fn execute_start(&mut self, task: &dyn KeyObj) {
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {
     let data = ExecuteStart {
-      task: task.to_owned(),
+      task: task.as_key_obj().to_owned(),
       index: self.events.len(),
     };
     self.events.push(Event::ExecuteStart(data));
   }
   #[inline]
-  fn execute_end(&mut self, task: &dyn KeyObj, output: &dyn ValueObj) {
+  fn execute_end(&mut self, task: &dyn TaskKeyObj, output: &dyn ValueObj) {
     let data = ExecuteEnd {
-      task: task.to_owned(),
+      task: task.as_key_obj().to_owned(),
       output: output.to_owned(),
       index: self.events.len(),
     };
     self.events.push(Event::ExecuteEnd(data));
   }
+
+  #[inline]
+  fn scope_start(&mut self, label: &'static str) {
+    let data = Scope { label, index: self.events.len() };
+    self.events.push(Event::ScopeStart(data));
+  }
+  #[inline]
+  fn scope_end(&mut self, label: &'static str) {
+    let data = Scope { label, index: self.events.len() };
+    self.events.push(Event::ScopeEnd(data));
+  }
 }
 
 impl Event {
@@ -300,6 +320,21 @@ impl Event {
       _ => None,
     }
   }
+
+  /// Returns `Some(&data)` if this is a [scope start event](Event::ScopeStart) with `label`, or `None` otherwise.
+  pub fn match_scope_start(&self, label: &str) -> Option<&Scope> {
+    match self {
+      Event::ScopeStart(data) if data.label == label => Some(data),
+      _ => None,
+    }
+  }
+  /// Returns `Some(&data)` if this is a [scope end event](Event::ScopeEnd) with `label`, or `None` otherwise.
+  pub fn match_scope_end(&self, label: &str) -> Option<&Scope> {
+    match self {
+      Event::ScopeEnd(data) if data.label == label => Some(data),
+      _ => None,
+    }
+  }
 }
 
 impl EventTracker {
@@ -417,4 +452,29 @@ impl EventTracker {
   pub fn first_execute_end_index(&self, task: &dyn KeyObj) -> Option<&usize> {
     self.first_execute_end(task).map(|d| &d.index)
   }
+
+  /// Finds the first scope [start](Event::ScopeStart) and [end](Event::ScopeEnd) event with `label` and returns
+  /// `Some(start_data.index..=end_data.index)`, or `None` otherwise.
+  pub fn first_scope_range(&self, label: &str) -> Option<RangeInclusive<usize>> {
+    let start_data = self.find_map(|e| e.match_scope_start(label));
+    let end_data = self.find_map(|e| e.match_scope_end(label));
+    start_data.zip(end_data).map(|(s, e)| s.index..=e.index)
+  }
+
+  /// Returns `true` if all `tasks` were executed, and the first [execute start event](Event::ExecuteStart) of each
+  /// task comes strictly after that of the previous task in `tasks`. Returns `false` otherwise, including when any task
+  /// in `tasks` was not executed.
+  pub fn executed_in_order(&self, tasks: &[&dyn KeyObj]) -> bool {
+    let mut previous_index = None;
+    for task in tasks {
+      let Some(index) = self.find_map(|e| e.match_execute_start(*task)).map(|d| d.index) else {
+        return false;
+      };
+      if previous_index.is_some_and(|previous_index| index <= previous_index) {
+        return false;
+      }
+      previous_index = Some(index);
+    }
+    true
+  }
 }
@@ -1,13 +1,25 @@
 use std::error::Error;
 use std::fmt::Debug;
 
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::Change;
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
 
 pub mod writing;
 pub mod event;
+pub mod chrome_trace;
+pub mod check_error;
+pub mod double_execution;
+pub mod sampling;
 
 /// Build event tracker. Can be used to implement logging, event tracing, progress tracking, metrics, etc.
 ///
+/// Tasks are passed as [`TaskKeyObj`] instead of [`KeyObj`], so that trackers can label them with their
+/// [description](crate::Task::describe) through [`TaskKeyObj::describe_obj`]. This is a breaking change for tracker
+/// implementations, which must change the type of their task parameters. We cannot provide descriptions through
+/// [`KeyObj`] instead, because it is implemented for all keys, including resources, and its methods cannot be
+/// overridden for tasks without specialization. Implementations that store or compare tasks can get a [`KeyObj`] with
+/// [`TaskKeyObj::as_key_obj`].
+///
 /// Object-safe trait.
 #[allow(unused_variables)]
 pub trait Tracker {
@@ -20,12 +32,12 @@ pub trait Tracker {
 
   /// Start: require `task` using `checker`.
   #[inline]
-  fn require_start(&mut self, task: &dyn KeyObj, checker: &dyn ValueObj) {}
+  fn require_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj) {}
   /// End: required `task`, using `checker` to create `stamp`, resulting in `output`.
   #[inline]
   fn require_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     output: &dyn ValueObj,
@@ -46,12 +58,12 @@ pub trait Tracker {
 
   /// Start: check consistency of `task` which used `checker` to create `stamp`.
   #[inline]
-  fn check_task_start(&mut self, task: &dyn KeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {}
+  fn check_task_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {}
   /// End: checked consistency of `task` which used `checker` to create `stamp`, possibly found an `inconsistency`
   #[inline]
   fn check_task_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     inconsistency: Option<&dyn Debug>,
@@ -60,32 +72,52 @@ pub trait Tracker {
   /// Start: check consistency of `resource` which used `checker` to create `stamp`.
   #[inline]
   fn check_resource_start(&mut self, resource: &dyn KeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {}
-  /// End: checked consistency of `resource` which used `checker` to create `stamp`, possibly found an `inconsistency`.
+  /// End: checked consistency of `resource` which used `checker` to create `stamp`, possibly found an `inconsistency`
+  /// described as a [`Change`] from `stamp`.
   #[inline]
   fn check_resource_end(
     &mut self,
     resource: &dyn KeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
-    inconsistency: Result<Option<&dyn Debug>, &dyn Error>,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
   ) {}
 
+  /// Cache hit: `task` was checked and found to be consistent, so its cached output is reused without executing it.
+  #[inline]
+  fn require_cache_hit(&mut self, task: &dyn TaskKeyObj) {}
+  /// Cache miss: `task` was checked and found to be new or inconsistent, so it will be executed.
+  #[inline]
+  fn require_cache_miss(&mut self, task: &dyn TaskKeyObj) {}
+
   /// Start: execute `task`.
   #[inline]
-  fn execute_start(&mut self, task: &dyn KeyObj) {}
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {}
   /// End: executed `task` resulting in `output`.
   #[inline]
-  fn execute_end(&mut self, task: &dyn KeyObj, output: &dyn ValueObj) {}
+  fn execute_end(&mut self, task: &dyn TaskKeyObj, output: &dyn ValueObj) {}
+  /// Panicked: making required `task` consistent panicked with `message`, and the panic was caught by
+  /// `Context::require_with_fallback`. Start events interrupted by the panic do not get corresponding end events.
+  #[inline]
+  fn execute_panicked(&mut self, task: &dyn TaskKeyObj, message: &str) {}
+
+  /// Start: scope with `label`, grouping the dependencies created inside it. Scopes are purely informative, and do not
+  /// affect incrementality.
+  #[inline]
+  fn scope_start(&mut self, label: &'static str) {}
+  /// End: scope with `label`.
+  #[inline]
+  fn scope_end(&mut self, label: &'static str) {}
 
 
   // Bottom-up build tracking.
 
   /// Start: schedule tasks affected by changes to the output of `task`.
-  fn schedule_affected_by_task_start(&mut self, task: &dyn KeyObj) {}
+  fn schedule_affected_by_task_start(&mut self, task: &dyn TaskKeyObj) {}
   /// Start: check consistency of `requiring_task`'s require dependency which used `checker` to create `stamp`.
   fn check_task_require_task_start(
     &mut self,
-    requiring_task: &dyn KeyObj,
+    requiring_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
   ) {}
@@ -93,37 +125,37 @@ pub trait Tracker {
   /// possibly found an `inconsistency`.
   fn check_task_require_task_end(
     &mut self,
-    requiring_task: &dyn KeyObj,
+    requiring_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     inconsistency: Option<&dyn Debug>,
   ) {}
   /// End: scheduled tasks affected by changes to the output of `task`.
-  fn schedule_affected_by_task_end(&mut self, task: &dyn KeyObj) {}
+  fn schedule_affected_by_task_end(&mut self, task: &dyn TaskKeyObj) {}
 
   /// Start: schedule tasks affected by changes to `resource`.
   fn schedule_affected_by_resource_start(&mut self, resource: &dyn KeyObj) {}
   /// Start: check consistency of `reading_task`'s read dependency which used `checker` to create `stamp`.
   fn check_task_read_resource_start(
     &mut self,
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
   ) {}
   /// End: checked consistency of `reading_task`'s read dependency which used `checker` to create `stamp`, possibly
-  /// found an `inconsistency`.
+  /// found an `inconsistency` described as a [`Change`] from `stamp`.
   fn check_task_read_resource_end(
     &mut self,
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
-    inconsistency: Result<Option<&dyn Debug>, &dyn Error>,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
   ) {}
   /// End: scheduled tasks affected by changes to `resource`.
   fn schedule_affected_by_resource_end(&mut self, resource: &dyn KeyObj) {}
 
   /// Schedule `task` for execution.
-  fn schedule_task(&mut self, task: &dyn KeyObj) {}
+  fn schedule_task(&mut self, task: &dyn TaskKeyObj) {}
 }
 
 /// Implement [`Tracker`] for `()` that does nothing.
@@ -148,14 +180,14 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
   }
 
   #[inline]
-  fn require_start(&mut self, task: &dyn KeyObj, checker: &dyn ValueObj) {
+  fn require_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj) {
     self.0.require_start(task, checker);
     self.1.require_start(task, checker);
   }
   #[inline]
   fn require_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     output: &dyn ValueObj,
@@ -185,14 +217,14 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
   }
 
   #[inline]
-  fn check_task_start(&mut self, task: &dyn KeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {
+  fn check_task_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {
     self.0.check_task_start(task, checker, stamp);
     self.1.check_task_start(task, checker, stamp);
   }
   #[inline]
   fn check_task_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     inconsistency: Option<&dyn Debug>,
@@ -212,22 +244,49 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
     resource: &dyn KeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
-    inconsistency: Result<Option<&dyn Debug>, &dyn Error>,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
   ) {
     self.0.check_resource_end(resource, checker, stamp, inconsistency);
     self.1.check_resource_end(resource, checker, stamp, inconsistency);
   }
 
   #[inline]
-  fn execute_start(&mut self, task: &dyn KeyObj) {
+  fn require_cache_hit(&mut self, task: &dyn TaskKeyObj) {
+    self.0.require_cache_hit(task);
+    self.1.require_cache_hit(task);
+  }
+  #[inline]
+  fn require_cache_miss(&mut self, task: &dyn TaskKeyObj) {
+    self.0.require_cache_miss(task);
+    self.1.require_cache_miss(task);
+  }
+
+  #[inline]
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {
     self.0.execute_start(task);
     self.1.execute_start(task);
   }
   #[inline]
-  fn execute_end(&mut self, task: &dyn KeyObj, output: &dyn ValueObj) {
+  fn execute_end(&mut self, task: &dyn TaskKeyObj, output: &dyn ValueObj) {
     self.0.execute_end(task, output);
     self.1.execute_end(task, output);
   }
+  #[inline]
+  fn execute_panicked(&mut self, task: &dyn TaskKeyObj, message: &str) {
+    self.0.execute_panicked(task, message);
+    self.1.execute_panicked(task, message);
+  }
+
+  #[inline]
+  fn scope_start(&mut self, label: &'static str) {
+    self.0.scope_start(label);
+    self.1.scope_start(label);
+  }
+  #[inline]
+  fn scope_end(&mut self, label: &'static str) {
+    self.0.scope_end(label);
+    self.1.scope_end(label);
+  }
 
 
   // Bottom-up build tracking.
@@ -240,7 +299,7 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
   #[inline]
   fn check_task_require_task_start(
     &mut self,
-    requiring_task: &dyn KeyObj,
+    requiring_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
   ) {
@@ -250,7 +309,7 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
   #[inline]
   fn check_task_require_task_end(
     &mut self,
-    requiring_task: &dyn KeyObj,
+    requiring_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     inconsistency: Option<&dyn Debug>,
@@ -265,14 +324,14 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
   }
 
   #[inline]
-  fn schedule_affected_by_task_start(&mut self, task: &dyn KeyObj) {
+  fn schedule_affected_by_task_start(&mut self, task: &dyn TaskKeyObj) {
     self.0.schedule_affected_by_task_start(task);
     self.1.schedule_affected_by_task_start(task);
   }
   #[inline]
   fn check_task_read_resource_start(
     &mut self,
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
   ) {
@@ -282,22 +341,22 @@ impl<A1: Tracker, A2: Tracker> Tracker for CompositeTracker<A1, A2> {
   #[inline]
   fn check_task_read_resource_end(
     &mut self,
-    reading_task: &dyn KeyObj,
+    reading_task: &dyn TaskKeyObj,
     checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
-    inconsistency: Result<Option<&dyn Debug>, &dyn Error>,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
   ) {
     self.0.check_task_read_resource_end(reading_task, checker, stamp, inconsistency);
     self.1.check_task_read_resource_end(reading_task, checker, stamp, inconsistency);
   }
   #[inline]
-  fn schedule_affected_by_task_end(&mut self, task: &dyn KeyObj) {
+  fn schedule_affected_by_task_end(&mut self, task: &dyn TaskKeyObj) {
     self.0.schedule_affected_by_task_end(task);
     self.1.schedule_affected_by_task_end(task);
   }
 
   #[inline]
-  fn schedule_task(&mut self, task: &dyn KeyObj) {
+  fn schedule_task(&mut self, task: &dyn TaskKeyObj) {
     self.0.schedule_task(task);
     self.1.schedule_task(task);
   }
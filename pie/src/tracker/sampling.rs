@@ -0,0 +1,385 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt::Debug;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::Change;
+use crate::tracker::Tracker;
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
+
+/// A [`Tracker`] that forwards only every `rate`th event of each kind to inner tracker `A`, reducing the volume of
+/// events for statistical use, such as estimating where time is spent in builds with millions of events. The first
+/// event of each kind is forwarded, then every `rate`th event after that.
+///
+/// [Build start](Tracker::build_start), [build end](Tracker::build_end), and
+/// [execute panicked](Tracker::execute_panicked) events are always forwarded. Start and end
+/// events are sampled together: an end event is forwarded if and only if its corresponding start event was forwarded.
+/// Start events without a corresponding end event, of tasks interrupted by a panic or unwind, are discarded when the
+/// panic is [caught](Tracker::execute_panicked), or at the start of the next build, so that they are not paired with
+/// later end events. To find the start events interrupted by a caught panic, the hash of every required task is kept
+/// until its require ends.
+///
+/// Sampling state is retained across builds.
+#[derive(Clone, Debug)]
+pub struct SamplingTracker<A> {
+  inner: A,
+  rate: u32,
+  require: PairSampler,
+  read: PairSampler,
+  write: PairSampler,
+  check_task: PairSampler,
+  check_resource: PairSampler,
+  require_cache_hit: Sampler,
+  require_cache_miss: Sampler,
+  execute: PairSampler,
+  scope: PairSampler,
+  schedule_affected_by_task: PairSampler,
+  check_task_require_task: PairSampler,
+  schedule_affected_by_resource: PairSampler,
+  check_task_read_resource: PairSampler,
+  schedule_task: Sampler,
+  /// Hash of the task and lengths of the stacks of all pair samplers, right after each require start whose end has not
+  /// occurred yet.
+  requires: Vec<(u64, [usize; PAIR_SAMPLER_COUNT])>,
+}
+/// Number of [pair samplers](PairSampler) in a [`SamplingTracker`].
+const PAIR_SAMPLER_COUNT: usize = 11;
+
+impl<A> SamplingTracker<A> {
+  /// Creates a new [`SamplingTracker`] that forwards every `rate`th event of each kind to `inner`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `rate` is 0.
+  #[inline]
+  pub fn new(inner: A, rate: u32) -> Self {
+    assert!(rate > 0, "sampling rate must be greater than 0");
+    Self {
+      inner,
+      rate,
+      require: PairSampler::default(),
+      read: PairSampler::default(),
+      write: PairSampler::default(),
+      check_task: PairSampler::default(),
+      check_resource: PairSampler::default(),
+      require_cache_hit: Sampler::default(),
+      require_cache_miss: Sampler::default(),
+      execute: PairSampler::default(),
+      scope: PairSampler::default(),
+      schedule_affected_by_task: PairSampler::default(),
+      check_task_require_task: PairSampler::default(),
+      schedule_affected_by_resource: PairSampler::default(),
+      check_task_read_resource: PairSampler::default(),
+      schedule_task: Sampler::default(),
+      requires: Vec::default(),
+    }
+  }
+
+  /// Gets the sampling rate.
+  #[inline]
+  pub fn rate(&self) -> u32 { self.rate }
+  /// Gets the inner tracker.
+  #[inline]
+  pub fn inner(&self) -> &A { &self.inner }
+  /// Gets the mutable inner tracker.
+  #[inline]
+  pub fn inner_mut(&mut self) -> &mut A { &mut self.inner }
+  /// Converts this tracker into its inner tracker.
+  #[inline]
+  pub fn into_inner(self) -> A { self.inner }
+
+  /// Gets all pair samplers.
+  #[inline]
+  fn pair_samplers(&mut self) -> [&mut PairSampler; PAIR_SAMPLER_COUNT] {
+    [
+      &mut self.require,
+      &mut self.read,
+      &mut self.write,
+      &mut self.check_task,
+      &mut self.check_resource,
+      &mut self.execute,
+      &mut self.scope,
+      &mut self.schedule_affected_by_task,
+      &mut self.check_task_require_task,
+      &mut self.schedule_affected_by_resource,
+      &mut self.check_task_read_resource,
+    ]
+  }
+}
+
+/// Returns the hash of `task` and its type, as tasks of different types may have equal hashes.
+#[inline]
+fn hash(task: &dyn TaskKeyObj) -> u64 {
+  let task = task.as_key_obj();
+  let mut hasher = DefaultHasher::new();
+  Any::type_id(task.as_any()).hash(&mut hasher);
+  task.hash(&mut hasher);
+  hasher.finish()
+}
+
+/// Samples every `rate`th event of a kind.
+#[derive(Default, Copy, Clone, Debug)]
+struct Sampler {
+  count: u32,
+}
+impl Sampler {
+  /// Returns whether the next event should be forwarded.
+  #[inline]
+  fn sample(&mut self, rate: u32) -> bool {
+    let forward = self.count == 0;
+    self.count = (self.count + 1) % rate;
+    forward
+  }
+}
+
+/// Samples every `rate`th start event of a kind, forwarding end events if their start event was forwarded. Start and
+/// end events can be nested, such as requires inside requires, so decisions are kept on a stack.
+#[derive(Default, Clone, Debug)]
+struct PairSampler {
+  sampler: Sampler,
+  forwarded: Vec<bool>,
+}
+impl PairSampler {
+  /// Returns whether the next start event should be forwarded.
+  #[inline]
+  fn start(&mut self, rate: u32) -> bool {
+    let forward = self.sampler.sample(rate);
+    self.forwarded.push(forward);
+    forward
+  }
+  /// Returns whether the next end event should be forwarded.
+  #[inline]
+  fn end(&mut self) -> bool {
+    self.forwarded.pop().unwrap_or(false)
+  }
+}
+
+impl<A: Tracker> Tracker for SamplingTracker<A> {
+  #[inline]
+  fn build_start(&mut self) {
+    for sampler in self.pair_samplers() {
+      sampler.forwarded.clear();
+    }
+    self.requires.clear();
+    self.inner.build_start();
+  }
+  #[inline]
+  fn build_end(&mut self) {
+    self.inner.build_end();
+  }
+
+  #[inline]
+  fn require_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj) {
+    let forward = self.require.start(self.rate);
+    let lengths = self.pair_samplers().map(|sampler| sampler.forwarded.len());
+    self.requires.push((hash(task), lengths));
+    if forward {
+      self.inner.require_start(task, checker);
+    }
+  }
+  #[inline]
+  fn require_end(
+    &mut self,
+    task: &dyn TaskKeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+    output: &dyn ValueObj,
+  ) {
+    self.requires.pop();
+    if self.require.end() {
+      self.inner.require_end(task, checker, stamp, output);
+    }
+  }
+  #[inline]
+  fn read_start(&mut self, resource: &dyn KeyObj, checker: &dyn ValueObj) {
+    if self.read.start(self.rate) {
+      self.inner.read_start(resource, checker);
+    }
+  }
+  #[inline]
+  fn read_end(&mut self, resource: &dyn KeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {
+    if self.read.end() {
+      self.inner.read_end(resource, checker, stamp);
+    }
+  }
+  #[inline]
+  fn write_start(&mut self, resource: &dyn KeyObj, checker: &dyn ValueObj) {
+    if self.write.start(self.rate) {
+      self.inner.write_start(resource, checker);
+    }
+  }
+  #[inline]
+  fn write_end(&mut self, resource: &dyn KeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {
+    if self.write.end() {
+      self.inner.write_end(resource, checker, stamp);
+    }
+  }
+
+  #[inline]
+  fn check_task_start(&mut self, task: &dyn TaskKeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {
+    if self.check_task.start(self.rate) {
+      self.inner.check_task_start(task, checker, stamp);
+    }
+  }
+  #[inline]
+  fn check_task_end(
+    &mut self,
+    task: &dyn TaskKeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+    inconsistency: Option<&dyn Debug>,
+  ) {
+    if self.check_task.end() {
+      self.inner.check_task_end(task, checker, stamp, inconsistency);
+    }
+  }
+  #[inline]
+  fn check_resource_start(&mut self, resource: &dyn KeyObj, checker: &dyn ValueObj, stamp: &dyn ValueObj) {
+    if self.check_resource.start(self.rate) {
+      self.inner.check_resource_start(resource, checker, stamp);
+    }
+  }
+  #[inline]
+  fn check_resource_end(
+    &mut self,
+    resource: &dyn KeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
+  ) {
+    if self.check_resource.end() {
+      self.inner.check_resource_end(resource, checker, stamp, inconsistency);
+    }
+  }
+
+  #[inline]
+  fn require_cache_hit(&mut self, task: &dyn TaskKeyObj) {
+    if self.require_cache_hit.sample(self.rate) {
+      self.inner.require_cache_hit(task);
+    }
+  }
+  #[inline]
+  fn require_cache_miss(&mut self, task: &dyn TaskKeyObj) {
+    if self.require_cache_miss.sample(self.rate) {
+      self.inner.require_cache_miss(task);
+    }
+  }
+
+  #[inline]
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {
+    if self.execute.start(self.rate) {
+      self.inner.execute_start(task);
+    }
+  }
+  #[inline]
+  fn execute_end(&mut self, task: &dyn TaskKeyObj, output: &dyn ValueObj) {
+    if self.execute.end() {
+      self.inner.execute_end(task, output);
+    }
+  }
+  #[inline]
+  fn execute_panicked(&mut self, task: &dyn TaskKeyObj, message: &str) {
+    // Discard start events interrupted by the panic: those after the require start of `task`, whose end comes next.
+    let hash = hash(task);
+    if let Some(index) = self.requires.iter().rposition(|(h, _)| *h == hash) {
+      self.requires.truncate(index + 1);
+      let (_, lengths) = self.requires[index];
+      for (sampler, length) in self.pair_samplers().into_iter().zip(lengths) {
+        sampler.forwarded.truncate(length);
+      }
+    }
+    self.inner.execute_panicked(task, message);
+  }
+
+  #[inline]
+  fn scope_start(&mut self, label: &'static str) {
+    if self.scope.start(self.rate) {
+      self.inner.scope_start(label);
+    }
+  }
+  #[inline]
+  fn scope_end(&mut self, label: &'static str) {
+    if self.scope.end() {
+      self.inner.scope_end(label);
+    }
+  }
+
+  #[inline]
+  fn schedule_affected_by_task_start(&mut self, task: &dyn TaskKeyObj) {
+    if self.schedule_affected_by_task.start(self.rate) {
+      self.inner.schedule_affected_by_task_start(task);
+    }
+  }
+  #[inline]
+  fn check_task_require_task_start(
+    &mut self,
+    requiring_task: &dyn TaskKeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+  ) {
+    if self.check_task_require_task.start(self.rate) {
+      self.inner.check_task_require_task_start(requiring_task, checker, stamp);
+    }
+  }
+  #[inline]
+  fn check_task_require_task_end(
+    &mut self,
+    requiring_task: &dyn TaskKeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+    inconsistency: Option<&dyn Debug>,
+  ) {
+    if self.check_task_require_task.end() {
+      self.inner.check_task_require_task_end(requiring_task, checker, stamp, inconsistency);
+    }
+  }
+  #[inline]
+  fn schedule_affected_by_task_end(&mut self, task: &dyn TaskKeyObj) {
+    if self.schedule_affected_by_task.end() {
+      self.inner.schedule_affected_by_task_end(task);
+    }
+  }
+
+  #[inline]
+  fn schedule_affected_by_resource_start(&mut self, resource: &dyn KeyObj) {
+    if self.schedule_affected_by_resource.start(self.rate) {
+      self.inner.schedule_affected_by_resource_start(resource);
+    }
+  }
+  #[inline]
+  fn check_task_read_resource_start(
+    &mut self,
+    reading_task: &dyn TaskKeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+  ) {
+    if self.check_task_read_resource.start(self.rate) {
+      self.inner.check_task_read_resource_start(reading_task, checker, stamp);
+    }
+  }
+  #[inline]
+  fn check_task_read_resource_end(
+    &mut self,
+    reading_task: &dyn TaskKeyObj,
+    checker: &dyn ValueObj,
+    stamp: &dyn ValueObj,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
+  ) {
+    if self.check_task_read_resource.end() {
+      self.inner.check_task_read_resource_end(reading_task, checker, stamp, inconsistency);
+    }
+  }
+  #[inline]
+  fn schedule_affected_by_resource_end(&mut self, resource: &dyn KeyObj) {
+    if self.schedule_affected_by_resource.end() {
+      self.inner.schedule_affected_by_resource_end(resource);
+    }
+  }
+
+  #[inline]
+  fn schedule_task(&mut self, task: &dyn TaskKeyObj) {
+    if self.schedule_task.sample(self.rate) {
+      self.inner.schedule_task(task);
+    }
+  }
+}
@@ -1,38 +1,71 @@
 use std::error::Error;
 use std::fmt::Debug;
-use std::io::{self, BufWriter, Stderr, Stdout, Write};
+use std::io::{self, BufWriter, IsTerminal, Stderr, Stdout, Write};
 
+use crate::Change;
 use crate::tracker::Tracker;
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
 
 /// A [`Tracker`] that writes events to a [`Write`] instance, for example [`Stdout`].
+///
+/// Event prefixes can be [colored](Self::with_color) with ANSI escape codes: green for executes, red for
+/// inconsistencies, and dim for consistent checks (cache hits).
 #[derive(Clone, Debug)]
 pub struct WritingTracker<W> {
   writer: W,
   indentation: u32,
+  color: bool,
 }
 
 impl WritingTracker<BufWriter<Stdout>> {
-  /// Creates a [`WritingTracker`] that writes to buffered standard output.
+  /// Creates a [`WritingTracker`] that writes to buffered standard output, with color enabled if standard output is a
+  /// terminal.
   #[inline]
-  pub fn with_stdout() -> Self { Self::new(BufWriter::new(io::stdout())) }
+  pub fn with_stdout() -> Self {
+    let color = io::stdout().is_terminal();
+    Self::new(BufWriter::new(io::stdout())).with_color(color)
+  }
 }
 impl WritingTracker<BufWriter<Stderr>> {
-  /// Creates a [`WritingTracker`] that writes to buffered standard error.
+  /// Creates a [`WritingTracker`] that writes to buffered standard error, with color enabled if standard error is a
+  /// terminal.
   #[inline]
-  pub fn with_stderr() -> Self { Self::new(BufWriter::new(io::stderr())) }
+  pub fn with_stderr() -> Self {
+    let color = io::stderr().is_terminal();
+    Self::new(BufWriter::new(io::stderr())).with_color(color)
+  }
 }
 impl<W: Write> WritingTracker<W> {
-  /// Creates a new [`WritingTracker`] that writes to `writer`.
+  /// Creates a new [`WritingTracker`] that writes to `writer`, with color disabled.
   #[inline]
   pub fn new(writer: W) -> Self {
     Self {
       writer,
       indentation: 0,
+      color: false,
     }
   }
+
+  /// Sets whether event prefixes are colored with ANSI escape codes.
+  #[inline]
+  pub fn with_color(mut self, color: bool) -> Self {
+    self.color = color;
+    self
+  }
+
+  /// Gets the writer of this tracker.
+  #[inline]
+  pub fn writer(&self) -> &W { &self.writer }
+  /// Gets the mutable writer of this tracker.
+  #[inline]
+  pub fn writer_mut(&mut self) -> &mut W { &mut self.writer }
 }
 
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
 #[allow(dead_code)]
 impl<W: Write> WritingTracker<W> {
   #[inline]
@@ -40,6 +73,15 @@ impl<W: Write> WritingTracker<W> {
     self.write_indentation();
     let _ = writeln!(&mut self.writer, "{}", args);
   }
+  /// Writes an event line with `prefix` colored with `color` if color is enabled and `color` is `Some`.
+  #[inline]
+  fn writeln_event(&mut self, prefix: &str, color: Option<&str>, args: std::fmt::Arguments) {
+    self.write_indentation();
+    let _ = match color.filter(|_| self.color) {
+      Some(color) => writeln!(&mut self.writer, "{}{}{} {}", color, prefix, RESET, args),
+      None => writeln!(&mut self.writer, "{} {}", prefix, args),
+    };
+  }
   #[inline]
   fn write(&mut self, args: std::fmt::Arguments) {
     let _ = write!(&mut self.writer, "{}", args);
@@ -82,52 +124,53 @@ impl<W: Write + 'static> Tracker for WritingTracker<W> {
   }
 
   #[inline]
-  fn require_start(&mut self, task: &dyn KeyObj, _checker: &dyn ValueObj) {
-    self.writeln(format_args!("→ {:?}", task));
+  fn require_start(&mut self, task: &dyn TaskKeyObj, _checker: &dyn ValueObj) {
+    self.writeln_event("→", None, format_args!("{}", task.describe_obj()));
     self.indent();
     self.flush();
   }
   #[inline]
   fn require_end(
     &mut self,
-    _task: &dyn KeyObj,
+    _task: &dyn TaskKeyObj,
     _checker: &dyn ValueObj,
     _stamp: &dyn ValueObj,
     output: &dyn ValueObj,
   ) {
     self.unindent();
-    self.writeln(format_args!("← {:?}", output));
+    self.writeln_event("←", None, format_args!("{:?}", output));
     self.flush();
   }
 
   #[inline]
   fn read_end(&mut self, resource: &dyn KeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
-    self.writeln(format_args!("r {:?}", resource)); // TODO: expose and use display?
+    self.writeln_event("r", None, format_args!("{:?}", resource)); // TODO: expose and use display?
   }
   #[inline]
   fn write_end(&mut self, resource: &dyn KeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
-    self.writeln(format_args!("w {:?}", resource)); // TODO: expose and use display?
+    self.writeln_event("w", None, format_args!("{:?}", resource)); // TODO: expose and use display?
   }
 
   #[inline]
-  fn check_task_start(&mut self, task: &dyn KeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
-    self.writeln(format_args!("? {:?}", task));
+  fn check_task_start(&mut self, task: &dyn TaskKeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
+    self.writeln_event("?", None, format_args!("{}", task.describe_obj()));
     self.indent();
     self.flush();
   }
   #[inline]
   fn check_task_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     _checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     inconsistency: Option<&dyn Debug>,
   ) {
     self.unindent();
     if let Some(new_stamp) = inconsistency {
-      self.writeln(format_args!("✗ {:?} (new: {:?} ≉ old: {:?})", task, new_stamp, stamp))
+      self.writeln_event("✗", Some(RED),
+        format_args!("{} (new: {:?} ≉ old: {:?})", task.describe_obj(), new_stamp, stamp))
     } else {
-      self.writeln(format_args!("✓ {:?}", task))
+      self.writeln_event("✓", Some(DIM), format_args!("{}", task.describe_obj()))
     }
     self.flush();
   }
@@ -136,49 +179,60 @@ impl<W: Write + 'static> Tracker for WritingTracker<W> {
     &mut self,
     resource: &dyn KeyObj,
     _checker: &dyn ValueObj,
-    stamp: &dyn ValueObj,
-    inconsistency: Result<Option<&dyn Debug>, &dyn Error>,
+    _stamp: &dyn ValueObj,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
   ) {
     match inconsistency { // TODO: expose and use display?
-      Err(e) => self.writeln(format_args!("✗ {:?} (err: {:?})", resource, e)),
-      Ok(Some(new_stamp)) =>
-        self.writeln(format_args!("✗ {:?} (new: {:?} ≉ old: {:?})", resource, new_stamp, stamp)),
-      Ok(None) => self.writeln(format_args!("✓ {:?}", resource)),
+      Err(e) => self.writeln_event("✗", Some(RED), format_args!("{:?} (err: {:?})", resource, e)),
+      Ok(Some(change)) => self.writeln_event("✗", Some(RED),
+        format_args!("{:?} (new: {} ≉ old: {})", resource, change.to, change.from)),
+      Ok(None) => self.writeln_event("✓", Some(DIM), format_args!("{:?}", resource)),
     }
   }
 
   #[inline]
-  fn execute_start(&mut self, task: &dyn KeyObj) {
-    self.writeln(format_args!("▶ {:?}", task));
+  fn execute_start(&mut self, task: &dyn TaskKeyObj) {
+    self.writeln_event("▶", Some(GREEN), format_args!("{}", task.describe_obj()));
     self.indent();
     self.flush();
   }
   #[inline]
-  fn execute_end(&mut self, _task: &dyn KeyObj, output: &dyn ValueObj) {
+  fn execute_end(&mut self, _task: &dyn TaskKeyObj, output: &dyn ValueObj) {
     self.unindent();
-    self.writeln(format_args!("◀ {:?}", output));
+    self.writeln_event("◀", None, format_args!("{:?}", output));
+    self.flush();
+  }
+
+  #[inline]
+  fn scope_start(&mut self, label: &'static str) {
+    self.writeln_event("§", None, format_args!("{}", label));
+    self.indent();
     self.flush();
   }
+  #[inline]
+  fn scope_end(&mut self, _label: &'static str) {
+    self.unindent();
+  }
 
   #[inline]
   fn schedule_affected_by_resource_start(&mut self, resource: &dyn KeyObj) {
-    self.writeln(format_args!("¿ {:?}", resource)); // TODO: expose and use display?
+    self.writeln_event("¿", None, format_args!("{:?}", resource)); // TODO: expose and use display?
     self.indent();
     self.flush();
   }
   #[inline]
   fn check_task_read_resource_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     _checker: &dyn ValueObj,
-    stamp: &dyn ValueObj,
-    inconsistency: Result<Option<&dyn Debug>, &dyn Error>,
+    _stamp: &dyn ValueObj,
+    inconsistency: Result<Option<&Change>, &dyn Error>,
   ) {
     match inconsistency {
-      Err(e) => self.writeln(format_args!("✗ {:?} (err: {:?})", task, e)),
-      Ok(Some(new_stamp)) =>
-        self.writeln(format_args!("✗ {:?} (new: {:?} ≉ old: {:?})", task, new_stamp, stamp)),
-      Ok(None) => self.writeln(format_args!("✓ {:?}", task)),
+      Err(e) => self.writeln_event("✗", Some(RED), format_args!("{} (err: {:?})", task.describe_obj(), e)),
+      Ok(Some(change)) => self.writeln_event("✗", Some(RED),
+        format_args!("{} (new: {} ≉ old: {})", task.describe_obj(), change.to, change.from)),
+      Ok(None) => self.writeln_event("✓", Some(DIM), format_args!("{}", task.describe_obj())),
     }
   }
   #[inline]
@@ -187,33 +241,33 @@ impl<W: Write + 'static> Tracker for WritingTracker<W> {
   }
 
   #[inline]
-  fn schedule_affected_by_task_start(&mut self, task: &dyn KeyObj) {
-    self.writeln(format_args!("¿ {:?}", task));
+  fn schedule_affected_by_task_start(&mut self, task: &dyn TaskKeyObj) {
+    self.writeln_event("¿", None, format_args!("{}", task.describe_obj()));
     self.indent();
     self.flush();
   }
   #[inline]
   fn check_task_require_task_end(
     &mut self,
-    task: &dyn KeyObj,
+    task: &dyn TaskKeyObj,
     _checker: &dyn ValueObj,
     stamp: &dyn ValueObj,
     inconsistency: Option<&dyn Debug>,
   ) {
     match inconsistency {
-      Some(new_stamp) =>
-        self.writeln(format_args!("✗ {:?} (new: {:?} ≉ old: {:?})", task, new_stamp, stamp)),
-      None => self.writeln(format_args!("✓ {:?}", task)),
+      Some(new_stamp) => self.writeln_event("✗", Some(RED),
+        format_args!("{} (new: {:?} ≉ old: {:?})", task.describe_obj(), new_stamp, stamp)),
+      None => self.writeln_event("✓", Some(DIM), format_args!("{}", task.describe_obj())),
     }
   }
   #[inline]
-  fn schedule_affected_by_task_end(&mut self, _task: &dyn KeyObj) {
+  fn schedule_affected_by_task_end(&mut self, _task: &dyn TaskKeyObj) {
     self.unindent();
   }
 
   #[inline]
-  fn schedule_task(&mut self, task: &dyn KeyObj) {
-    self.writeln(format_args!("↑ {:?}", task));
+  fn schedule_task(&mut self, task: &dyn TaskKeyObj) {
+    self.writeln_event("↑", None, format_args!("{}", task.describe_obj()));
     self.flush();
   }
 }
@@ -1,7 +1,7 @@
 use std::any::{Any, TypeId};
 use std::collections::HashMap;
 
-use crate::{Resource, ResourceState};
+use crate::{Resource, ResourceState, StateEntry};
 
 /// Hash map from types (through [`TypeId`]) to any value (through [`Box<dyn Any>`](Any)).
 #[derive(Default)]
@@ -57,6 +57,12 @@ impl TypeToAnyMap {
     self.ensure_inserted_and_correct_type::<T, V>().downcast_mut::<V>().unwrap()
   }
 
+  /// Gets the entry of the value of type `V` for type `T`.
+  #[inline]
+  pub fn entry<T: ?Sized + Any, V: Any>(&mut self) -> StateEntry<'_, V> {
+    StateEntry::new(self.0.entry(TypeId::of::<T>()))
+  }
+
   #[inline]
   fn ensure_inserted_and_correct_type<T: ?Sized + Any, V: Any + Default>(&mut self) -> &mut dyn Any {
     let box_any = self.0.entry(TypeId::of::<T>())
@@ -93,4 +99,7 @@ impl<R: Resource> ResourceState<R> for TypeToAnyMap {
   fn get_or_set_default<S: Default + Any>(&mut self) -> &S { self.get_or_set_default::<R, S>() }
   #[inline]
   fn get_or_set_default_mut<S: Default + Any>(&mut self) -> &mut S { self.get_or_set_default_mut::<R, S>() }
+
+  #[inline]
+  fn entry<S: Any>(&mut self) -> StateEntry<'_, S> { self.entry::<R, S>() }
 }
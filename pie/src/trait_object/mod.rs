@@ -1,12 +1,18 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 
 use dyn_clone::DynClone;
 
 use base::{AsAny, EqObj, HashObj};
 
-use crate::{Key, Value};
+use crate::{Context, Key, OutputChecker, Resource, ResourceChecker, Task, Value};
+use crate::context::bottom_up::BottomUpContext;
+use crate::context::SessionExt;
+use crate::context::top_down::TopDownContext;
+use crate::pie::SessionInternal;
 
 #[macro_use]
 pub(crate) mod base;
@@ -78,6 +84,125 @@ impl<'a> From<Box<dyn KeyObj>> for Cow<'a, dyn KeyObj> {
   fn from(value: Box<dyn KeyObj>) -> Self { Cow::Owned(value) }
 }
 
+/// Object safe [`Task`] proxy that can be converted to a [`KeyObj`], and described. [Trackers](crate::tracker::Tracker)
+/// receive tasks as this proxy.
+pub trait TaskKeyObj: KeyObj {
+  /// Returns this task as a [`KeyObj`].
+  fn as_key_obj(&self) -> &dyn KeyObj;
+  /// Returns the [description](Task::describe) of this task.
+  fn describe_obj(&self) -> Cow<'_, str>;
+}
+const_assert_object_safe!(dyn TaskKeyObj);
+impl<T: Task> TaskKeyObj for T {
+  #[inline]
+  fn as_key_obj(&self) -> &dyn KeyObj { self as &dyn KeyObj }
+  #[inline]
+  fn describe_obj(&self) -> Cow<'_, str> { self.describe() }
+}
+
+/// Object safe [`ResourceChecker`] proxy, for creating dependencies to [resources](Resource) whose concrete type is
+/// only known as a [`KeyObj`]. Create one with [`resource_checker_obj`].
+pub trait ResourceCheckerObj: DynClone + Debug {
+  /// Creates a write dependency from the current executing task in `session` to `resource`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the type of `resource` does not match the resource type of this checker.
+  fn written_to_obj(&self, session: &mut SessionInternal, resource: &dyn KeyObj) -> Result<(), Box<dyn Error>>;
+}
+const_assert_object_safe!(dyn ResourceCheckerObj);
+impl Clone for Box<dyn ResourceCheckerObj> {
+  #[inline]
+  fn clone(&self) -> Self { dyn_clone::clone_box(self.as_ref()) }
+}
+
+/// Boxes `checker` for resources of type `R` into a [resource checker trait object](ResourceCheckerObj).
+#[inline]
+pub fn resource_checker_obj<R: Resource, C: ResourceChecker<R>>(checker: C) -> Box<dyn ResourceCheckerObj> {
+  Box::new(TypedResourceChecker::<R, C>(checker, PhantomData))
+}
+
+/// [Resource checker trait object](ResourceCheckerObj) implementation for `checker` of resources of type `R`.
+struct TypedResourceChecker<R, C>(C, PhantomData<fn() -> R>);
+impl<R: Resource, C: ResourceChecker<R>> ResourceCheckerObj for TypedResourceChecker<R, C> {
+  #[inline]
+  fn written_to_obj(&self, session: &mut SessionInternal, resource: &dyn KeyObj) -> Result<(), Box<dyn Error>> {
+    let Some(resource) = resource.as_any().downcast_ref::<R>() else {
+      panic!("Non-matching resource type; resource '{:?}' does not match the resource type of checker '{:?}'",
+        resource, self.0);
+    };
+    session.written_to(resource, self.0.clone())?;
+    Ok(())
+  }
+}
+impl<R, C: Clone> Clone for TypedResourceChecker<R, C> {
+  #[inline]
+  fn clone(&self) -> Self { Self(self.0.clone(), PhantomData) }
+}
+impl<R, C: Debug> Debug for TypedResourceChecker<R, C> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { self.0.fmt(f) }
+}
+
+/// Object safe [`OutputChecker`] proxy, for requiring [tasks](Task) whose concrete type is only known as a [`KeyObj`].
+/// Create one with [`task_checker_obj`].
+pub trait TaskCheckerObj: DynClone + Debug {
+  /// Requires `task` in top-down `context`, returning its boxed output.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the type of `task` does not match the task type of this checker.
+  fn require_top_down(&self, context: &mut TopDownContext, task: &dyn KeyObj) -> Box<dyn ValueObj>;
+  /// Requires `task` in bottom-up `context`, returning its boxed output.
+  ///
+  /// # Panics
+  ///
+  /// Panics if the type of `task` does not match the task type of this checker.
+  fn require_bottom_up(&self, context: &mut BottomUpContext, task: &dyn KeyObj) -> Box<dyn ValueObj>;
+}
+const_assert_object_safe!(dyn TaskCheckerObj);
+impl Clone for Box<dyn TaskCheckerObj> {
+  #[inline]
+  fn clone(&self) -> Self { dyn_clone::clone_box(self.as_ref()) }
+}
+
+/// Boxes `checker` for tasks of type `T` into a [task checker trait object](TaskCheckerObj).
+#[inline]
+pub fn task_checker_obj<T: Task, C: OutputChecker<T::Output>>(checker: C) -> Box<dyn TaskCheckerObj> {
+  Box::new(TypedTaskChecker::<T, C>(checker, PhantomData))
+}
+
+/// [Task checker trait object](TaskCheckerObj) implementation for `checker` of tasks of type `T`.
+struct TypedTaskChecker<T, C>(C, PhantomData<fn() -> T>);
+impl<T: Task, C: OutputChecker<T::Output>> TypedTaskChecker<T, C> {
+  #[inline]
+  fn downcast<'t>(&self, task: &'t dyn KeyObj) -> &'t T {
+    let Some(task) = task.as_any().downcast_ref::<T>() else {
+      panic!("Non-matching task type; task '{:?}' does not match the task type of checker '{:?}'", task, self.0);
+    };
+    task
+  }
+}
+impl<T: Task, C: OutputChecker<T::Output>> TaskCheckerObj for TypedTaskChecker<T, C> {
+  #[inline]
+  fn require_top_down(&self, context: &mut TopDownContext, task: &dyn KeyObj) -> Box<dyn ValueObj> {
+    Box::new(context.require(self.downcast(task), self.0.clone()))
+  }
+  #[inline]
+  fn require_bottom_up(&self, context: &mut BottomUpContext, task: &dyn KeyObj) -> Box<dyn ValueObj> {
+    Box::new(context.require(self.downcast(task), self.0.clone()))
+  }
+}
+impl<T, C: Clone> Clone for TypedTaskChecker<T, C> {
+  #[inline]
+  fn clone(&self) -> Self { Self(self.0.clone(), PhantomData) }
+}
+impl<T, C: Debug> Debug for TypedTaskChecker<T, C> {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { self.0.fmt(f) }
+}
+
+
 #[cfg(test)]
 mod tests {
   use std::collections::hash_map::DefaultHasher;
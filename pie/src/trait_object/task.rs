@@ -5,19 +5,23 @@ use std::hash::{Hash, Hasher};
 use crate::{OutputChecker, Task};
 use crate::context::bottom_up::BottomUpContext;
 use crate::context::top_down::TopDownContext;
-use crate::trait_object::{KeyObj, ValueObj};
+use crate::trait_object::{KeyObj, TaskKeyObj, ValueObj};
 
 /// Internal object safe [`Task`] proxy. Has execute methods for concrete [`Context`] implementations, instead of a
 /// generic method, due to object safety.
-pub trait TaskObj: KeyObj {
-  fn as_key_obj(&self) -> &dyn KeyObj;
+pub trait TaskObj: TaskKeyObj {
+  fn as_task_key_obj(&self) -> &dyn TaskKeyObj;
+  fn cache_key_obj(&self) -> Box<dyn KeyObj>;
   fn execute_top_down(&self, context: &mut TopDownContext) -> Box<dyn ValueObj>;
   fn execute_bottom_up(&self, context: &mut BottomUpContext) -> Box<dyn ValueObj>;
+  fn precondition_bottom_up(&self, context: &mut BottomUpContext) -> Result<(), String>;
 }
 const_assert_object_safe!(dyn TaskObj);
 impl<T: Task> TaskObj for T {
   #[inline]
-  fn as_key_obj(&self) -> &dyn KeyObj { self as &dyn KeyObj }
+  fn as_task_key_obj(&self) -> &dyn TaskKeyObj { self as &dyn TaskKeyObj }
+  #[inline]
+  fn cache_key_obj(&self) -> Box<dyn KeyObj> { self.cache_key() }
   #[inline]
   fn execute_top_down(&self, context: &mut TopDownContext) -> Box<dyn ValueObj> {
     Box::new(self.execute(context))
@@ -26,6 +30,10 @@ impl<T: Task> TaskObj for T {
   fn execute_bottom_up(&self, context: &mut BottomUpContext) -> Box<dyn ValueObj> {
     Box::new(self.execute(context))
   }
+  #[inline]
+  fn precondition_bottom_up(&self, context: &mut BottomUpContext) -> Result<(), String> {
+    self.precondition(context)
+  }
 }
 impl<'a, T: Task> From<&'a T> for &'a dyn TaskObj {
   #[inline]
@@ -0,0 +1,78 @@
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::{Event, RecursiveMode, Watcher};
+
+use crate::{Pie, Session};
+use crate::tracker::Tracker;
+
+/// Duration to wait for further change events after receiving a change event, before running a build.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+impl<A: Tracker> Pie<A> {
+  /// Watches files and directories in `roots` (recursively) for changes, running a bottom-up build on each change and
+  /// then calling `on_change` with the session of that build. Returns `Ok(value)` when `on_change` returns
+  /// [`ControlFlow::Break(value)`](ControlFlow::Break), and continues watching when it returns
+  /// [`ControlFlow::Continue`].
+  ///
+  /// Change events are debounced: after a change event, further change events are collected until no change event has
+  /// been received for 100 milliseconds. Then, a new session is created, tasks affected by all changed paths are
+  /// [scheduled](crate::BottomUpBuild::schedule_tasks_affected_by), and the affected tasks are
+  /// [updated](crate::BottomUpBuild::update_affected_tasks). Access events (such as tasks reading files) are ignored.
+  ///
+  /// Changed paths are scheduled as reported by the watcher: the (relative or absolute) root they were found under,
+  /// joined with the path to the changed file or directory. Therefore, tasks should read files with paths in the same
+  /// form as `roots`.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error if watching any of the `roots` failed, or if the watcher reported an error.
+  pub fn watch<B>(
+    &mut self,
+    roots: &[PathBuf],
+    mut on_change: impl FnMut(&mut Session) -> ControlFlow<B>,
+  ) -> Result<B, notify::Error> {
+    let (sender, receiver) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for root in roots {
+      watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+
+    let mut changed_paths = Vec::new();
+    loop {
+      let event = receiver.recv().map_err(|_| notify::Error::generic("watcher stopped sending events"))?;
+      collect_changed_paths(event?, &mut changed_paths);
+      while let Ok(event) = receiver.recv_timeout(DEBOUNCE) {
+        collect_changed_paths(event?, &mut changed_paths);
+      }
+      if changed_paths.is_empty() {
+        continue;
+      }
+
+      let mut session = self.new_session();
+      let mut bottom_up_build = session.create_bottom_up_build();
+      for path in changed_paths.drain(..) {
+        bottom_up_build.schedule_tasks_affected_by(&path);
+      }
+      bottom_up_build.update_affected_tasks();
+      if let ControlFlow::Break(value) = on_change(&mut session) {
+        return Ok(value);
+      }
+    }
+  }
+}
+
+/// Collects the paths of `event` into `changed_paths`, unless it is an access event or the path was already collected.
+#[inline]
+fn collect_changed_paths(event: Event, changed_paths: &mut Vec<PathBuf>) {
+  if event.kind.is_access() {
+    return;
+  }
+  for path in event.paths {
+    if !changed_paths.contains(&path) {
+      changed_paths.push(path);
+    }
+  }
+}
@@ -1,3 +1,5 @@
+use std::convert::Infallible;
+use std::fmt::Debug;
 use std::fs::{read_to_string, write};
 use std::path::PathBuf;
 
@@ -7,10 +9,12 @@ use testresult::TestResult;
 use dev_ext::downcast::Downcast;
 use dev_ext::task::*;
 use dev_util::{create_temp_dir, write_until_modified};
-use pie::{Context, Task};
+use pie::{Context, Pie, PieStats, ResourceChecker, ResourceState, Task};
 use pie::resource::file::{ExistsChecker, FsError};
+use pie::resource::map::{GetGlobalMap, MapEqualsChecker, MapKey, MapWriter};
 use pie::task::AlwaysConsistent;
-use pie::trait_object::ValueObj;
+use pie::tracker::Tracker;
+use pie::trait_object::{TaskKeyObj, ValueObj};
 
 use crate::util::{new_test_pie, TestPieExt};
 
@@ -263,3 +267,117 @@ fn test_require_now() -> TestResult {
 
   Ok(())
 }
+
+#[test]
+fn test_statistics() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let origin_path = temp_dir.path().join("origin.txt");
+  let read_path = temp_dir.path().join("in.txt");
+  write(&origin_path, "origin")?;
+  write(&read_path, "hello world!")?;
+  let origin_task = ReadFile::new(&origin_path);
+  let task = ReadFile::new(&read_path).with_origin(origin_task.clone());
+
+  // Initially require the tasks, executing both in one session.
+  pie.require(&task)?;
+  assert_eq!(pie.statistics(), PieStats { session_count: 1, total_executions: 2, total_cache_hits: 0 });
+
+  // Change the file that `task` reads, so that only `task` is affected and executed, while `origin_task` is not
+  // affected and its cached output is reused.
+  write_until_modified(&read_path, "hello world!!")?;
+  pie.bottom_up_build_then_assert(|b| b.schedule_tasks_affected_by(&read_path), |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(!tracker.any_execute_of(&origin_task));
+  });
+  assert_eq!(pie.statistics(), PieStats { session_count: 2, total_executions: 3, total_cache_hits: 1 });
+
+  Ok(())
+}
+
+
+/// In-memory counter resource, which can only change by being written to in a build.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct Counter;
+impl MapKey for Counter {
+  type Value = usize;
+}
+
+/// [`MapEqualsChecker`] for [`Counter`] that opts out of checking external changes.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct InMemoryChecker;
+impl ResourceChecker<Counter> for InMemoryChecker {
+  type Stamp = Option<usize>;
+  type Error = Infallible;
+  fn stamp<RS: ResourceState<Counter>>(&self, key: &Counter, state: &mut RS) -> Result<Self::Stamp, Infallible> {
+    MapEqualsChecker.stamp(key, state)
+  }
+  fn stamp_reader(&self, key: &Counter, value: &mut Option<&usize>) -> Result<Self::Stamp, Infallible> {
+    MapEqualsChecker.stamp_reader(key, value)
+  }
+  fn stamp_writer(&self, key: &Counter, writer: MapWriter<'_, Counter>) -> Result<Self::Stamp, Infallible> {
+    MapEqualsChecker.stamp_writer(key, writer)
+  }
+  fn check<RS: ResourceState<Counter>>(
+    &self,
+    key: &Counter,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<impl Debug>, Infallible> {
+    MapEqualsChecker.check(key, state, stamp)
+  }
+  fn checks_external_changes(&self) -> bool { false }
+  fn wrap_error(&self, error: Infallible) -> Infallible { error }
+}
+
+/// Task that reads [`Counter`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadCounter;
+impl Task for ReadCounter {
+  type Output = Option<usize>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    match context.read(&Counter, InMemoryChecker) {
+      Ok(value) => value.copied(),
+      Err(e) => match e {},
+    }
+  }
+}
+
+/// Tracker that counts checks of read dependencies in bottom-up builds.
+#[derive(Default)]
+struct ReadCheckCountTracker(usize);
+impl Tracker for ReadCheckCountTracker {
+  fn check_task_read_resource_start(&mut self, _task: &dyn TaskKeyObj, _checker: &dyn ValueObj, _stamp: &dyn ValueObj) {
+    self.0 += 1;
+  }
+}
+
+#[test]
+fn test_unwritten_in_memory_resource_not_checked() -> TestResult {
+  let mut pie = Pie::with_tracker(ReadCheckCountTracker::default());
+  pie.resource_state_mut::<Counter>().get_global_map_mut().insert(Counter, 1);
+  assert_eq!(pie.new_session().require(&ReadCounter), Some(1));
+
+  // Nothing wrote to `Counter` in this session: its read dependency is not checked.
+  pie.run_in_session(|mut session| {
+    let mut bottom_up = session.create_bottom_up_build();
+    bottom_up.schedule_tasks_affected_by(&Counter);
+    bottom_up.update_affected_tasks();
+  });
+  assert_eq!(pie.tracker().0, 0);
+
+  // Read dependencies of resources whose checker checks external changes are still checked.
+  let temp_dir = create_temp_dir()?;
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "HELLO WORLD!")?;
+  pie.new_session().require(&ReadFile::new(&path))?;
+  pie.run_in_session(|mut session| {
+    let mut bottom_up = session.create_bottom_up_build();
+    bottom_up.schedule_tasks_affected_by(&path);
+    bottom_up.update_affected_tasks();
+  });
+  assert_eq!(pie.tracker().0, 1);
+
+  Ok(())
+}
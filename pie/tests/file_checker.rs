@@ -1,10 +1,20 @@
 use std::error::Error;
-use std::fs::{create_dir_all, remove_file, write};
+use std::fmt::Debug;
+use std::fs::{create_dir_all, remove_dir, remove_file, write, File};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use dev_ext::task::*;
 use dev_util::{create_temp_dir, wait_until_modified_time_changes, write_until_modified};
+use pie::{Context, ErrorKind, ResourceChecker, ResourceState, Task};
+use pie::resource::file::{FsError, OpenRead};
+use pie::resource::file::cached_checker::CachedChecker;
 use pie::resource::file::hash_checker::HashChecker;
-use pie::resource::file::ModifiedChecker;
+use pie::resource::file::normalized_text_checker::NormalizedTextChecker;
+use pie::resource::file::two_phase_checker::TwoPhaseChecker;
+use pie::resource::file::{FileChecker, MetadataChecker, MetadataFields, ModifiedChecker, ModifiedCreatedChecker,
+  OptionalModifiedChecker, PermissionsChecker, SymlinkTargetChecker};
 
 use crate::util::{new_test_pie, TestPieExt};
 
@@ -62,6 +72,137 @@ fn test_modified_checker_on_directory() -> Result<(), Box<dyn Error>> {
   Ok(())
 }
 
+#[test]
+fn test_modified_checker_classify_error() {
+  let checker = ModifiedChecker;
+  assert_eq!(checker.classify_error(&io::ErrorKind::NotFound.into()), ErrorKind::Permanent);
+  assert_eq!(checker.classify_error(&io::ErrorKind::PermissionDenied.into()), ErrorKind::Permanent);
+  assert_eq!(checker.classify_error(&io::ErrorKind::WouldBlock.into()), ErrorKind::Transient);
+  assert_eq!(checker.classify_error(&io::ErrorKind::TimedOut.into()), ErrorKind::Transient);
+}
+
+/// [`ModifiedChecker`] that classifies all errors as transient.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct TransientModifiedChecker;
+impl ResourceChecker<PathBuf> for TransientModifiedChecker {
+  type Stamp = <ModifiedChecker as ResourceChecker<PathBuf>>::Stamp;
+  type Error = FsError;
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, state: &mut RS) -> Result<Self::Stamp, FsError> {
+    ModifiedChecker.stamp(path, state)
+  }
+  fn stamp_reader(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, FsError> {
+    ModifiedChecker.stamp_reader(path, open_read)
+  }
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, FsError> {
+    ModifiedChecker.stamp_writer(path, file)
+  }
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<impl Debug>, FsError> {
+    ModifiedChecker.check(path, state, stamp)
+  }
+  fn wrap_error(&self, error: FsError) -> FsError { error }
+  fn classify_error(&self, _error: &FsError) -> ErrorKind { ErrorKind::Transient }
+}
+
+#[test]
+fn test_wrapping_checkers_classify_error() {
+  let not_found: FsError = io::ErrorKind::NotFound.into();
+  assert_eq!(CachedChecker(ModifiedChecker).classify_error(&not_found), ErrorKind::Permanent);
+  assert_eq!(CachedChecker(TransientModifiedChecker).classify_error(&not_found), ErrorKind::Transient);
+  let (permanent, transient) = (ModifiedChecker, TransientModifiedChecker);
+  assert_eq!(TwoPhaseChecker(permanent, permanent).classify_error(&not_found), ErrorKind::Permanent);
+  assert_eq!(TwoPhaseChecker(permanent, transient).classify_error(&not_found), ErrorKind::Transient);
+  assert_eq!(TwoPhaseChecker(transient, permanent).classify_error(&not_found), ErrorKind::Transient);
+}
+
+#[test]
+fn test_modified_created_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello world!")?;
+
+  // Where creation dates are unavailable, behavior matches `ModifiedChecker`, so both tasks are executed identically.
+  let task = ReadFile::new(&path).with_checker(ModifiedCreatedChecker);
+  let modified_task = ReadFile::new(&path).with_checker(ModifiedChecker);
+
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  pie.require_then_assert_one_execute(&modified_task)?;
+  // Stamp unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  pie.require_then_assert_no_execute(&modified_task)?;
+  // Stamp changed even though file contents is the same: execute
+  write_until_modified(&path, "hello world!")?;
+  pie.require_then_assert_one_execute(&task)?;
+  pie.require_then_assert_one_execute(&modified_task)?;
+  // File removed: execute
+  remove_file(&path)?;
+  assert!(pie.require_then_assert_one_execute(&task).is_err());
+  assert!(pie.require_then_assert_one_execute(&modified_task).is_err());
+  // File re-created: execute
+  write(&path, "hello world!")?;
+  pie.require_then_assert_one_execute(&task)?;
+  pie.require_then_assert_one_execute(&modified_task)?;
+
+  Ok(())
+}
+
+/// Task that reads the contents of an optional file, returning `None` if no file is present.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadOptionalFile(PathBuf);
+impl Task for ReadOptionalFile {
+  type Output = Result<Option<String>, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let Some(mut file) = context.read(&self.0, OptionalModifiedChecker)?.into_file() else {
+      return Ok(None);
+    };
+    let mut buf = String::new();
+    file.read_to_string(&mut buf)?;
+    Ok(Some(buf))
+  }
+}
+
+#[test]
+fn test_optional_modified_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  let task = ReadOptionalFile(path.clone());
+
+  // New task: execute
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, None);
+  // Absent then absent: no execute
+  assert_eq!(pie.require_then_assert_no_execute(&task)?, None);
+  // File appeared: execute
+  write(&path, "hello world!")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_deref(), Some("hello world!"));
+  // Present unchanged: no execute
+  assert_eq!(pie.require_then_assert_no_execute(&task)?.as_deref(), Some("hello world!"));
+  // File modified: execute
+  write_until_modified(&path, "hello!")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_deref(), Some("hello!"));
+  // File disappeared: execute
+  remove_file(&path)?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, None);
+  assert_eq!(pie.require_then_assert_no_execute(&task)?, None);
+  // Directory appeared, which is treated as absent: no execute
+  create_dir_all(&path)?;
+  assert_eq!(pie.require_then_assert_no_execute(&task)?, None);
+  // Directory replaced by a file: execute
+  remove_dir(&path)?;
+  write(&path, "hello world!")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_deref(), Some("hello world!"));
+
+  Ok(())
+}
+
 #[test]
 fn test_hash_checker_on_file() -> Result<(), Box<dyn Error>> {
   let mut pie = new_test_pie();
@@ -118,3 +259,315 @@ fn test_hash_checker_on_directory() -> Result<(), Box<dyn Error>> {
 
   Ok(())
 }
+
+#[test]
+fn test_metadata_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello world!")?;
+
+  let task = ReadFile::new(&path).with_checker(MetadataChecker::new(MetadataFields::SIZE));
+
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  // Stamp unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  // File modified, but size is unchanged: no execute
+  write_until_modified(&path, "HELLO WORLD!")?;
+  pie.require_then_assert_no_execute(&task)?;
+  // Size changed: execute
+  write(&path, "hello world!!")?;
+  pie.require_then_assert_one_execute(&task)?;
+
+  // Also selecting the modified date: modifications with the same size execute as well.
+  let task = ReadFile::new(&path).with_checker(MetadataChecker::new(MetadataFields::SIZE | MetadataFields::MODIFIED));
+  pie.require_then_assert_one_execute(&task)?;
+  write_until_modified(&path, "HELLO WORLD!!")?;
+  pie.require_then_assert_one_execute(&task)?;
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_permissions_checker_on_file() -> Result<(), Box<dyn Error>> {
+  use std::fs::{metadata, set_permissions};
+  use std::os::unix::fs::PermissionsExt;
+
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.sh");
+  write(&path, "echo hello world!")?;
+  let mut permissions = metadata(&path)?.permissions();
+  permissions.set_mode(0o644);
+  set_permissions(&path, permissions.clone())?;
+
+  let task = ReadFile::new(&path).with_checker(PermissionsChecker);
+
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  // Stamp unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp unchanged because mode is unchanged, even though file contents changed: no execute
+  write_until_modified(&path, "echo hello!")?;
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp changed because file was made executable: execute
+  permissions.set_mode(0o755);
+  set_permissions(&path, permissions)?;
+  pie.require_then_assert_one_execute(&task)?;
+  // Stamp unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_target_checker() -> Result<(), Box<dyn Error>> {
+  use std::os::unix::fs::symlink;
+
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let target_1 = temp_dir.path().join("v1.txt");
+  write(&target_1, "hello world!")?;
+  let target_2 = temp_dir.path().join("v2.txt");
+  write(&target_2, "hello world!")?;
+  let link = temp_dir.path().join("current.txt");
+  symlink(&target_1, &link)?;
+
+  let task = ReadFile::new(&link).with_checker(SymlinkTargetChecker);
+
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  // Stamp unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp unchanged because the link is unchanged, even though the target file changed: no execute
+  write_until_modified(&target_1, "hello!")?;
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp changed because the link was retargeted, even though the target file contents are the same: execute
+  remove_file(&link)?;
+  symlink(&target_2, &link)?;
+  pie.require_then_assert_one_execute(&task)?;
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp changed because the link was replaced by a regular file: execute
+  remove_file(&link)?;
+  write(&link, "hello world!")?;
+  pie.require_then_assert_one_execute(&task)?;
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp changed because the file was removed: execute
+  remove_file(&link)?;
+  assert!(pie.require_then_assert_one_execute(&task).is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_normalized_text_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello\r\nworld!\r\n")?;
+
+  let task = ReadFile::new(&path).with_checker(NormalizedTextChecker);
+
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  // Stamp unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp unchanged because only line endings changed from CRLF to LF: no execute
+  write(&path, "hello\nworld!\n")?;
+  pie.require_then_assert_no_execute(&task)?;
+  // Stamp changed because file contents changed: execute
+  write(&path, "hello\nnormalized world!\n")?;
+  pie.require_then_assert_one_execute(&task)?;
+
+  Ok(())
+}
+
+#[test]
+fn test_file_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello world!")?;
+
+  // Select checkers at runtime, storing them in a field of the same task type.
+  let [modified, exists, hash, permissions] =
+    [FileChecker::Modified, FileChecker::Exists, FileChecker::Hash, FileChecker::Permissions]
+      .map(|checker| ReadFile::new(&path).with_checker(checker));
+
+  // New tasks: execute
+  for task in [&modified, &exists, &hash, &permissions] {
+    pie.require_then_assert_one_execute(task)?;
+  }
+  // Stamps unchanged: no execute
+  for task in [&modified, &exists, &hash, &permissions] {
+    pie.require_then_assert_no_execute(task)?;
+  }
+  // File contents are the same, but modified date changed: only execute modified task
+  write_until_modified(&path, "hello world!")?;
+  pie.require_then_assert_one_execute(&modified)?;
+  for task in [&exists, &hash, &permissions] {
+    pie.require_then_assert_no_execute(task)?;
+  }
+  // File contents changed: execute modified and hash tasks
+  write_until_modified(&path, "hello!")?;
+  for task in [&modified, &hash] {
+    pie.require_then_assert_one_execute(task)?;
+  }
+  for task in [&exists, &permissions] {
+    pie.require_then_assert_no_execute(task)?;
+  }
+  // File removed: execute all tasks
+  remove_file(&path)?;
+  for task in [&modified, &exists, &hash, &permissions] {
+    assert!(pie.require_then_assert_one_execute(task).is_err());
+  }
+
+  Ok(())
+}
+
+/// Task that reads file `self.0` with [`FileChecker::Exists`], and then reads it again with [`FileChecker::Hash`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadExistsThenHash(PathBuf);
+impl Task for ReadExistsThenHash {
+  type Output = Result<String, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.read(&self.0, FileChecker::Exists)?;
+    let mut string = String::new();
+    if let Some(file) = context.read(&self.0, FileChecker::Hash)?.as_file() {
+      file.read_to_string(&mut string)?;
+    }
+    Ok(string)
+  }
+}
+
+#[test]
+fn test_file_checker_variants_on_same_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello world!")?;
+
+  let task = ReadExistsThenHash(path.clone());
+
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  // File unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  // File still exists but its contents changed: execute, as both dependencies are kept
+  write_until_modified(&path, "hello!")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, "hello!");
+
+  Ok(())
+}
+
+/// Number of times [`CountingHashChecker`] stamped or checked a file.
+static HASH_CHECKER_INVOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// [`HashChecker`] that counts its invocations in [`HASH_CHECKER_INVOCATIONS`].
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct CountingHashChecker;
+impl ResourceChecker<PathBuf> for CountingHashChecker {
+  type Stamp = <HashChecker as ResourceChecker<PathBuf>>::Stamp;
+  type Error = FsError;
+
+  fn stamp<RS: ResourceState<PathBuf>>(&self, path: &PathBuf, state: &mut RS) -> Result<Self::Stamp, FsError> {
+    HASH_CHECKER_INVOCATIONS.fetch_add(1, Ordering::SeqCst);
+    HashChecker.stamp(path, state)
+  }
+  fn stamp_reader(&self, path: &PathBuf, open_read: &mut OpenRead) -> Result<Self::Stamp, FsError> {
+    HASH_CHECKER_INVOCATIONS.fetch_add(1, Ordering::SeqCst);
+    HashChecker.stamp_reader(path, open_read)
+  }
+  fn stamp_writer(&self, path: &PathBuf, file: File) -> Result<Self::Stamp, FsError> {
+    HASH_CHECKER_INVOCATIONS.fetch_add(1, Ordering::SeqCst);
+    HashChecker.stamp_writer(path, file)
+  }
+  #[allow(refining_impl_trait)]
+  fn check<RS: ResourceState<PathBuf>>(
+    &self,
+    path: &PathBuf,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<Self::Stamp>, FsError> {
+    HASH_CHECKER_INVOCATIONS.fetch_add(1, Ordering::SeqCst);
+    HashChecker.check(path, state, stamp)
+  }
+  fn wrap_error(&self, error: FsError) -> FsError { error }
+}
+
+#[test]
+fn test_cached_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello world!")?;
+
+  let task = ReadFile::new(&path).with_checker(CachedChecker::new(CountingHashChecker));
+  let invocations = || HASH_CHECKER_INVOCATIONS.load(Ordering::SeqCst);
+
+  // New task: execute, stamping the file with the inner checker
+  pie.require_then_assert_one_execute(&task)?;
+  assert_eq!(invocations(), 1);
+  // Modified date unchanged: no execute, and the inner checker is not invoked
+  pie.require_then_assert_no_execute(&task)?;
+  pie.require_then_assert_no_execute(&task)?;
+  assert_eq!(invocations(), 1);
+  // Modified date changed but file contents the same: no execute, stamping the file with the inner checker once
+  write_until_modified(&path, "hello world!")?;
+  pie.require_then_assert_no_execute(&task)?;
+  assert_eq!(invocations(), 2);
+  pie.require_then_assert_no_execute(&task)?;
+  assert_eq!(invocations(), 2);
+  // File contents changed: execute
+  write_until_modified(&path, "hello!")?;
+  pie.require_then_assert_one_execute(&task)?;
+  // File removed: execute
+  remove_file(&path)?;
+  assert!(pie.require_then_assert_one_execute(&task).is_err());
+
+  Ok(())
+}
+
+#[test]
+fn test_two_phase_checker_on_file() -> Result<(), Box<dyn Error>> {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "hello world!")?;
+
+  let task = ReadFile::new(&path).with_checker(TwoPhaseChecker::new(ModifiedChecker, HashChecker));
+  // New task: execute
+  pie.require_then_assert_one_execute(&task)?;
+  // Modified date unchanged: no execute
+  pie.require_then_assert_no_execute(&task)?;
+  // Modified date changed but file contents the same: slow check rescues it, no execute
+  write_until_modified(&path, "hello world!")?;
+  pie.require_then_assert_no_execute(&task)?;
+  pie.require_then_assert_no_execute(&task)?;
+  // File contents changed: execute
+  write_until_modified(&path, "hello!")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_str(), "hello!");
+  // File removed: execute
+  remove_file(&path)?;
+  assert!(pie.require_then_assert_one_execute(&task).is_err());
+
+  // Writing stamps with both checkers.
+  let write_path = temp_dir.path().join("write.txt");
+  let checker = TwoPhaseChecker::new(ModifiedChecker, HashChecker);
+  let write = WriteFile::with_checker(Constant::new_ok("hello"), &write_path, checker);
+  pie.require_then_assert_one_execute(&write)?;
+  pie.require_then_assert_no_execute(&write)?;
+
+  Ok(())
+}
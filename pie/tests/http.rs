@@ -0,0 +1,85 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use testresult::TestResult;
+
+use pie::{Context, Task};
+use pie::resource::http::{HttpError, HttpETagChecker, HttpResource, Url};
+
+use crate::util::{new_test_pie, TestPieExt};
+
+mod util;
+
+/// Task that reads the body of an HTTP resource, using [`HttpETagChecker`] for consistency checking.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadUrl(Url);
+impl Task for ReadUrl {
+  type Output = Result<String, HttpError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let mut reader = context.read(&HttpResource(self.0.clone()), HttpETagChecker)?;
+    let mut body = String::new();
+    reader.read_to_string(&mut body).map_err(|e| HttpError::Transport(e.to_string()))?;
+    Ok(body)
+  }
+}
+
+/// Mock HTTP server state: the current `ETag` and body.
+type MockState = Arc<Mutex<(String, String)>>;
+
+/// Starts a mock HTTP server that serves `state` for every path, responding with `304 Not Modified` to requests with a
+/// matching `If-None-Match` header. Returns the URL of the server.
+fn start_mock_server(state: MockState) -> Result<Url, Box<dyn std::error::Error>> {
+  let listener = TcpListener::bind("127.0.0.1:0")?;
+  let url = Url::parse(&format!("http://{}/resource", listener.local_addr()?))?;
+  thread::spawn(move || {
+    for stream in listener.incoming() {
+      let Ok(mut stream) = stream else { continue; };
+      let mut reader = BufReader::new(&mut stream);
+      let mut request_line = String::new();
+      if reader.read_line(&mut request_line).is_err() { continue; }
+      let mut if_none_match = None;
+      loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).is_err() || line.trim().is_empty() { break; }
+        if let Some((name, value)) = line.split_once(':') {
+          if name.eq_ignore_ascii_case("If-None-Match") {
+            if_none_match = Some(value.trim().to_string());
+          }
+        }
+      }
+      let (etag, body) = state.lock().unwrap().clone();
+      let is_head = request_line.starts_with("HEAD");
+      let response = if if_none_match.as_ref() == Some(&etag) {
+        format!("HTTP/1.1 304 Not Modified\r\nETag: {}\r\nConnection: close\r\n\r\n", etag)
+      } else {
+        let content = if is_head { "" } else { body.as_str() };
+        format!(
+          "HTTP/1.1 200 OK\r\nETag: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+          etag, body.len(), content
+        )
+      };
+      let _ = stream.write_all(response.as_bytes());
+    }
+  });
+  Ok(url)
+}
+
+#[test]
+fn test_etag_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let state: MockState = Arc::new(Mutex::new(("\"v1\"".to_string(), "Hello".to_string())));
+  let url = start_mock_server(state.clone())?;
+  let task = ReadUrl(url);
+
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_str(), "Hello");
+  // ETag is unchanged: the conditional request returns `304 Not Modified`, so the task is not executed.
+  assert_eq!(pie.require_then_assert_no_execute(&task)?.as_str(), "Hello");
+
+  *state.lock().unwrap() = ("\"v2\"".to_string(), "World".to_string());
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_str(), "World");
+  assert_eq!(pie.require_then_assert_no_execute(&task)?.as_str(), "World");
+
+  Ok(())
+}
@@ -0,0 +1,46 @@
+use std::io::Read;
+
+use testresult::TestResult;
+
+use pie::{Context, ResourceChecker, Task};
+use pie::resource::process::{ProcessError, ProcessOutputHashChecker, ProcessOutputResource};
+
+use crate::util::{new_test_pie, TestPieExt};
+
+mod util;
+
+/// Task that reads the standard output of a process, using [`ProcessOutputHashChecker`] for consistency checking.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadProcessOutput(ProcessOutputResource);
+impl Task for ReadProcessOutput {
+  type Output = Result<String, ProcessError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let mut reader = context.read(&self.0, ProcessOutputHashChecker)?;
+    let mut stdout = String::new();
+    reader.read_to_string(&mut stdout)?;
+    Ok(stdout)
+  }
+}
+
+#[test]
+fn test_hash_checker_stable() -> TestResult {
+  let mut pie = new_test_pie();
+  let resource = ProcessOutputResource::new("echo", ["Hello", "World"]);
+
+  // Stamping runs the process again, but identical output results in identical stamps.
+  let state = pie.resource_state_mut::<ProcessOutputResource>();
+  let stamp = ProcessOutputHashChecker.stamp(&resource, state)?;
+  assert_eq!(ProcessOutputHashChecker.stamp(&resource, state)?, stamp);
+  assert_eq!(ProcessOutputHashChecker.check(&resource, state, &stamp)?, None);
+  let other = ProcessOutputResource::new("echo", ["Hello", "Pie"]);
+  assert_ne!(ProcessOutputHashChecker.stamp(&other, state)?, stamp);
+
+  let task = ReadProcessOutput(resource);
+  assert_eq!(pie.require_then_assert_one_execute(&task)?.as_str(), "Hello World\n");
+  assert_eq!(pie.require_then_assert_no_execute(&task)?.as_str(), "Hello World\n");
+
+  let task = ReadProcessOutput(ProcessOutputResource::new("false", Vec::<String>::new()));
+  assert_eq!(pie.require(&task), Err(ProcessError::ExitStatus(Some(1))));
+
+  Ok(())
+}
@@ -1,18 +1,43 @@
-use std::fs::{remove_file, write};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt::Debug;
+use std::fs::{self, create_dir, remove_dir_all, remove_file, write};
+use std::io::{self, Read, Write};
 use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 use assert_matches::assert_matches;
 use testresult::TestResult;
 
 use dev_ext::task::*;
 use dev_util::{create_temp_dir, write_until_modified};
-use pie::{Context, Task};
+use pie::{BuildViolation, Change, Context, ErrorKind, OutputChecker, Pie, PieStats, RequireOutcome, Resource,
+  ResourceChecker, ResourceState, RetryPolicy, Task, Value, WriteOutcome};
+use pie::plan::{BuildPlan, PlanDependency, PlanDependencyKind, PlanDivergence, PlanStep};
+use pie::resource;
+use pie::resource::closure::ClosureResource;
 use pie::resource::file::{ExistsChecker, FsError, ModifiedChecker};
 use pie::resource::file::hash_checker::HashChecker;
-use pie::task::AlwaysConsistent;
+use pie::task::{AlwaysConsistent, ApproxEqualsChecker, Clock, ContainsChecker, DebounceChecker, EqualsChecker,
+  GenerationChecker, HashOutputChecker, IndexChecker, LenChecker, TaskGraph, TaskId};
+#[cfg(feature = "serde")]
+use pie::task::SerdeEqChecker;
+use pie::tracker::check_error::CheckErrorTracker;
+use pie::tracker::double_execution::DoubleExecutionGuardTracker;
+use pie::tracker::chrome_trace::ChromeTraceTracker;
 use pie::tracker::event::*;
+use pie::tracker::Tracker;
+use pie::tracker::sampling::SamplingTracker;
+use pie::tracker::writing::WritingTracker;
+use pie::trait_object::{KeyObj, resource_checker_obj, task_checker_obj, TaskKeyObj, ValueObj};
 
-use crate::util::{new_test_pie, TestPieExt};
+use crate::util::{new_test_pie, TestPie, TestPieExt};
 
 mod util;
 
@@ -204,34 +229,2263 @@ fn require_task() -> TestResult {
   Ok(())
 }
 
+#[test]
+fn reset_outputs() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  let read = ReadFile::new(&file);
+  let lower = ToLower(read.clone());
+
+  // New tasks: execute both.
+  pie.require(&lower)?;
+  // Nothing changed: no execute.
+  pie.require_then_assert_no_execute(&lower)?;
+  // Reset outputs: execute both even though nothing changed.
+  pie.reset_outputs();
+  let output = pie.require_then_assert(&lower, |tracker| {
+    assert!(tracker.one_execute_of(&lower));
+    assert!(tracker.one_execute_of(&read));
+  })?;
+  assert_eq!(output.as_str(), "hello world!");
+  // Nothing changed: no execute.
+  pie.require_then_assert_no_execute(&lower)?;
+
+  Ok(())
+}
+
+#[test]
+fn executed_in_order() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  let read = ReadFile::new(&file);
+  let lower = ToLower(read.clone());
+  let require = Require::new(lower.clone());
+
+  // Tasks in a dependency chain start executing from the requiring task down to the required task.
+  pie.require_then_assert(&require, |tracker| {
+    assert!(tracker.executed_in_order(&[&require, &lower, &read]));
+    assert!(tracker.executed_in_order(&[&require, &read]));
+    assert!(tracker.executed_in_order(&[]));
+    assert!(!tracker.executed_in_order(&[&read, &lower, &require]));
+    assert!(!tracker.executed_in_order(&[&require, &require]));
+    // Not executed: not in order.
+    assert!(!tracker.executed_in_order(&[&require, &lower, &read, &Constant("Hello, World!")]));
+  })?;
+
+  Ok(())
+}
+
 #[test]
 fn no_superfluous_task_dependencies() -> TestResult {
   let mut pie = new_test_pie();
   let temp_dir = create_temp_dir()?;
 
-  let file = temp_dir.path().join("in.txt");
-  write(&file, "Hello, World!")?;
-  let read = ReadFile::new(&file);
-  let lower = ToLower(read.clone());
-  let upper = ToUpper(lower.clone());
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "Hello, World!")?;
+  let read = ReadFile::new(&file);
+  let lower = ToLower(read.clone());
+  let upper = ToUpper(lower.clone());
+
+  // Require `ToLower` and assert that `ReadFile` and `ToLower` are executed because they are new, but not `ToUpper`,
+  // because it not required by anything. `ToLower` will return `"hello, world!"`.
+  let output = pie.require_then_assert(&lower, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&lower));
+    assert!(!tracker.any_execute_of(&upper));
+  })?;
+  assert_eq!(output.as_str(), "hello, world!");
+
+  // Require `ToUpper` and assert that it is executed because it is new, but not `ReadFile` nor `ToLower` because their
+  // dependencies are consistent.
+  let output = pie.require_then_assert(&upper, |tracker| {
+    assert!(!tracker.any_execute_of(&read));
+    assert!(!tracker.any_execute_of(&lower));
+    assert!(tracker.one_execute_of(&upper));
+  })?;
+  assert_eq!(output.as_str(), "HELLO, WORLD!");
+
+  Ok(())
+}
+
+
+// Fold tests.
+
+/// Task that sums the outputs of integer constant tasks.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Sum(Vec<Constant<i32>>);
+impl Task for Sum {
+  type Output = i32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.require_fold(&self.0, EqualsChecker, 0, |sum, output| sum + output)
+  }
+}
+
+#[test]
+fn require_fold() {
+  let mut pie = new_test_pie();
+  let constants = vec![Constant(1), Constant(2), Constant(3), Constant(4)];
+  let sum = Sum(constants.clone());
+
+  // New task: execute, requiring all constants.
+  let output = pie.require_then_assert(&sum, |tracker| {
+    assert!(tracker.one_execute_of(&sum));
+    for constant in &constants {
+      assert!(tracker.one_execute_of(constant));
+    }
+  });
+  assert_eq!(output, 10);
+
+  // Nothing changed: no execute.
+  let output = pie.require_then_assert_no_execute(&sum);
+  assert_eq!(output, 10);
+}
+
+
+// Require ok tests.
+
+/// Task that returns the length of the string read by [`ReadFile`] task `self.0`, propagating its errors.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Length(ReadFile<ModifiedChecker, ()>);
+impl Task for Length {
+  type Output = Result<usize, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let string = context.require_ok(&self.0, EqualsChecker)?;
+    Ok(string.len())
+  }
+}
+
+#[test]
+fn require_ok() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "Hello")?;
+  let read = ReadFile::new(&file);
+  let task = Length(read.clone());
+
+  assert_eq!(pie.require(&task)?, 5);
+
+  // Error is propagated, and the dependency is still created.
+  remove_file(&file)?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, Err(FsError::from(io::ErrorKind::NotFound)));
+  write(&file, "Hello, World!")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, Ok(13));
+
+  Ok(())
+}
+
+// Require optional tests.
+
+/// Task that returns the length of the string read by [`ReadFile`] task `self.0`, or `None` if reading failed.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct OptionalLength(ReadFile<ModifiedChecker, ()>);
+impl Task for OptionalLength {
+  type Output = Option<usize>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.require_optional(&self.0, EqualsChecker).map(|string| string.len())
+  }
+}
+
+#[test]
+fn require_optional() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in.txt");
+  let read = ReadFile::new(&file);
+  let task = OptionalLength(read.clone());
+
+  // Optional dependency fails: `None`.
+  assert_eq!(pie.require(&task), None);
+  pie.require_then_assert_no_execute(&task);
+
+  // Optional dependency succeeds: the dependency was created, so the dependent is re-executed and sees `Some`.
+  write(&file, "Hello")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, Some(5));
+
+  Ok(())
+}
+
+// Require indirect tests.
+
+/// Task that reads the file at `self.0`, selecting the file to read with a [`ReadFile`] task: `self.1` if the file
+/// contains `"1"`, `self.2` otherwise.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct SelectFile(PathBuf, PathBuf, PathBuf);
+impl Task for SelectFile {
+  type Output = ReadFile<ModifiedChecker, ()>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let mut string = String::new();
+    context.read(&self.0, ModifiedChecker).unwrap().as_file().unwrap().read_to_string(&mut string).unwrap();
+    ReadFile::new(if string == "1" { &self.1 } else { &self.2 })
+  }
+}
+
+/// Task that requires the [`ReadFile`] task selected by [`SelectFile`] task `self.0`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct RequireSelected(SelectFile);
+impl Task for RequireSelected {
+  type Output = Result<String, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.require_indirect(&self.0, EqualsChecker)
+  }
+}
+
+#[test]
+fn require_indirect() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let selection_file = temp_dir.path().join("selection.txt");
+  write(&selection_file, "1")?;
+  let file_1 = temp_dir.path().join("1.txt");
+  write(&file_1, "Hello")?;
+  let file_2 = temp_dir.path().join("2.txt");
+  write(&file_2, "World")?;
+  let select = SelectFile(selection_file.clone(), file_1.clone(), file_2.clone());
+  let read_1 = ReadFile::new(&file_1);
+  let read_2 = ReadFile::new(&file_2);
+  let task = RequireSelected(select.clone());
+
+  // New tasks: execute all but `read_2`, which is not selected.
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(tracker.one_execute_of(&select));
+    assert!(tracker.one_execute_of(&read_1));
+    assert!(!tracker.any_execute_of(&read_2));
+  })?;
+  assert_eq!(output, "Hello");
+
+  // Unselected file changes: execute nothing.
+  write_until_modified(&file_2, "World!")?;
+  pie.require_then_assert(&task, |tracker| {
+    assert!(!tracker.any_execute_of(&task));
+    assert!(!tracker.any_execute_of(&read_2));
+  })?;
+
+  // Selected file changes: execute `read_1` and `task`, but not `select`.
+  write_until_modified(&file_1, "Hello!")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(!tracker.any_execute_of(&select));
+    assert!(tracker.one_execute_of(&read_1));
+  })?;
+  assert_eq!(output, "Hello!");
+
+  // Selection changes: execute `select`, `task`, and the newly selected `read_2`.
+  write_until_modified(&selection_file, "2")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(tracker.one_execute_of(&select));
+    assert!(!tracker.any_execute_of(&read_1));
+    assert!(tracker.one_execute_of(&read_2));
+  })?;
+  assert_eq!(output, "World!");
+
+  // Previously selected file changes: no longer a dependency, execute nothing.
+  write_until_modified(&file_1, "Hello?")?;
+  pie.require_then_assert(&task, |tracker| {
+    assert!(!tracker.any_execute_of(&task));
+    assert!(!tracker.any_execute_of(&read_1));
+  })?;
+
+  Ok(())
+}
+
+// Require dyn tests.
+
+/// Task that requires boxed tasks `self.0` whose concrete types are only known at runtime: a `&'static str` constant,
+/// a `usize` constant, and a [`ReadFile`] task.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct RequireAllDyn(Vec<Box<dyn KeyObj>>);
+impl Task for RequireAllDyn {
+  type Output = Vec<Box<dyn ValueObj>>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.require_all_dyn(&self.0, |i| match i {
+      0 => task_checker_obj::<Constant<&'static str>, _>(EqualsChecker),
+      1 => task_checker_obj::<Constant<usize>, _>(EqualsChecker),
+      _ => task_checker_obj::<ReadFile<ModifiedChecker, ()>, _>(EqualsChecker),
+    })
+  }
+}
+
+#[test]
+fn require_all_dyn() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "Hello, File!")?;
+  let read_file = ReadFile::new(&file);
+  let task = RequireAllDyn(vec![Box::new(Constant("Hello")), Box::new(Constant(42usize)), Box::new(read_file.clone())]);
+
+  let outputs = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(tracker.one_execute_of(&read_file));
+  });
+  assert_eq!(outputs.len(), 3);
+  assert_eq!(outputs[0].as_any().downcast_ref::<&'static str>(), Some(&"Hello"));
+  assert_eq!(outputs[1].as_any().downcast_ref::<usize>(), Some(&42));
+  assert_matches!(outputs[2].as_any().downcast_ref::<Result<String, FsError>>(), Some(Ok(s)) if s == "Hello, File!");
+
+  // Dependencies to the boxed tasks are created as usual.
+  pie.require_then_assert_no_execute(&task);
+  write_until_modified(&file, "Hello, Changed!")?;
+  let outputs = pie.require_then_assert_one_execute(&task);
+  assert_matches!(outputs[2].as_any().downcast_ref::<Result<String, FsError>>(), Some(Ok(s)) if s == "Hello, Changed!");
+
+  Ok(())
+}
+
+
+// Peek output tests.
+
+/// Task that peeks at the output of another task.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Peek<T>(T);
+impl<T: Task> Task for Peek<T> {
+  type Output = Option<T::Output>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.peek_output(&self.0)
+  }
+}
+
+#[test]
+fn peek_output() {
+  let mut pie = new_test_pie();
+  let constant = Constant("Hello, World!");
+
+  // `constant` has not been executed yet, so there is no output to peek at. Peeking does not execute `constant`.
+  let output = pie.require_then_assert(&Peek(constant), |tracker| {
+    assert!(!tracker.any_execute_of(&constant));
+  });
+  assert_eq!(output, None);
+
+  // After executing `constant`, a new task peeking at it gets its output, again without executing `constant`.
+  let mut pie = new_test_pie();
+  pie.require(&constant);
+  let output = pie.require_then_assert(&Peek(constant), |tracker| {
+    assert!(!tracker.any_execute_of(&constant));
+  });
+  assert_eq!(output, Some("Hello, World!"));
+}
+
+
+// Memo local tests.
+
+/// Number of times [`MemoSquare`] computed a square.
+static SQUARE_COMPUTATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Task that squares `self.0` twice with [`Context::memo_local`], returning the sum of both squares.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct MemoSquare(u64);
+impl Task for MemoSquare {
+  type Output = u64;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let square = |n: u64| move || {
+      SQUARE_COMPUTATIONS.fetch_add(1, Ordering::SeqCst);
+      n * n
+    };
+    context.memo_local(self.0, square(self.0)) + context.memo_local(self.0, square(self.0))
+  }
+}
+
+#[test]
+fn memo_local() {
+  let mut pie = new_test_pie();
+
+  // Same key in one session: compute once, also across tasks.
+  pie.run_in_session(|mut session| {
+    assert_eq!(session.require(&MemoSquare(3)), 18);
+    assert_eq!(session.require(&Require::new(MemoSquare(3))), 18);
+    assert_eq!(SQUARE_COMPUTATIONS.load(Ordering::SeqCst), 1);
+  });
+  // Memoized values do not create tasks.
+  assert_eq!(pie.tasks().len(), 2);
+
+  // Memoized values are not persisted: compute again in a new session.
+  pie.run_in_session(|mut session| {
+    session.invalidate(&MemoSquare(3));
+    assert_eq!(session.require(&MemoSquare(3)), 18);
+    assert_eq!(SQUARE_COMPUTATIONS.load(Ordering::SeqCst), 2);
+  });
+}
+
+
+// Scope tests.
+
+/// Task that requires its first task inside a scope labelled `"inner"`, and its second task outside of it.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Scoped<T>(T, T);
+impl<T: Task> Task for Scoped<T> where T::Output: Eq {
+  type Output = (T::Output, T::Output);
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let inner = context.scope("inner", |context| context.require(&self.0, EqualsChecker));
+    let outer = context.require(&self.1, EqualsChecker);
+    (inner, outer)
+  }
+}
+
+#[test]
+fn scope() {
+  let mut pie = new_test_pie();
+  let inner = Constant("Inner");
+  let outer = Constant("Outer");
+  let task = Scoped(inner, outer);
+
+  let output = pie.require_then_assert(&task, |tracker| {
+    let scope_range = tracker.first_scope_range("inner").unwrap();
+    let inner_range = tracker.first_require_range(&inner).unwrap();
+    let outer_range = tracker.first_require_range(&outer).unwrap();
+    assert!(scope_range.start() < inner_range.start() && inner_range.end() < scope_range.end());
+    assert!(scope_range.end() < outer_range.start());
+  });
+  assert_eq!(output, ("Inner", "Outer"));
+
+  // Scopes do not affect incrementality.
+  pie.require_then_assert_no_execute(&task);
+}
+
+
+// Invalidate tests.
+
+#[test]
+fn invalidate() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  let read = ReadFile::new(&file);
+  let task = Require::new(read.clone());
+  assert_eq!(pie.require(&task)?, "HELLO WORLD!");
+
+  // Dependencies are consistent, but `read` is invalidated: only execute `read`, as its output did not change.
+  let mut session = pie.new_session();
+  session.invalidate(&read);
+  assert_eq!(session.require(&task)?, "HELLO WORLD!");
+  drop(session);
+  assert!(pie.tracker().0.one_execute_of(&read));
+  assert!(!pie.tracker().0.any_execute_of(&task));
+
+  // Invalidation only affects the next require: without invalidation, nothing is executed.
+  pie.require_then_assert_no_execute(&task)?;
+
+  // Invalidate `task` in a session where it is already consistent: execute it again, but not `read`.
+  let mut session = pie.new_session();
+  assert_eq!(session.require(&task)?, "HELLO WORLD!");
+  session.invalidate(&task);
+  assert_eq!(session.require(&task)?, "HELLO WORLD!");
+  drop(session);
+  assert!(pie.tracker().0.one_execute_of(&task));
+  assert!(!pie.tracker().0.any_execute_of(&read));
+
+  // Invalidating a task that was never required does nothing.
+  pie.new_session().invalidate(&ReadFile::new(temp_dir.path().join("other.txt")));
+  pie.require_then_assert_no_execute(&task)?;
+
+  Ok(())
+}
+
+#[test]
+fn invalidate_matching() {
+  let mut pie = new_test_pie();
+  let stale_1 = Constant("stale 1");
+  let stale_2 = Constant("stale 2");
+  let fresh = Constant("fresh");
+  let task = Require::new(stale_1);
+  pie.require(&task);
+  pie.require(&stale_2);
+  pie.require(&fresh);
+
+  // Only invalidate tasks whose debug representation contains "stale", which includes `task` as it contains `stale_1`.
+  let count = pie.run_in_session(|mut session| session.invalidate_matching(|t| format!("{:?}", t).contains("stale")));
+  assert_eq!(count, 3);
+  pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(tracker.one_execute_of(&stale_1));
+  });
+  pie.require_then_assert_one_execute(&stale_2);
+  pie.require_then_assert_no_execute(&fresh);
+
+  // Invalidated tasks are consistent again.
+  pie.require_then_assert_no_execute(&task);
+  pie.require_then_assert_no_execute(&stale_2);
+}
+
+
+// Require graph tests.
+
+#[test]
+fn require_graph() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file_a = temp_dir.path().join("a.txt");
+  write(&file_a, "A")?;
+  let file_b = temp_dir.path().join("b.txt");
+  write(&file_b, "B")?;
+
+  let read_a = ReadFile::new(&file_a);
+  let read_b = ReadFile::new(&file_b);
+  let mut graph = TaskGraph::new();
+  let id_a = graph.add_task(read_a.clone(), EqualsChecker);
+  let id_b = graph.add_task(read_b.clone(), EqualsChecker);
+  assert!(graph.add_dependency(id_a, id_b));
+  assert!(!graph.add_dependency(id_b, id_a)); // Would create a cycle.
+  assert_eq!(graph.len(), 2);
+  assert_eq!(graph.get_task(id_a), Some(&read_a as &dyn KeyObj));
+
+  let get_output = |outputs: &HashMap<TaskId, Box<dyn ValueObj>>, id| {
+    outputs[&id].as_any().downcast_ref::<Result<String, FsError>>().cloned()
+  };
+
+  // Tasks are required in topological order of the graph: `read_b` before `read_a`, even though it was added later.
+  let outputs = pie.assert_in_session(|s| s.require_graph(&graph), |tracker| {
+    assert!(tracker.executed_in_order(&[&read_b, &read_a]));
+  });
+  assert_eq!(outputs.len(), 2);
+  assert_eq!(get_output(&outputs, id_a), Some(Ok("A".to_string())));
+  assert_eq!(get_output(&outputs, id_b), Some(Ok("B".to_string())));
+
+  // Tasks are still checked incrementally.
+  pie.assert_in_session(|s| s.require_graph(&graph), |tracker| {
+    assert!(!tracker.any_execute());
+  });
+  write_until_modified(&file_a, "AA")?;
+  let outputs = pie.assert_in_session(|s| s.require_graph(&graph), |tracker| {
+    assert!(tracker.one_execute_of(&read_a));
+    assert!(!tracker.any_execute_of(&read_b));
+  });
+  assert_eq!(get_output(&outputs, id_a), Some(Ok("AA".to_string())));
+
+  Ok(())
+}
+
+
+// Cache key tests.
+
+/// Task that returns its name in lowercase, with a case-insensitive cache key.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct LowercaseName(String);
+impl Task for LowercaseName {
+  type Output = String;
+  fn execute<C: Context>(&self, _context: &mut C) -> Self::Output {
+    self.0.to_lowercase()
+  }
+  fn cache_key(&self) -> Box<dyn KeyObj> {
+    Box::new(LowercaseName(self.0.to_lowercase()))
+  }
+}
+
+#[test]
+fn cache_key() {
+  let mut pie = new_test_pie();
+  let task_a = LowercaseName("Hello".to_string());
+  let task_b = LowercaseName("HELLO".to_string());
+  assert_ne!(task_a, task_b);
+
+  // Tasks with equal cache keys share one execution, and one entry in the store.
+  assert_eq!(pie.require_then_assert_one_execute(&task_a), "hello");
+  assert_eq!(pie.require_then_assert_no_execute(&task_b), "hello");
+  pie.assert_in_session(|s| (s.require(&task_a), s.require(&task_b)), |tracker| {
+    assert!(!tracker.any_execute());
+  });
+  assert_eq!(pie.tasks(), vec![&task_a as &dyn KeyObj]);
+
+  // Tasks with different cache keys do not share an entry.
+  assert_eq!(pie.require_then_assert_one_execute(&LowercaseName("World".to_string())), "world");
+}
+
+#[test]
+fn cache_key_wrapped() {
+  let mut pie = new_test_pie();
+  let task_a = Box::new(LowercaseName("Hello".to_string()));
+  let task_b = Box::new(LowercaseName("HELLO".to_string()));
+
+  // Wrapped tasks with equal cache keys of their inner task share one execution.
+  assert_eq!(pie.require_then_assert_one_execute(&task_a), "hello");
+  assert_eq!(pie.require_then_assert_no_execute(&task_b), "hello");
+  assert_eq!(pie.require_then_assert_one_execute(&Arc::new(LowercaseName("hello".to_string()))), "hello");
+  assert_eq!(pie.require_then_assert_no_execute(&Arc::new(LowercaseName("hElLo".to_string()))), "hello");
+
+  // Tasks with different wrappers do not share an entry.
+  assert_eq!(pie.require_then_assert_one_execute(&Rc::new(LowercaseName("hello".to_string()))), "hello");
+  assert_eq!(pie.require_then_assert_one_execute(&LowercaseName("hello".to_string())), "hello");
+  assert_eq!(pie.tasks().len(), 4);
+}
+
+
+// Require stream tests.
+
+#[test]
+fn require_stream() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let tasks: Vec<_> = ["a", "b", "c"].into_iter().map(|name| {
+    let path = temp_dir.path().join(format!("{}.txt", name));
+    write(&path, name.to_uppercase())?;
+    Ok(ReadFile::new(path))
+  }).collect::<Result<_, io::Error>>()?;
+
+  // All outputs arrive with the index of their task.
+  let outputs: Vec<_> = pie.new_session().require_stream(&tasks)
+    .map(|(index, output)| output.map(|output| (index, output)))
+    .collect::<Result<_, _>>()?;
+  assert_eq!(outputs, vec![(0, "A".to_string()), (1, "B".to_string()), (2, "C".to_string())]);
+
+  // Outputs arrive as each task is made consistent: tasks after the consumed ones are not required yet.
+  write_until_modified(&tasks[0].0, "AA")?;
+  write_until_modified(&tasks[2].0, "CC")?;
+  pie.assert_in_session(|session| {
+    let mut stream = session.require_stream(&tasks);
+    assert_matches!(stream.next(), Some((0, Ok(output))) if output == "AA");
+  }, |tracker| {
+    assert!(tracker.one_execute_of(&tasks[0]));
+    assert!(!tracker.any_execute_of(&tasks[2]));
+  });
+  pie.assert_in_session(|session| {
+    assert_eq!(session.require_stream(&tasks).count(), 3);
+  }, |tracker| {
+    assert!(!tracker.any_execute_of(&tasks[0]));
+    assert!(tracker.one_execute_of(&tasks[2]));
+  });
+
+  Ok(())
+}
+
+
+// Require if changed tests.
+
+#[test]
+fn require_if_changed() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("in.txt");
+  write(&path, "hello")?;
+  let task = ReadFile::new(&path);
+  let previous = pie.require(&task);
+  assert_eq!(previous.as_deref(), Ok("hello"));
+
+  // File unchanged: output unchanged.
+  let (output, changed) = pie.new_session().require_if_changed(&task, &previous);
+  assert_eq!(output, previous);
+  assert!(!changed);
+
+  // File modified but contents the same: task executes, but output unchanged.
+  write_until_modified(&path, "hello")?;
+  let (output, changed) = pie.assert_in_session(
+    |session| session.require_if_changed(&task, &previous),
+    |tracker| assert!(tracker.one_execute_of(&task)),
+  );
+  assert_eq!(output, previous);
+  assert!(!changed);
+
+  // File contents changed: output changed.
+  write_until_modified(&path, "world")?;
+  let (output, changed) = pie.new_session().require_if_changed(&task, &previous);
+  assert_eq!(output.as_deref(), Ok("world"));
+  assert!(changed);
+
+  Ok(())
+}
+
+
+// Require with budget tests.
+
+/// Task that reads file `self.1` if `self.0` is `0`, or otherwise requires `FileChain(self.0 - 1, self.1)` and appends
+/// a `+` to its output.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct FileChain(usize, PathBuf);
+impl Task for FileChain {
+  type Output = String;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    if self.0 == 0 {
+      let mut string = String::new();
+      context.read(&self.1, ModifiedChecker).unwrap().as_file().unwrap().read_to_string(&mut string).unwrap();
+      string
+    } else {
+      context.require(&FileChain(self.0 - 1, self.1.clone()), EqualsChecker) + "+"
+    }
+  }
+}
+
+#[test]
+fn require_with_budget() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "0")?;
+  let chain: Vec<_> = (0..4).map(|i| FileChain(i, file.clone())).collect();
+  let task = &chain[3];
+  assert_eq!(pie.new_session().require_with_budget(task, 4), RequireOutcome::Complete("0+++".to_string()));
+
+  // Consistent: complete even without budget.
+  assert_eq!(pie.new_session().require_with_budget(task, 0), RequireOutcome::Complete("0+++".to_string()));
+
+  // Changing the file requires executing the whole chain, which exceeds the budget: one execution per session.
+  write_until_modified(&file, "1")?;
+  for executed in &chain[0..3] {
+    let mut session = pie.new_session();
+    assert_eq!(session.require_with_budget(task, 1), RequireOutcome::Partial { budget_exhausted: true });
+    drop(session);
+    assert!(pie.tracker().0.one_execute_of(executed));
+    assert_eq!(pie.tracker().0.iter().filter(|e| matches!(e, Event::ExecuteStart(_))).count(), 1);
+  }
+  let mut session = pie.new_session();
+  assert_eq!(session.require_with_budget(task, 1), RequireOutcome::Complete("1+++".to_string()));
+  drop(session);
+  assert!(pie.tracker().0.one_execute_of(task));
+
+  // Deferred work is also picked up by a regular require.
+  write_until_modified(&file, "2")?;
+  assert_eq!(pie.new_session().require_with_budget(task, 2), RequireOutcome::Partial { budget_exhausted: true });
+  let output = pie.require_then_assert(task, |tracker| {
+    assert!(!tracker.any_execute_of(&chain[0]));
+    assert!(!tracker.any_execute_of(&chain[1]));
+    assert!(tracker.one_execute_of(&chain[2]));
+    assert!(tracker.one_execute_of(&chain[3]));
+  });
+  assert_eq!(output, "2+++");
+
+  Ok(())
+}
+
+
+// Cache hit/miss tracking tests.
+
+/// Tracker that counts cache hits and misses.
+#[derive(Default)]
+struct CacheTracker {
+  hits: usize,
+  misses: usize,
+}
+impl Tracker for CacheTracker {
+  fn require_cache_hit(&mut self, _task: &dyn TaskKeyObj) { self.hits += 1; }
+  fn require_cache_miss(&mut self, _task: &dyn TaskKeyObj) { self.misses += 1; }
+}
+
+#[test]
+fn require_cache_hit_and_miss() {
+  let mut pie = Pie::with_tracker(CacheTracker::default());
+  let task = Constant("Hello, World!");
+
+  // New task: cache miss, since it has no cached output.
+  pie.new_session().require(&task);
+  assert_eq!(pie.tracker().hits, 0);
+  assert_eq!(pie.tracker().misses, 1);
+
+  // Unchanged task: exactly one cache hit and no additional cache misses.
+  pie.new_session().require(&task);
+  assert_eq!(pie.tracker().hits, 1);
+  assert_eq!(pie.tracker().misses, 1);
+}
+
+
+// Chrome trace tracker tests.
+
+#[test]
+fn chrome_trace_tracker() -> TestResult {
+  let mut pie = Pie::with_tracker(ChromeTraceTracker::new(Vec::new()).with_check_events(true));
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  let read = ReadFile::new(&file);
+  let task = ToLower::new(read.clone());
+  pie.new_session().require(&task)?;
+
+  let trace = std::mem::take(pie.tracker_mut().writer_mut());
+  let events: Vec<serde_json::Value> = serde_json::from_slice(&trace)?;
+  // Executed tasks produce matching begin and end events, with the inner task nested in the outer one.
+  let executes: Vec<_> = events.iter()
+    .filter(|e| e["cat"] == "execute")
+    .map(|e| (e["ph"].as_str().unwrap(), e["name"].as_str().unwrap()))
+    .collect();
+  let (task_name, read_name) = (format!("{:?}", task), format!("{:?}", read));
+  assert_eq!(executes, vec![
+    ("B", task_name.as_str()),
+    ("B", read_name.as_str()),
+    ("E", read_name.as_str()),
+    ("E", task_name.as_str()),
+  ]);
+  assert!(events.windows(2).all(|w| w[0]["ts"].as_u64() <= w[1]["ts"].as_u64()));
+
+  // Changing the file checks the tasks and the file, and executes both tasks again.
+  write_until_modified(&file, "HELLO!")?;
+  pie.new_session().require(&task)?;
+  let trace = std::mem::take(pie.tracker_mut().writer_mut());
+  let events: Vec<serde_json::Value> = serde_json::from_slice(&trace)?;
+  for category in ["check", "execute"] {
+    let begins = events.iter().filter(|e| e["cat"] == category && e["ph"] == "B").count();
+    let ends = events.iter().filter(|e| e["cat"] == category && e["ph"] == "E").count();
+    assert_ne!(begins, 0);
+    assert_eq!(begins, ends);
+  }
+
+  Ok(())
+}
+
+// Check error tracker tests.
+
+#[test]
+fn check_error_tracker() -> TestResult {
+  let mut pie = Pie::with_tracker(CheckErrorTracker::default());
+  let temp_dir = create_temp_dir()?;
+  let dir = temp_dir.path().join("dir");
+  create_dir(&dir)?;
+  let file = dir.join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  let task = ToLower::new(ReadFile::new(&file));
+  pie.new_session().require(&task)?;
+  assert!(pie.tracker().errors().is_empty());
+
+  // Replace the directory with a file: getting the metadata of `file` fails because its parent is not a directory.
+  remove_dir_all(&dir)?;
+  write(&dir, "NOT A DIRECTORY")?;
+  let mut session = pie.new_session();
+  assert!(session.require(&task).is_err());
+  assert_eq!(session.dependency_check_errors().len(), 1);
+  drop(session);
+
+  let errors = pie.tracker().errors();
+  assert_eq!(errors.len(), 1);
+  let (key, error) = &errors[0];
+  assert_eq!(key.as_any().downcast_ref::<PathBuf>(), Some(&file));
+  assert!(!error.is_empty());
+
+  Ok(())
+}
+
+// Double execution guard tracker tests.
+
+/// Task that really executes more than once in the same build: `Top` requires `Count` as a fixpoint starting from `0`,
+/// and `Count` outputs `min(Count + 1, 3)` by requiring itself, which is iterated until it stabilizes at `3`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum SelfFixpoint {
+  Top,
+  Count,
+}
+impl Task for SelfFixpoint {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    match self {
+      Self::Top => context.require_fixpoint(&Self::Count, EqualsChecker, 0, 10),
+      Self::Count => (context.require(&Self::Count, EqualsChecker) + 1).min(3),
+    }
+  }
+}
+
+#[test]
+fn double_execution_guard_tracker() {
+  let double_executions = Rc::new(RefCell::new(Vec::<Box<dyn KeyObj>>::new()));
+  let tracker = {
+    let double_executions = double_executions.clone();
+    DoubleExecutionGuardTracker::new(move |task: &dyn TaskKeyObj| {
+      double_executions.borrow_mut().push(task.as_key_obj().to_owned())
+    })
+  };
+  let mut pie = Pie::with_tracker(tracker);
+  let task = Require::new(Constant("Hello"));
+
+  // Executing a task again in a later build is fine.
+  pie.run_in_session(|mut session| {
+    session.require(&task);
+    session.invalidate(&task);
+    session.require(&task);
+  });
+  assert!(double_executions.borrow().is_empty());
+
+  // Iterating to a fixpoint executes `Count` 4 times in the same build: it is 1, 2, 3, and then 3 again.
+  assert_eq!(pie.new_session().require(&SelfFixpoint::Top), 3);
+  let double_executions = double_executions.borrow();
+  assert_eq!(double_executions.len(), 3);
+  assert!(double_executions.iter().all(|t| t.as_ref() == &SelfFixpoint::Count as &dyn KeyObj));
+}
+
+// Sampling tracker tests.
+
+/// Task that requires `Constant(i)` for all `i` in `0..self.0`, returning their sum.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct SumConstants(u32);
+impl Task for SumConstants {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    (0..self.0).map(|i| context.require(&Constant(i), EqualsChecker)).sum()
+  }
+}
+
+#[test]
+fn sampling_tracker() {
+  let mut pie = Pie::with_tracker(SamplingTracker::new(EventTracker::default(), 10));
+  assert_eq!(pie.tracker().rate(), 10);
+
+  // 100 `Constant` tasks and 1 `SumConstants` task are executed: 11 of those 101 executions reach the inner tracker.
+  assert_eq!(pie.new_session().require(&SumConstants(100)), 4950);
+  let tracker = pie.tracker().inner();
+  assert_eq!(tracker.iter().filter(|e| matches!(e, Event::ExecuteStart(_))).count(), 11);
+  assert_eq!(tracker.iter().filter(|e| matches!(e, Event::ExecuteEnd(_))).count(), 11);
+  assert_eq!(tracker.iter().filter(|e| matches!(e, Event::RequireEnd(_))).count(), 11);
+  // Sampled start and end events are paired, even when nested: the first executed task is `SumConstants`.
+  assert!(tracker.one_execute_of(&SumConstants(100)));
+  // Build boundaries always reach the inner tracker.
+  assert_matches!(tracker.slice().first(), Some(Event::BuildStart));
+  assert_matches!(tracker.slice().last(), Some(Event::BuildEnd));
+  assert_eq!(tracker.iter().filter(|e| e.is_build_start()).count(), 1);
+
+  // Build boundaries of later builds also reach the inner tracker.
+  pie.new_session().require(&Constant(0));
+  let tracker = pie.tracker().inner();
+  assert_matches!(tracker.slice().first(), Some(Event::BuildStart));
+  assert_matches!(tracker.slice().last(), Some(Event::BuildEnd));
+  assert!(!tracker.any_execute());
+}
+
+/// Returns whether all start events of `tracker` have a corresponding end event.
+fn starts_and_ends_paired(tracker: &EventTracker) -> bool {
+  let count = |predicate: fn(&Event) -> bool| tracker.iter().filter(|e| predicate(e)).count();
+  count(|e| matches!(e, Event::RequireStart(_))) == count(|e| matches!(e, Event::RequireEnd(_))) &&
+    count(|e| matches!(e, Event::ExecuteStart(_))) == count(|e| matches!(e, Event::ExecuteEnd(_)))
+}
+
+#[test]
+fn sampling_tracker_after_unwind() -> TestResult {
+  let mut pie = Pie::with_tracker(SamplingTracker::new(EventTracker::default(), 2));
+  let temp_dir = create_temp_dir()?;
+
+  // Require is unwound by a violation, leaving start events without end events.
+  let task = Require::new(ListExistingDirectory(temp_dir.path().join("dir")));
+  let result = pie.new_session().try_require(&task);
+  assert_matches!(result, Err(BuildViolation::PreconditionFailed { .. }));
+
+  // Start and end events of the next build are paired.
+  assert_eq!(pie.new_session().require(&SumConstants(100)), 4950);
+  let tracker = pie.tracker().inner();
+  assert!(starts_and_ends_paired(tracker));
+  assert!(tracker.any(|e| matches!(e, Event::ExecuteEnd(_))));
+
+  Ok(())
+}
+
+#[cfg(feature = "panic_fallback")]
+#[test]
+fn sampling_tracker_after_caught_panic() {
+  let mut pie = Pie::with_tracker(SamplingTracker::new(EventTracker::default(), 2));
+
+  // The panic of `Panics` is caught while it is being executed, so its execute start event has no end event. The
+  // start events of the requiring tasks are still paired with their own end events.
+  let task = RequireWithFallback(Require::new(Panics("boom")));
+  assert_eq!(pie.new_session().require(&task), "fallback");
+  let tracker = pie.tracker().inner();
+  assert!(tracker.first_require(&task).is_some());
+  assert!(tracker.first_execute(&task).is_some());
+  assert!(tracker.iter().filter_map(|e| e.match_require_end(&Require::new(Panics("boom")))).next().is_none());
+}
+
+
+// Writing tracker tests.
+
+#[test]
+fn writing_tracker_color() -> TestResult {
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  let task = ToLower::new(ReadFile::new(&file));
+
+  let mut pie = Pie::with_tracker(WritingTracker::new(Vec::new()));
+  pie.new_session().require(&task)?;
+  pie.new_session().require(&task)?;
+  let output = pie.tracker().writer();
+  assert!(!output.is_empty());
+  assert!(!output.contains(&0x1b), "output contains escape sequences with color disabled");
+
+  let mut pie = Pie::with_tracker(WritingTracker::new(Vec::new()).with_color(true));
+  pie.new_session().require(&task)?;
+  pie.new_session().require(&task)?;
+  let output = String::from_utf8(pie.tracker().writer().clone())?;
+  assert!(output.contains("\x1b[32m▶\x1b[0m"), "execute prefix is not colored green");
+  assert!(output.contains("\x1b[2m✓\x1b[0m"), "consistent check prefix is not dimmed");
+
+  Ok(())
+}
+
+/// Task with a large field that describes itself with a short label.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Described(Vec<u8>);
+impl Task for Described {
+  type Output = usize;
+  fn execute<C: Context>(&self, _context: &mut C) -> Self::Output {
+    self.0.len()
+  }
+  fn describe(&self) -> Cow<'_, str> {
+    Cow::Owned(format!("Described({} bytes)", self.0.len()))
+  }
+}
+
+#[test]
+fn writing_tracker_describe() -> TestResult {
+  let task = Described(vec![42; 1024]);
+  let mut pie = Pie::with_tracker(WritingTracker::new(Vec::new()));
+  assert_eq!(pie.new_session().require(&task), 1024);
+
+  let output = String::from_utf8(pie.tracker().writer().clone())?;
+  assert!(output.contains("▶ Described(1024 bytes)"), "execute does not use the task description");
+  assert!(output.contains("→ Described(1024 bytes)"), "require does not use the task description");
+  assert!(!output.contains("42, 42"), "output contains the debug representation of the task");
+  assert_eq!((&task as &dyn TaskKeyObj).describe_obj(), "Described(1024 bytes)");
+
+  // Wrapped tasks use the description of their inner task.
+  let wrapped = Rc::new(Described(vec![42; 16]));
+  assert_eq!(pie.new_session().require(&wrapped), 16);
+  let output = String::from_utf8(pie.tracker().writer().clone())?;
+  assert!(output.contains("▶ Described(16 bytes)"), "execute does not use the description of the wrapped task");
+  assert_eq!((&Box::new(task) as &dyn TaskKeyObj).describe_obj(), "Described(1024 bytes)");
+
+  Ok(())
+}
+
+
+// Resource retry tests.
+
+/// Resource that fails to be read or written with error `self.1` until it has been attempted `self.0` times. Attempts
+/// are counted in the resource state.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Flaky(usize, io::ErrorKind);
+impl Flaky {
+  fn attempt<RS: ResourceState<Self>>(&self, state: &mut RS) -> Result<usize, FsError> {
+    let attempts = state.get_or_set_default_mut::<usize>();
+    *attempts += 1;
+    if *attempts < self.0 {
+      Err(self.1.into())
+    } else {
+      Ok(*attempts)
+    }
+  }
+}
+impl Resource for Flaky {
+  type Reader<'rs> = usize;
+  type Writer<'r> = usize;
+  type Error = FsError;
+  fn read<RS: ResourceState<Self>>(&self, state: &mut RS) -> Result<usize, FsError> {
+    self.attempt(state)
+  }
+  fn write<'r, RS: ResourceState<Self>>(&'r self, state: &'r mut RS) -> Result<usize, FsError> {
+    self.attempt(state)
+  }
+  fn read_retrying<RS: ResourceState<Self>, W>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(FsError) -> W,
+    retry: impl FnMut(&W) -> bool,
+  ) -> Result<usize, W> {
+    resource::retry(|| self.attempt(&mut *state), wrap, retry)
+  }
+  fn write_retrying<RS: ResourceState<Self>, W>(
+    &self,
+    state: &mut RS,
+    wrap: impl FnMut(FsError) -> W,
+    retry: impl FnMut(&W) -> bool,
+  ) -> Result<usize, W> {
+    resource::retry(|| self.attempt(&mut *state), wrap, retry)
+  }
+}
+
+/// Checker for [`Flaky`] that considers it to be always consistent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct FlakyChecker;
+impl ResourceChecker<Flaky> for FlakyChecker {
+  type Stamp = ();
+  type Error = FsError;
+  fn stamp<RS: ResourceState<Flaky>>(&self, _resource: &Flaky, _state: &mut RS) -> Result<(), FsError> { Ok(()) }
+  fn stamp_reader(&self, _resource: &Flaky, _reader: &mut usize) -> Result<(), FsError> { Ok(()) }
+  fn stamp_writer(&self, _resource: &Flaky, _writer: usize) -> Result<(), FsError> { Ok(()) }
+  fn check<RS: ResourceState<Flaky>>(
+    &self,
+    _resource: &Flaky,
+    _state: &mut RS,
+    _stamp: &(),
+  ) -> Result<Option<impl Debug>, FsError> {
+    Ok(None::<()>)
+  }
+  fn wrap_error(&self, error: FsError) -> FsError { error }
+  fn classify_error(&self, error: &FsError) -> ErrorKind { error.classify() }
+}
+
+/// Task that reads a [`Flaky`] resource, returning the number of attempts it took.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadFlaky(Flaky);
+impl Task for ReadFlaky {
+  type Output = Result<usize, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.read(&self.0, FlakyChecker)
+  }
+}
+
+#[test]
+fn resource_retry() {
+  let policy = RetryPolicy { max_attempts: 3, backoff: Duration::from_millis(1) };
+
+  // Read fails on the first attempt without retrying.
+  let mut pie = new_test_pie();
+  let output = pie.new_session().require(&ReadFlaky(Flaky(3, io::ErrorKind::Interrupted)));
+  assert_eq!(output, Err(io::ErrorKind::Interrupted.into()));
+
+  // Read fails twice, then succeeds on the third attempt.
+  let mut pie = new_test_pie();
+  let output = pie.new_session().with_resource_retry(policy).require(&ReadFlaky(Flaky(3, io::ErrorKind::Interrupted)));
+  assert_eq!(output, Ok(3));
+
+  // Read fails after exhausting all attempts.
+  let mut pie = new_test_pie();
+  let output = pie.new_session().with_resource_retry(policy).require(&ReadFlaky(Flaky(4, io::ErrorKind::Interrupted)));
+  assert_eq!(output, Err(io::ErrorKind::Interrupted.into()));
+
+  // Read fails on the first attempt without retrying, because the error is permanent.
+  let mut pie = new_test_pie();
+  let output = pie.new_session().with_resource_retry(policy).require(&ReadFlaky(Flaky(2, io::ErrorKind::NotFound)));
+  assert_eq!(output, Err(io::ErrorKind::NotFound.into()));
+}
+
+/// Task that writes to a [`Flaky`] resource, returning the number of attempts it took.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct WriteFlaky(Flaky);
+impl Task for WriteFlaky {
+  type Output = Result<usize, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let mut attempts = 0;
+    context.write(&self.0, FlakyChecker, |writer| {
+      attempts = *writer;
+      Ok(())
+    })?;
+    Ok(attempts)
+  }
+}
+
+#[test]
+fn resource_retry_write() {
+  let policy = RetryPolicy { max_attempts: 3, backoff: Duration::from_millis(1) };
+
+  // Write fails twice, then succeeds on the third attempt.
+  let mut pie = new_test_pie();
+  let output = pie.new_session().with_resource_retry(policy).require(&WriteFlaky(Flaky(3, io::ErrorKind::Interrupted)));
+  assert_eq!(output, Ok(3));
+
+  // Write fails after exhausting all attempts.
+  let mut pie = new_test_pie();
+  let output = pie.new_session().with_resource_retry(policy).require(&WriteFlaky(Flaky(4, io::ErrorKind::Interrupted)));
+  assert_eq!(output, Err(io::ErrorKind::Interrupted.into()));
+}
+
+
+// Closure resource tests.
+
+/// In-memory store shared between the test and the closures that open [`Memory`] resources.
+type MemoryStore = Rc<RefCell<HashMap<&'static str, String>>>;
+/// Closure resource reading a copy of the value in a [`MemoryStore`], and writing to the [`MemoryStore`] directly.
+type Memory = ClosureResource<&'static str, Option<String>, MemoryStore, Infallible>;
+
+/// Checker for [`Memory`] that compares values.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct MemoryChecker;
+impl ResourceChecker<Memory> for MemoryChecker {
+  type Stamp = Option<String>;
+  type Error = Infallible;
+  fn stamp<RS: ResourceState<Memory>>(&self, resource: &Memory, state: &mut RS) -> Result<Self::Stamp, Infallible> {
+    resource.read(state)
+  }
+  fn stamp_reader(&self, _resource: &Memory, reader: &mut Option<String>) -> Result<Self::Stamp, Infallible> {
+    Ok(reader.clone())
+  }
+  fn stamp_writer(&self, resource: &Memory, writer: MemoryStore) -> Result<Self::Stamp, Infallible> {
+    Ok(writer.borrow().get(resource.key()).cloned())
+  }
+  fn check<RS: ResourceState<Memory>>(
+    &self,
+    resource: &Memory,
+    state: &mut RS,
+    stamp: &Self::Stamp,
+  ) -> Result<Option<impl Debug>, Infallible> {
+    let value = resource.read(state)?;
+    Ok(if value != *stamp { Some(value) } else { None })
+  }
+  fn wrap_error(&self, error: Infallible) -> Infallible { error }
+}
+
+/// Task that reads a [`Memory`] resource, returning its value in uppercase.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct UpperMemory(Memory);
+impl Task for UpperMemory {
+  type Output = Option<String>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let value = context.read(&self.0, MemoryChecker).unwrap_or_else(|e| match e {});
+    value.map(|v| v.to_uppercase())
+  }
+}
+
+#[test]
+fn closure_resource() {
+  let mut pie = new_test_pie();
+  let store = MemoryStore::default();
+  store.borrow_mut().insert("greeting", "Hello, World!".to_string());
+  {
+    let read_store = store.clone();
+    let write_store = store.clone();
+    Memory::register(
+      pie.resource_state_mut::<Memory>(),
+      move |key| Ok(read_store.borrow().get(key).cloned()),
+      move |_| Ok(write_store.clone()),
+    );
+  }
+  let task = UpperMemory(Memory::new("greeting"));
+
+  // New task: execute.
+  let output = pie.require_then_assert_one_execute(&task);
+  assert_eq!(output.as_deref(), Some("HELLO, WORLD!"));
+  // Value unchanged: no execute.
+  pie.require_then_assert_no_execute(&task);
+  // Value changed: execute.
+  store.borrow_mut().insert("greeting", "Hello, Closure!".to_string());
+  let output = pie.require_then_assert_one_execute(&task);
+  assert_eq!(output.as_deref(), Some("HELLO, CLOSURE!"));
+}
+
+
+// Resource state entry tests.
+
+#[test]
+fn resource_state_entry() {
+  let mut pie = new_test_pie();
+  let state = pie.resource_state_mut::<PathBuf>();
+
+  // Not modified when no state is set, then inserted.
+  let value = state.entry::<Vec<u32>>().and_modify(|v| v.push(2)).or_insert_with(|| vec![1]);
+  assert_eq!(value, &vec![1]);
+  // Modified when state is set, and not replaced.
+  let value = state.entry::<Vec<u32>>().and_modify(|v| v.push(2)).or_insert_with(|| vec![3]);
+  assert_eq!(value, &vec![1, 2]);
+  assert_eq!(state.get::<Vec<u32>>(), Some(&vec![1, 2]));
+
+  // State of a different type is replaced.
+  let value = state.entry::<String>().and_modify(|s| s.push('!')).or_default();
+  assert_eq!(value, "");
+  assert_eq!(state.get::<Vec<u32>>(), None);
+}
+
+
+// Seeded resource state tests.
+
+/// Mock clock resource, reading the time in seconds from its resource state, which defaults to 0.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct MockClock;
+impl Resource for MockClock {
+  type Reader<'rs> = u64;
+  type Writer<'r> = &'r mut u64;
+  type Error = Infallible;
+  fn read<'rs, RS: ResourceState<Self>>(&self, state: &'rs mut RS) -> Result<Self::Reader<'rs>, Infallible> {
+    Ok(*state.get_or_set_default::<u64>())
+  }
+  fn write<'r, RS: ResourceState<Self>>(&'r self, state: &'r mut RS) -> Result<Self::Writer<'r>, Infallible> {
+    Ok(state.get_or_set_default_mut::<u64>())
+  }
+}
+
+/// Checker for [`MockClock`] that compares times.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct MockClockChecker;
+impl ResourceChecker<MockClock> for MockClockChecker {
+  type Stamp = u64;
+  type Error = Infallible;
+  fn stamp<RS: ResourceState<MockClock>>(&self, clock: &MockClock, state: &mut RS) -> Result<u64, Infallible> {
+    clock.read(state)
+  }
+  fn stamp_reader(&self, _clock: &MockClock, reader: &mut u64) -> Result<u64, Infallible> {
+    Ok(*reader)
+  }
+  fn stamp_writer(&self, _clock: &MockClock, writer: &mut u64) -> Result<u64, Infallible> {
+    Ok(*writer)
+  }
+  fn check<RS: ResourceState<MockClock>>(
+    &self,
+    clock: &MockClock,
+    state: &mut RS,
+    stamp: &u64,
+  ) -> Result<Option<impl Debug>, Infallible> {
+    let time = clock.read(state)?;
+    Ok(if time != *stamp { Some(time) } else { None })
+  }
+  fn wrap_error(&self, error: Infallible) -> Infallible { error }
+}
+
+/// Task that reads the time from the [`MockClock`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadClock;
+impl Task for ReadClock {
+  type Output = u64;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.read(&MockClock, MockClockChecker).unwrap_or_else(|e| match e {})
+  }
+}
+
+#[test]
+fn with_resource_state() {
+  let mut pie = new_test_pie().with_resource_state::<MockClock, _>(42u64);
+  assert_eq!(pie.resource_state::<MockClock>().get::<u64>(), Some(&42));
+
+  // First build observes the seeded time.
+  let output = pie.require_then_assert_one_execute(&ReadClock);
+  assert_eq!(output, 42);
+  pie.require_then_assert_no_execute(&ReadClock);
+
+  // Without seeding, the default time is observed.
+  let mut pie = new_test_pie();
+  assert_eq!(pie.require(&ReadClock), 0);
+}
+
+
+// Changed resources tests.
+
+#[test]
+fn changed_resources() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let changed_file = temp_dir.path().join("changed.txt");
+  write(&changed_file, "Hello, World!")?;
+  let unchanged_file = temp_dir.path().join("unchanged.txt");
+  write(&unchanged_file, "Hello, World!")?;
+  let read_changed = ReadFile::new(&changed_file);
+  let read_unchanged = ReadFile::new(&unchanged_file);
+
+  let require_both = |pie: &mut TestPie| pie.run_in_session(|mut session| {
+    session.require(&read_changed)?;
+    session.require(&read_unchanged)?;
+    Ok::<_, FsError>(session.changed_resources())
+  });
+
+  // New tasks: no resources are checked, thus none are changed.
+  assert!(require_both(&mut pie)?.is_empty());
+
+  // Only `changed_file` is changed.
+  write_until_modified(&changed_file, "Hello, Changes!")?;
+  let changed_resources = require_both(&mut pie)?;
+  assert_eq!(changed_resources.len(), 1);
+  assert!(changed_resources.iter().any(|r| r.as_ref() == &changed_file as &dyn KeyObj));
+  assert!(!changed_resources.iter().any(|r| r.as_ref() == &unchanged_file as &dyn KeyObj));
+
+  Ok(())
+}
+
+
+// Output checker tests.
+
+/// Task that requires a [`ReadFile`] task and parses its contents with function `self.1`. Used to test output checkers
+/// on outputs of different types.
+#[derive(Clone, Debug)]
+struct ParseFile<O>(ReadFile<ModifiedChecker, ()>, fn(&str) -> O);
+impl<O> ParseFile<O> {
+  fn new(file: impl Into<PathBuf>, parse: fn(&str) -> O) -> Self {
+    Self(ReadFile::new(file), parse)
+  }
+}
+impl<O: Value> Task for ParseFile<O> {
+  type Output = O;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    (self.1)(&context.require(&self.0, EqualsChecker).unwrap())
+  }
+}
+// Manual implementations: derives would require `O` to implement these traits as well.
+impl<O> PartialEq for ParseFile<O> {
+  fn eq(&self, other: &Self) -> bool { self.0 == other.0 && std::ptr::fn_addr_eq(self.1, other.1) }
+}
+impl<O> Eq for ParseFile<O> {}
+impl<O> std::hash::Hash for ParseFile<O> {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    self.0.hash(state);
+    (self.1 as usize).hash(state);
+  }
+}
+
+
+// Approximate equality checker tests.
+
+#[test]
+fn approx_equals_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("float.txt");
+  write(&file, "1.0")?;
+  let read = ParseFile::new(&file, |s| s.parse::<f64>().unwrap());
+  let task = Require::with_checker(read.clone(), ApproxEqualsChecker::new(0.01));
+
+  // New tasks: execute both.
+  let output = pie.require(&task);
+  assert_eq!(output, 1.0);
+
+  // Output jitters within epsilon: only execute `read`.
+  write_until_modified(&file, "1.001")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(output, 1.0);
+
+  // Output jitters within epsilon of the first-seen value: only execute `read`.
+  write_until_modified(&file, "0.999")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(output, 1.0);
+
+  // Output changes beyond epsilon: execute both.
+  write_until_modified(&file, "1.1")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, 1.1);
+
+  // Output changes to NaN: execute both.
+  write_until_modified(&file, "NaN")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert!(output.is_nan());
+
+  // Output changes from NaN: execute both.
+  write_until_modified(&file, "1.1")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, 1.1);
+
+  Ok(())
+}
+
+
+// Hash output checker tests.
+
+#[test]
+fn hash_output_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "HELLO WORLD!")?;
+  // Repeat the trimmed contents of the file, creating a large output.
+  let repeat = ParseFile::new(&file, |s| s.trim().repeat(100_000));
+  let task = Require::with_checker(repeat.clone(), HashOutputChecker);
+
+  // New tasks: execute both.
+  assert_eq!(pie.require(&task).len(), 1_200_000);
+
+  // File changed but output unchanged: only execute `repeat`.
+  write_until_modified(&file, "HELLO WORLD!\n")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&repeat));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(output.len(), 1_200_000);
+
+  // Output changed but has the same length: execute both.
+  write_until_modified(&file, "hello world!")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&repeat));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, "hello world!".repeat(100_000));
+
+  // Stamp size is constant regardless of output length.
+  let small_stamp = HashOutputChecker.stamp(&"HELLO WORLD!".to_string());
+  let large_stamp = HashOutputChecker.stamp(&"HELLO WORLD!".repeat(100_000));
+  assert_eq!(size_of_val(&small_stamp), size_of_val(&large_stamp));
+  assert_ne!(small_stamp, large_stamp);
+
+  Ok(())
+}
+
+
+// Serde equality checker tests.
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_eq_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  let lines: Vec<_> = (0..32).map(|i| format!("word{}={}.5", i, i)).collect();
+  write(&file, lines.join("\n"))?;
+  // Parse lines into a map from words to weights. The map is created anew on every execution, so its iteration order
+  // may differ between executions.
+  let read = ParseFile::new(&file, |s| s.lines()
+    .filter_map(|line| line.split_once('='))
+    .map(|(word, weight)| (word.to_string(), weight.parse::<f64>().unwrap()))
+    .collect::<HashMap<_, _>>()
+  );
+  let task = Require::with_checker(read.clone(), SerdeEqChecker);
+  let sum = |weights: HashMap<String, f64>| weights.values().sum::<f64>() as u64;
+  assert_eq!(sum(pie.require(&task)), 512);
+
+  // File modified with lines in a different order, creating a map with the same entries but possibly a different
+  // iteration order: only execute `read`.
+  let reversed: Vec<_> = lines.iter().rev().cloned().collect();
+  write_until_modified(&file, reversed.join("\n"))?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(sum(output), 512);
+
+  // Weight changed: execute both.
+  write_until_modified(&file, reversed.join("\n").replace("word0=0.5", "word0=1.5"))?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(sum(output), 513);
+
+  // Stamps of maps with equal entries are equal, regardless of insertion order.
+  let forward: HashMap<_, _> = (0..32).map(|i| (i.to_string(), i as f64)).collect();
+  let backward: HashMap<_, _> = (0..32).rev().map(|i| (i.to_string(), i as f64)).collect();
+  assert_eq!(SerdeEqChecker.stamp(&forward), SerdeEqChecker.stamp(&backward));
+
+  // Outputs that fail to serialize are always inconsistent.
+  let unserializable: HashMap<_, _> = [((0u8, 0u8), 0.5)].into_iter().collect();
+  let stamp = SerdeEqChecker.stamp(&unserializable);
+  assert_eq!(stamp, None);
+  assert!(SerdeEqChecker.check(&unserializable, &stamp).is_some());
+
+  Ok(())
+}
+
+
+// Debounce checker tests.
+
+/// Clock that only advances when [advanced](TestClock::advance), shared between clones.
+#[derive(Clone, Debug)]
+struct TestClock(Rc<Cell<Instant>>);
+impl TestClock {
+  fn new() -> Self { Self(Rc::new(Cell::new(Instant::now()))) }
+  fn advance(&self, duration: Duration) { self.0.set(self.0.get() + duration); }
+}
+impl Clock for TestClock {
+  fn now(&self) -> Instant { self.0.get() }
+}
+// Manual implementations: compare and hash by identity of the shared time.
+impl PartialEq for TestClock {
+  fn eq(&self, other: &Self) -> bool { Rc::ptr_eq(&self.0, &other.0) }
+}
+impl Eq for TestClock {}
+impl std::hash::Hash for TestClock {
+  fn hash<H: std::hash::Hasher>(&self, state: &mut H) { Rc::as_ptr(&self.0).hash(state); }
+}
+
+#[test]
+fn debounce_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "0")?;
+  let read = ReadFile::new(&file);
+  let clock = TestClock::new();
+  let min_interval = Duration::from_secs(60);
+  let checker = DebounceChecker::with_clock(EqualsChecker, min_interval, clock.clone());
+  let task = Require::with_checker(read.clone(), checker);
+  assert_eq!(pie.require(&task)?, "0");
+
+  // Rapid output changes within the interval: only execute `read`, keeping the stale output.
+  for content in ["1", "2", "3"] {
+    write_until_modified(&file, content)?;
+    clock.advance(Duration::from_secs(10));
+    let output = pie.require_then_assert(&task, |tracker| {
+      assert!(tracker.one_execute_of(&read));
+      assert!(!tracker.any_execute_of(&task));
+    });
+    assert_eq!(output?, "0");
+  }
+
+  // Interval elapsed: the suppressed change is picked up once.
+  clock.advance(min_interval);
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(!tracker.any_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output?, "3");
+
+  // Changes within the new interval are suppressed again.
+  write_until_modified(&file, "4")?;
+  clock.advance(min_interval - Duration::from_secs(1));
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(output?, "3");
+
+  Ok(())
+}
+
+// Contains checker tests.
+
+#[test]
+fn contains_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("words.txt");
+  write(&file, "hello world")?;
+  // Parse the set of whitespace-separated words.
+  let read = ParseFile::new(&file, |s| s.split_whitespace().map(|s| s.to_string()).collect::<HashSet<_>>());
+  let task = Require::with_checker(read.clone(), ContainsChecker::new("hello".to_string()));
+
+  // New tasks: execute both.
+  let output = pie.require(&task);
+  assert!(output.contains("hello"));
+
+  // Other words change, but `hello` is still present: only execute `read`.
+  write_until_modified(&file, "hello there general kenobi")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert!(output.contains("hello"));
+
+  // `hello` is removed: execute both.
+  write_until_modified(&file, "goodbye there")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert!(!output.contains("hello"));
+
+  // Other words change, but `hello` is still absent: only execute `read`.
+  write_until_modified(&file, "goodbye world")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert!(!output.contains("hello"));
+
+  Ok(())
+}
+
+
+// Index checker tests.
+
+#[test]
+fn index_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("lines.txt");
+  write(&file, "hello\nworld")?;
+  // Parse the first and second line.
+  let read = ParseFile::new(&file, |s| {
+    let (first, second) = s.split_once('\n').unwrap_or((s, ""));
+    (first.to_string(), second.to_string())
+  });
+  let task = Require::with_checker(read.clone(), IndexChecker::<0>);
+  assert_eq!(pie.require(&task).0, "hello");
+
+  // Only element 1 changes: only execute `read`.
+  write_until_modified(&file, "hello\nthere")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(output.0, "hello");
+
+  // Element 0 changes: execute both.
+  write_until_modified(&file, "goodbye\nthere")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output.0, "goodbye");
+
+  // Vectors are checked by the element at the index, if any.
+  let stamp = IndexChecker::<1>.stamp(&vec![1, 2]);
+  assert_eq!(stamp, Some(2));
+  assert!(IndexChecker::<1>.check(&vec![3, 2, 1], &stamp).is_none());
+  assert!(IndexChecker::<1>.check(&vec![1], &stamp).is_some());
+
+  Ok(())
+}
+
+
+// Generation checker tests.
+
+#[test]
+fn generation_checker() {
+  let mut pie = new_test_pie();
+  let checker = GenerationChecker::default();
+  let constant = Constant("Hello");
+  let task = Require::with_checker(constant, checker.clone());
+  assert_eq!(pie.require(&task), "Hello");
+
+  // Generation is unchanged: nothing is executed.
+  pie.require_then_assert_no_execute(&task);
+
+  // Generation advances: dependent is executed despite the identical output.
+  assert_eq!(checker.advance(), 1);
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(!tracker.any_execute_of(&constant));
+  });
+  assert_eq!(output, "Hello");
+
+  // Dependent was re-stamped with the new generation: nothing is executed.
+  pie.require_then_assert_no_execute(&task);
+}
+
+
+// Len checker tests.
+
+#[test]
+fn len_checker() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("lines.txt");
+  write(&file, "hello\nworld")?;
+  // Parse all lines.
+  let read = ParseFile::new(&file, |s| s.lines().map(|s| s.to_string()).collect::<Vec<_>>());
+  let task = Require::with_checker(read.clone(), LenChecker);
+  assert_eq!(pie.require(&task).len(), 2);
+
+  // Elements change but length stays the same: only execute `read`.
+  write_until_modified(&file, "goodbye\nthere")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(!tracker.any_execute_of(&task));
+  });
+  assert_eq!(output, ["hello", "world"]);
+
+  // Length changes: execute both.
+  write_until_modified(&file, "goodbye\nthere\nworld")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&read));
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, ["goodbye", "there", "world"]);
+
+  // Strings and maps are checked by their length.
+  let stamp = LenChecker.stamp(&"abc".to_string());
+  assert_eq!(stamp, 3);
+  assert!(LenChecker.check(&"cba".to_string(), &stamp).is_none());
+  assert!(LenChecker.check(&HashMap::from([(1, 2)]), &1).is_none());
+  assert!(LenChecker.check(&HashMap::<i32, i32>::new(), &1).is_some());
+
+  Ok(())
+}
+
+
+// Maximum depth tests.
+
+/// Task that requires a chain of `self.0` tasks below it.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Chain(u32);
+impl Task for Chain {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    if self.0 == 0 {
+      0
+    } else {
+      context.require(&Chain(self.0 - 1), EqualsChecker) + 1
+    }
+  }
+}
+
+#[test]
+fn max_depth_not_exceeded() {
+  let mut pie = new_test_pie();
+  // New tasks: a depth of 5 when executing.
+  assert_eq!(pie.new_session().with_max_depth(5).require(&Chain(4)), 4);
+  // Unchanged tasks: also a depth of 5 when checking.
+  assert_eq!(pie.new_session().with_max_depth(5).require(&Chain(4)), 4);
+}
+
+#[test]
+fn max_depth_exceeded() {
+  let mut pie = new_test_pie();
+  let result = pie.new_session().with_max_depth(5).try_require(&Chain(10));
+  assert_matches!(result, Err(BuildViolation::DepthExceeded { task, max_depth: 5 }) => {
+    assert_eq!(task.as_ref(), &Chain(5) as &dyn KeyObj);
+  });
+}
+
+#[test]
+fn max_depth_exceeded_when_checking() {
+  let mut pie = new_test_pie();
+  pie.require(&Chain(10));
+  let result = pie.new_session().with_max_depth(5).try_require(&Chain(10));
+  assert_matches!(result, Err(BuildViolation::DepthExceeded { task, max_depth: 5 }) => {
+    assert_eq!(task.as_ref(), &Chain(5) as &dyn KeyObj);
+  });
+}
+
+
+// Require interceptor tests.
+
+#[test]
+fn require_interceptor() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let path = temp_dir.path().join("in.txt");
+  let read = ReadFile::new(&path);
+  let task = ToLower::from(&read);
+
+  // Stub the output of `read`: `task` uses the stub, and `read` is not executed, even though the file does not exist.
+  let stubbed = read.clone();
+  let output = pie.new_session()
+    .with_require_interceptor(move |t| {
+      (t == &stubbed as &dyn KeyObj).then(|| Box::new(Ok::<_, FsError>("STUB".to_string())) as Box<dyn ValueObj>)
+    })
+    .require(&task)?;
+  assert_eq!(output.as_str(), "stub");
+  let tracker = &pie.tracker().0;
+  assert!(tracker.one_execute_of(&task));
+  assert!(!tracker.any_execute_of(&read));
+  // A dependency to the stubbed task is still created.
+  let read_key = &read as &dyn KeyObj;
+  assert!(tracker.any(|e| matches!(e, Event::RequireEnd(RequireEnd { task: t, .. }) if t.as_ref() == read_key)));
+
+  // Without interceptor: the dependency is checked against the real output of `read`, and `task` is re-executed.
+  write(&path, "HELLO")?;
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(tracker.one_execute_of(&read));
+  })?;
+  assert_eq!(output.as_str(), "hello");
+
+  Ok(())
+}
+
+
+// Always execute tests.
+
+/// Task that always executes when required, returning `self.0`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct AlwaysExecute(&'static str);
+impl Task for AlwaysExecute {
+  type Output = &'static str;
+  fn execute<C: Context>(&self, _context: &mut C) -> Self::Output {
+    self.0
+  }
+  fn always_execute(&self) -> bool { true }
+}
+
+/// Task that requires both its tasks, returning their outputs.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct RequireBoth(AlwaysExecute, Constant<&'static str>);
+impl Task for RequireBoth {
+  type Output = (&'static str, &'static str);
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    (context.require(&self.0, EqualsChecker), context.require(&self.1, EqualsChecker))
+  }
+}
+
+#[test]
+fn always_execute() {
+  let mut pie = new_test_pie();
+  let always = AlwaysExecute("Hello");
+  let constant = Constant("World");
+  let task = RequireBoth(always.clone(), constant);
+
+  // New tasks: execute all.
+  let output = pie.require_then_assert(&task, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+    assert!(tracker.one_execute_of(&always));
+    assert!(tracker.one_execute_of(&constant));
+  });
+  assert_eq!(output, ("Hello", "World"));
+
+  // Only `always` executes. Its output did not change, so `task` is consistent and does not execute.
+  for _ in 0..2 {
+    let output = pie.require_then_assert(&task, |tracker| {
+      assert!(!tracker.any_execute_of(&task));
+      assert!(tracker.one_execute_of(&always));
+      assert!(!tracker.any_execute_of(&constant));
+    });
+    assert_eq!(output, ("Hello", "World"));
+  }
+
+  // Directly requiring `always` also executes it.
+  pie.require_then_assert_one_execute(&always);
+}
+
+// Read many tests.
+
+/// Task that reads all files in `self.0` using [`Context::read_many`], concatenating their contents.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadMany(Vec<PathBuf>);
+impl Task for ReadMany {
+  type Output = String;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let mut string = String::new();
+    for mut reader in context.read_many(&self.0, ModifiedChecker).unwrap() {
+      reader.as_file().unwrap().read_to_string(&mut string).unwrap();
+    }
+    string
+  }
+}
+
+#[test]
+fn read_many() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let files: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt"].into_iter().map(|f| temp_dir.path().join(f)).collect();
+  for file in &files {
+    write(file, "0")?;
+  }
+  let task = ReadMany(files.clone());
+
+  // New task: execute, creating a separate read dependency to each file.
+  let output = pie.require_then_assert(&task, |tracker| {
+    for file in &files {
+      assert_eq!(tracker.iter().filter(|e| e.match_read_end(file).is_some()).count(), 1);
+    }
+  });
+  assert_eq!(output, "000");
+
+  // Unchanged: do not execute.
+  pie.require_then_assert_no_execute(&task);
+
+  // Changing any one file re-executes the task.
+  for (i, file) in files.iter().enumerate() {
+    write_until_modified(file, "1")?;
+    let output = pie.require_then_assert_one_execute(&task);
+    assert_eq!(output, "1".repeat(i + 1) + &"0".repeat(2 - i));
+  }
+
+  Ok(())
+}
+
+// Read deduplication tests.
+
+/// Task that reads file `self.0` with an [`ExistsChecker`], and then twice with a [`ModifiedChecker`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadRepeatedly(PathBuf);
+impl Task for ReadRepeatedly {
+  type Output = String;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.read(&self.0, ExistsChecker).unwrap();
+    let mut string = String::new();
+    for _ in 0..2 {
+      context.read(&self.0, ModifiedChecker).unwrap().as_file().unwrap().read_to_string(&mut string).unwrap();
+    }
+    string
+  }
+}
+
+/// Tracker that counts resource dependency checks.
+#[derive(Default)]
+struct ResourceCheckCountTracker(usize);
+impl Tracker for ResourceCheckCountTracker {
+  fn check_resource_end(
+    &mut self,
+    _resource: &dyn KeyObj,
+    _checker: &dyn ValueObj,
+    _stamp: &dyn ValueObj,
+    _inconsistency: Result<Option<&Change>, &dyn std::error::Error>,
+  ) {
+    self.0 += 1;
+  }
+}
+
+#[test]
+fn read_deduplication() -> TestResult {
+  let mut pie = Pie::with_tracker(ResourceCheckCountTracker::default());
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("in.txt");
+  write(&file, "0")?;
+  let task = ReadRepeatedly(file.clone());
+  let plan = pie.run_in_session(|mut session| {
+    assert_eq!(session.require(&task), "00");
+    session.export_plan()
+  });
+  assert_eq!(pie.tracker().0, 0);
+  // Only one read dependency exists, merging the dependencies of all reads.
+  assert_matches!(plan.steps[0].dependencies.as_slice(), [PlanDependency { kind: PlanDependencyKind::Read, .. }]);
+
+  // Unchanged: the two reads with a `ModifiedChecker` are deduplicated, so only one check per checker type is done.
+  assert_eq!(pie.new_session().require(&task), "00");
+  assert_eq!(pie.tracker().0, 2);
+
+  // Modified: detected even though the file was first read with an `ExistsChecker`, thus re-executing the task.
+  write_until_modified(&file, "1")?;
+  assert_eq!(pie.new_session().require(&task), "11");
+
+  // The plan contains the stamps of both checkers, so the changed stamp of the `ModifiedChecker` is a divergence, even
+  // though the stamp of the `ExistsChecker` is unchanged.
+  let [dependency] = plan.steps[0].dependencies.as_slice() else { panic!("expected one dependency") };
+  let stamp = &dependency.stamp;
+  assert!(stamp.starts_with("[true, "), "stamp {} does not list the stamps of both checkers", stamp);
+  assert_matches!(pie.verify_plan(&plan).as_slice(), [PlanDivergence::ChangedStamp { .. }]);
+
+  Ok(())
+}
+
+/// Task that writes to the file at `self.0` twice.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct WriteTwice(PathBuf);
+impl Task for WriteTwice {
+  type Output = Result<(), FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.write(&self.0, ModifiedChecker, |file| Ok(file.write_all(b"Hello")?))?;
+    context.write(&self.0, ModifiedChecker, |file| Ok(file.write_all(b"Hello, World!")?))
+  }
+}
+
+#[test]
+fn write_twice_deduplication() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("out.txt");
+  let task = WriteTwice(file.clone());
+
+  // Writing twice is not an overlapping write with itself, and creates one write dependency.
+  let plan = pie.run_in_session(|mut session| {
+    assert_matches!(session.try_require(&task), Ok(Ok(())));
+    session.export_plan()
+  });
+  assert_matches!(plan.steps[0].dependencies.as_slice(), [PlanDependency { kind: PlanDependencyKind::Write, .. }]);
+  assert_eq!(fs::read_to_string(&file)?, "Hello, World!");
+
+  // Unchanged: consistent with the stamp of the last write.
+  pie.require_then_assert_no_execute(&task)?;
+  // Modified: re-executed.
+  write_until_modified(&file, "Hi")?;
+  pie.require_then_assert_one_execute(&task)?;
+  assert_eq!(fs::read_to_string(&file)?, "Hello, World!");
+
+  Ok(())
+}
+
+/// Task that reads the file at `self.0`, and writes it back in uppercase.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ReadThenWrite(PathBuf);
+impl Task for ReadThenWrite {
+  type Output = Result<String, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    let mut string = String::new();
+    if let Some(file) = context.read(&self.0, ModifiedChecker)?.as_file() {
+      file.read_to_string(&mut string)?;
+    }
+    let string = string.to_uppercase();
+    context.write(&self.0, ModifiedChecker, |file| Ok(file.write_all(string.as_bytes())?))?;
+    Ok(string)
+  }
+}
+
+#[test]
+fn read_then_write_deduplication() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in_out.txt");
+  write(&file, "hello")?;
+  let task = ReadThenWrite(file.clone());
+
+  // Reading then writing is not a hidden dependency on itself, and the read is merged into one write dependency.
+  let plan = pie.run_in_session(|mut session| {
+    assert_matches!(session.try_require(&task), Ok(Ok(output)) if output == "HELLO");
+    session.export_plan()
+  });
+  assert_matches!(plan.steps[0].dependencies.as_slice(), [PlanDependency { kind: PlanDependencyKind::Write, .. }]);
 
-  // Require `ToLower` and assert that `ReadFile` and `ToLower` are executed because they are new, but not `ToUpper`,
-  // because it not required by anything. `ToLower` will return `"hello, world!"`.
-  let output = pie.require_then_assert(&lower, |tracker| {
-    assert!(tracker.one_execute_of(&read));
-    assert!(tracker.one_execute_of(&lower));
-    assert!(!tracker.any_execute_of(&upper));
+  // Unchanged: consistent with the stamp of the write.
+  pie.require_then_assert_no_execute(&task)?;
+  // Modified: re-executed.
+  write_until_modified(&file, "world")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, "WORLD");
+
+  Ok(())
+}
+
+
+// Try write tests.
+
+/// Task that writes `.1` to the file at `.0`, returning whether the write changed the file.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct TryWriteFile(PathBuf, &'static str);
+impl Task for TryWriteFile {
+  type Output = Result<WriteOutcome, FsError>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.try_write(&self.0, HashChecker, |file| Ok(file.write_all(self.1.as_bytes())?))
+  }
+}
+
+#[test]
+fn try_write() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let path = temp_dir.path().join("out.txt");
+  let task = TryWriteFile(path.clone(), "Hello");
+
+  // File does not exist yet: changed.
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, WriteOutcome::Changed);
+  // Writing identical content again: unchanged.
+  pie.run_in_session(|mut session| session.invalidate(&task));
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, WriteOutcome::Unchanged);
+  assert_eq!(fs::read_to_string(&path)?, "Hello");
+
+  // The write dependency is still created: changing the file re-executes the task, writing back its content.
+  write_until_modified(&path, "Hello, World!")?;
+  assert_eq!(pie.require_then_assert_one_execute(&task)?, WriteOutcome::Changed);
+  assert_eq!(fs::read_to_string(&path)?, "Hello");
+  pie.require_then_assert_no_execute(&task)?;
+
+  Ok(())
+}
+
+
+// Statistics tests.
+
+#[test]
+fn statistics() {
+  let mut pie = new_test_pie();
+  assert_eq!(pie.statistics(), PieStats::default());
+  let task = Require::new(Constant("Hello"));
+
+  // New tasks: execute both.
+  pie.require(&task);
+  assert_eq!(pie.statistics(), PieStats { session_count: 1, total_executions: 2, total_cache_hits: 0 });
+
+  // Consistent tasks: cache hit for both, accumulating session count and executions.
+  pie.require(&task);
+  assert_eq!(pie.statistics(), PieStats { session_count: 2, total_executions: 2, total_cache_hits: 2 });
+
+  // New task requiring a consistent task: execute one, cache hit for the other.
+  pie.require(&Require::with_checker(Constant("Hello"), AlwaysConsistent));
+  assert_eq!(pie.statistics(), PieStats { session_count: 3, total_executions: 3, total_cache_hits: 3 });
+
+  // Multiple requires in one session: counted as one session when it ends.
+  let mut session = pie.new_session();
+  session.require(&task);
+  session.require(&task);
+  drop(session);
+  assert_eq!(pie.statistics(), PieStats { session_count: 4, total_executions: 3, total_cache_hits: 5 });
+}
+
+
+// Diagnostics tests.
+
+#[test]
+fn diagnostics_order() -> TestResult {
+  let temp_dir = create_temp_dir()?;
+  let files: Vec<_> = ["c.txt", "a.txt", "d.txt", "b.txt"].into_iter().map(|f| temp_dir.path().join(f)).collect();
+  for file in &files {
+    write(file, "Hello")?;
+  }
+
+  // Build the same graph twice, in separate instances and in a different order.
+  let build = |files: &[PathBuf]| -> Result<(Vec<String>, Vec<String>), FsError> {
+    let mut pie = new_test_pie();
+    for file in files {
+      pie.require(&ReadFile::new(file))?;
+    }
+    pie.require(&Require::new(Constant("Hello")));
+    let tasks = pie.tasks().iter().map(|t| format!("{:?}", t)).collect();
+    let resources = pie.resources().iter().map(|r| format!("{:?}", r)).collect();
+    Ok((tasks, resources))
+  };
+  let (tasks, resources) = build(&files)?;
+  let reversed: Vec<_> = files.iter().rev().cloned().collect();
+  assert_eq!(build(&reversed)?, (tasks.clone(), resources.clone()));
+
+  // Order is by debug representation.
+  assert_eq!(tasks.len(), 6);
+  assert!(tasks.is_sorted());
+  assert_eq!(resources.len(), 4);
+  assert!(resources.is_sorted());
+
+  Ok(())
+}
+
+// Build plan tests.
+
+#[test]
+fn export_and_verify_plan() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let path = temp_dir.path().join("in.txt");
+  write(&path, "HELLO")?;
+  let read = ReadFile::new(&path);
+  let task = ToLower::from(&read);
+
+  let plan = pie.run_in_session(|mut session| {
+    assert_eq!(session.require(&task)?.as_str(), "hello");
+    Ok::<_, FsError>(session.export_plan())
   })?;
-  assert_eq!(output.as_str(), "hello, world!");
+  // Tasks are in the order they finished executing, with their dependencies.
+  assert_eq!(plan.steps.len(), 2);
+  assert_eq!(plan.steps[0].task, format!("{:?}", read));
+  assert_matches!(plan.steps[0].dependencies.as_slice(), [PlanDependency { kind: PlanDependencyKind::Read, .. }]);
+  assert_eq!(plan.steps[1].task, format!("{:?}", task));
+  assert_matches!(plan.steps[1].dependencies.as_slice(), [PlanDependency { kind: PlanDependencyKind::Require, .. }]);
+  #[cfg(feature = "serde")] {
+    let deserialized: BuildPlan = serde_json::from_str(&serde_json::to_string(&plan)?)?;
+    assert_eq!(deserialized, plan);
+  }
 
-  // Require `ToUpper` and assert that it is executed because it is new, but not `ReadFile` nor `ToLower` because their
-  // dependencies are consistent.
-  let output = pie.require_then_assert(&upper, |tracker| {
-    assert!(!tracker.any_execute_of(&read));
-    assert!(!tracker.any_execute_of(&lower));
-    assert!(tracker.one_execute_of(&upper));
+  // Unchanged environment: plan is reproduced.
+  assert_eq!(pie.verify_plan(&plan), vec![]);
+  // Consistent tasks are not in the plan of a session.
+  let plan_no_execute = pie.run_in_session(|mut session| {
+    session.require(&task)?;
+    Ok::<_, FsError>(session.export_plan())
   })?;
-  assert_eq!(output.as_str(), "HELLO, WORLD!");
+  assert_eq!(plan_no_execute, BuildPlan::default());
+
+  // Changed input: plan is not reproduced.
+  write_until_modified(&path, "HELLO WORLD")?;
+  let divergences = pie.verify_plan(&plan);
+  assert_matches!(divergences.as_slice(), [PlanDivergence::ChangedStamp { task, dependency, .. }] => {
+    assert_eq!(task, &format!("{:?}", read));
+    assert_eq!(dependency.target, format!("{:?}", path));
+  });
+
+  // Rebuilt: the new dependency graph diverges from the old plan, but reproduces the new plan.
+  let new_plan = pie.run_in_session(|mut session| {
+    session.require(&task)?;
+    Ok::<_, FsError>(session.export_plan())
+  })?;
+  let changed_stamp = |step: usize| PlanDivergence::ChangedStamp {
+    task: plan.steps[step].task.clone(),
+    dependency: plan.steps[step].dependencies[0].clone(),
+    current: new_plan.steps[step].dependencies[0].stamp.clone(),
+  };
+  assert_eq!(pie.verify_plan(&plan), vec![changed_stamp(0), changed_stamp(1)]);
+  assert_eq!(pie.verify_plan(&new_plan), vec![]);
+
+  // Unknown tasks diverge.
+  let other_plan = BuildPlan { steps: vec![PlanStep { task: "Unknown".to_string(), dependencies: vec![] }] };
+  assert_eq!(pie.verify_plan(&other_plan), vec![PlanDivergence::UnknownTask { task: "Unknown".to_string() }]);
+  // Plans can only be verified against the dependency graph they were exported from: all tasks are unknown to a new
+  // instance, even though the environment would reproduce the plan.
+  let unknown_task = |step: usize| PlanDivergence::UnknownTask { task: new_plan.steps[step].task.clone() };
+  assert_eq!(new_test_pie().verify_plan(&new_plan), vec![unknown_task(0), unknown_task(1)]);
 
   Ok(())
 }
@@ -396,6 +2650,58 @@ fn overlapping_write_panics() {
   run().unwrap();
 }
 
+/// Task that writes `self.1` to file `self.0`, creating a write dependency through the dynamic API.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct WriteFileDyn(PathBuf, &'static str);
+impl Task for WriteFileDyn {
+  type Output = Result<(), String>;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    write(&self.0, self.1).map_err(|e| e.to_string())?;
+    context.written_to_dyn(&self.0, resource_checker_obj(ModifiedChecker)).map_err(|e| e.to_string())
+  }
+}
+
+#[test]
+fn written_to_dyn() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("out.txt");
+  let task = WriteFileDyn(file.clone(), "Hello, World!");
+
+  // New task: execute.
+  pie.require_then_assert_one_execute(&task)?;
+  assert_eq!(std::fs::read_to_string(&file)?, "Hello, World!");
+  // Write dependency is consistent: no execute.
+  pie.require_then_assert_no_execute(&task)?;
+  // Written file is modified, making the write dependency inconsistent: execute.
+  write_until_modified(&file, "Hello?")?;
+  pie.require_then_assert_one_execute(&task)?;
+  assert_eq!(std::fs::read_to_string(&file)?, "Hello, World!");
+
+  Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Overlapping write")]
+fn overlapping_write_dyn_panics() {
+  fn run() -> TestResult {
+    let mut pie = new_test_pie();
+    let temp_dir = create_temp_dir()?;
+    let file = temp_dir.path().join("out.txt");
+
+    pie.run_in_session(|mut session| {
+      let task_1 = WriteFile::new(Constant::new_ok("Test 1"), &file);
+      session.require(&task_1)?;
+      let task_2 = WriteFileDyn(file.clone(), "Test 2");
+      session.require(&task_2)?;
+      Ok::<(), Box<dyn std::error::Error>>(())
+    })?;
+
+    Ok(())
+  }
+  run().unwrap();
+}
+
 #[test]
 fn same_task_no_overlap() -> TestResult {
   let mut pie = new_test_pie();
@@ -418,3 +2724,421 @@ fn same_task_no_overlap() -> TestResult {
 
   Ok(())
 }
+
+
+// Build violation tests.
+
+#[test]
+fn try_require_cycle() {
+  let mut pie = new_test_pie();
+  pie.run_in_session(|mut session| {
+    let result = session.try_require(&Cycle::RequireSelf);
+    assert_matches!(result, Err(BuildViolation::CyclicDependency { .. }));
+    let result = session.try_require(&Cycle::RequireA);
+    assert_matches!(result, Err(BuildViolation::CyclicDependency { required_task, .. }) => {
+      assert_eq!(required_task.as_ref(), &Cycle::RequireA as &dyn KeyObj);
+    });
+  });
+  // Interrupted tasks are reset: requiring them again returns the same violation.
+  pie.run_in_session(|mut session| {
+    let result = session.try_require(&Cycle::RequireA);
+    assert_matches!(result, Err(BuildViolation::CyclicDependency { .. }));
+  });
+}
+
+#[test]
+fn try_require_hidden_dependency() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("in_out.txt");
+  write(&file, "test")?;
+  let writing_task = WriteFile::new(Constant::new_ok("Test 1"), &file);
+  let reading_task = ReadFile::new(&file);
+
+  // Hidden dependency during read.
+  pie.run_in_session(|mut session| {
+    session.require(&writing_task)?;
+    let result = session.try_require(&reading_task);
+    assert_matches!(result, Err(BuildViolation::HiddenDependency { reading_task: r, writing_task: w, .. }) => {
+      assert_eq!(r.as_ref(), &reading_task as &dyn KeyObj);
+      assert_eq!(w.as_ref(), &writing_task as &dyn KeyObj);
+    });
+    Ok::<(), FsError>(())
+  })?;
+
+  // Hidden dependency during write.
+  let mut pie = new_test_pie();
+  pie.run_in_session(|mut session| {
+    session.require(&reading_task)?;
+    let result = session.try_require(&writing_task);
+    assert_matches!(result, Err(BuildViolation::HiddenDependency { reading_task: r, writing_task: w, .. }) => {
+      assert_eq!(r.as_ref(), &reading_task as &dyn KeyObj);
+      assert_eq!(w.as_ref(), &writing_task as &dyn KeyObj);
+    });
+    Ok::<(), FsError>(())
+  })?;
+
+  Ok(())
+}
+
+#[test]
+fn try_require_overlapping_write() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let file = temp_dir.path().join("out.txt");
+  let task_1 = WriteFile::new(Constant::new_ok("Test 1"), &file);
+  let task_2 = WriteFile::new(Constant::new_ok("Test 2"), &file);
+
+  pie.run_in_session(|mut session| {
+    session.require(&task_1)?;
+    let result = session.try_require(&task_2);
+    assert_matches!(result, Err(BuildViolation::OverlappingWrite { writing_task, previous_writing_task, .. }) => {
+      assert_eq!(writing_task.as_ref(), &task_2 as &dyn KeyObj);
+      assert_eq!(previous_writing_task.as_ref(), &task_1 as &dyn KeyObj);
+    });
+    // Session can still be used after a violation.
+    let result = session.try_require(&task_1);
+    assert_matches!(result, Ok(Ok(_)));
+    Ok::<(), FsError>(())
+  })?;
+
+  Ok(())
+}
+
+
+// Precondition tests.
+
+/// Task that lists the names of the entries of directory `self.0`, with the precondition that the directory exists.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct ListExistingDirectory(PathBuf);
+impl Task for ListExistingDirectory {
+  type Output = Vec<String>;
+  fn precondition<C: Context>(&self, _context: &mut C) -> Result<(), String> {
+    if self.0.is_dir() {
+      Ok(())
+    } else {
+      Err(format!("directory '{}' does not exist", self.0.display()))
+    }
+  }
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.read(&self.0, ModifiedChecker).unwrap();
+    let mut names: Vec<_> = fs::read_dir(&self.0).unwrap()
+      .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+      .collect();
+    names.sort();
+    names
+  }
+}
+
+#[test]
+fn precondition() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let dir = temp_dir.path().join("dir");
+  let task = ListExistingDirectory(dir.clone());
+  let require = Require::new(task.clone());
+
+  // Precondition fails: not executed, and the failure is reported.
+  pie.assert_in_session(|session| {
+    let result = session.try_require(&require);
+    assert_matches!(result, Err(BuildViolation::PreconditionFailed { task: t, message }) => {
+      assert_eq!(t.as_ref(), &task as &dyn KeyObj);
+      assert!(message.contains("does not exist"));
+    });
+  }, |tracker| {
+    assert!(!tracker.any_execute_of(&task));
+  });
+
+  // Precondition holds: executed.
+  create_dir(&dir)?;
+  write(dir.join("file.txt"), "Hello")?;
+  let output = pie.require_then_assert(&require, |tracker| {
+    assert!(tracker.one_execute_of(&task));
+  });
+  assert_eq!(output, vec!["file.txt".to_string()]);
+
+  Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Precondition failed")]
+fn precondition_panics() {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir().unwrap();
+  pie.require(&ListExistingDirectory(temp_dir.path().join("dir")));
+}
+
+#[test]
+fn precondition_wrapped() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+  let task = Box::new(ListExistingDirectory(temp_dir.path().join("dir")));
+
+  // Precondition of the wrapped task fails: not executed, and the failure is reported.
+  pie.assert_in_session(|session| {
+    let result = session.try_require(&task);
+    assert_matches!(result, Err(BuildViolation::PreconditionFailed { task: t, .. }) => {
+      assert_eq!(t.as_ref(), &task as &dyn KeyObj);
+    });
+  }, |tracker| {
+    assert!(!tracker.any_execute_of(&task));
+  });
+
+  Ok(())
+}
+
+
+// Require with fallback tests.
+
+/// Task that panics with message `self.0` when executed.
+#[cfg(feature = "panic_fallback")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Panics(&'static str);
+#[cfg(feature = "panic_fallback")]
+impl Task for Panics {
+  type Output = String;
+  fn execute<C: Context>(&self, _context: &mut C) -> Self::Output {
+    panic!("{}", self.0)
+  }
+}
+
+/// Task that requires `self.0` with a fallback output of `"fallback"`.
+#[cfg(feature = "panic_fallback")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct RequireWithFallback<T>(T);
+#[cfg(feature = "panic_fallback")]
+impl<T: Task<Output=String> + std::panic::RefUnwindSafe> Task for RequireWithFallback<T> {
+  type Output = String;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.require_with_fallback(&self.0, EqualsChecker, "fallback".to_string())
+  }
+}
+
+/// Task that requires `Chain(self.0)` with a fallback output of `0`.
+#[cfg(feature = "panic_fallback")]
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct RequireChainWithFallback(u32);
+#[cfg(feature = "panic_fallback")]
+impl Task for RequireChainWithFallback {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    context.require_with_fallback(&Chain(self.0), EqualsChecker, 0)
+  }
+}
+
+/// Tracker that captures panicked tasks with their panic message.
+#[cfg(feature = "panic_fallback")]
+#[derive(Default)]
+struct PanicTracker(Vec<(Box<dyn KeyObj>, String)>);
+#[cfg(feature = "panic_fallback")]
+impl Tracker for PanicTracker {
+  fn execute_panicked(&mut self, task: &dyn TaskKeyObj, message: &str) {
+    self.0.push((task.as_key_obj().to_owned(), message.to_string()));
+  }
+}
+
+#[cfg(feature = "panic_fallback")]
+#[test]
+fn require_with_fallback() {
+  let mut pie = Pie::with_tracker(PanicTracker::default());
+  let panics = Require::new(Panics("boom"));
+  let task = RequireWithFallback(panics.clone());
+
+  // Panic is caught: fallback is returned, and the panic is tracked for the required task.
+  assert_eq!(pie.new_session().require(&task), "fallback");
+  assert_eq!(pie.tracker().0.len(), 1);
+  assert_eq!(pie.tracker().0[0].0.as_ref(), &panics as &dyn KeyObj);
+  assert_eq!(pie.tracker().0[0].1, "boom");
+
+  // Dependency was recorded as inconsistent: task is re-executed and the panic is caught again.
+  assert_eq!(pie.new_session().require(&task), "fallback");
+  assert_eq!(pie.tracker().0.len(), 2);
+
+  // Non-panicking dependency: its output is returned.
+  assert_eq!(pie.new_session().require(&RequireWithFallback(Constant::new("Hello"))), "Hello");
+  assert_eq!(pie.tracker().0.len(), 2);
+}
+
+#[cfg(feature = "panic_fallback")]
+#[test]
+fn require_with_fallback_propagates_max_depth_exceeded() {
+  let mut pie = Pie::with_tracker(PanicTracker::default());
+  let result = pie.new_session().with_max_depth(5).try_require(&RequireChainWithFallback(10));
+  assert_matches!(result, Err(BuildViolation::DepthExceeded { max_depth: 5, .. }));
+  assert!(pie.tracker().0.is_empty());
+}
+
+
+// Fixpoint tests.
+
+/// Mutually recursive tasks: `A` outputs `min(B + 1, limit)` where `limit` is read from file `self.0`, and `B` outputs
+/// `A + 1`. `Top` requires `A` as a fixpoint starting from `0`, iterating at most `max_iters` times.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum Fixpoint {
+  Top(PathBuf, usize),
+  A(PathBuf),
+  B(PathBuf),
+}
+impl Task for Fixpoint {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    match self {
+      Self::Top(path, max_iters) => context.require_fixpoint(&Self::A(path.clone()), EqualsChecker, 0, *max_iters),
+      Self::A(path) => {
+        let mut string = String::new();
+        context.read(path, ModifiedChecker).unwrap().as_file().unwrap().read_to_string(&mut string).unwrap();
+        let limit: u32 = string.parse().unwrap();
+        (context.require(&Self::B(path.clone()), EqualsChecker) + 1).min(limit)
+      }
+      Self::B(path) => context.require(&Self::A(path.clone()), EqualsChecker) + 1,
+    }
+  }
+}
+
+#[test]
+fn require_fixpoint() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("limit.txt");
+  write(&file, "5")?;
+  let a = Fixpoint::A(file.clone());
+  let execute_count = |tracker: &EventTracker, task: &Fixpoint| {
+    tracker.iter().filter(|e| e.match_execute_start(task).is_some()).count()
+  };
+
+  // Iterates from 0: A is 2, 4, 5, and then 5 again, at which point it has stabilized.
+  let task = Fixpoint::Top(file.clone(), 10);
+  let output = pie.require_then_assert(&task, |tracker| assert_eq!(execute_count(tracker, &a), 4));
+  assert_eq!(output, 5);
+
+  // Consistent: nothing is executed.
+  pie.require_then_assert_no_execute(&task);
+
+  // Limit changed: A is executed with the stale output of B, then iterated from 0 again: A is 2, 3, and then 3 again,
+  // at which point it has stabilized.
+  write_until_modified(&file, "3")?;
+  let output = pie.require_then_assert(&task, |tracker| assert_eq!(execute_count(tracker, &a), 4));
+  assert_eq!(output, 3);
+  pie.require_then_assert_no_execute(&task);
+
+  // Maximum number of iterations reached: returns the last output, which has not stabilized yet.
+  let task = Fixpoint::Top(file.clone(), 2);
+  pie = new_test_pie();
+  write_until_modified(&file, "5")?;
+  let output = pie.require_then_assert(&task, |tracker| assert_eq!(execute_count(tracker, &a), 2));
+  assert_eq!(output, 4);
+
+  Ok(())
+}
+
+/// Tasks that only form a cycle when a flag is set: `A` outputs `B` if the flag read from file `self.0` is set, or `7`
+/// otherwise, and `B` outputs `A`. `Top` requires `A` as a fixpoint starting from `0`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum FlagFixpoint {
+  Top(PathBuf),
+  A(PathBuf),
+  B(PathBuf),
+}
+impl Task for FlagFixpoint {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    match self {
+      Self::Top(path) => context.require_fixpoint(&Self::A(path.clone()), EqualsChecker, 0, 10),
+      Self::A(path) => {
+        let mut string = String::new();
+        context.read(path, ModifiedChecker).unwrap().as_file().unwrap().read_to_string(&mut string).unwrap();
+        if string == "true" { context.require(&Self::B(path.clone()), EqualsChecker) } else { 7 }
+      }
+      Self::B(path) => context.require(&Self::A(path.clone()), EqualsChecker),
+    }
+  }
+}
+
+#[test]
+fn require_fixpoint_iterates_from_initial() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("flag.txt");
+  let task = FlagFixpoint::Top(file.clone());
+
+  // No cycle: A is 7.
+  write(&file, "false")?;
+  assert_eq!(pie.require(&task), 7);
+
+  // Cycle: iterates from `initial`, not from the output of the previous build, so A is 0 just like in a clean build.
+  write_until_modified(&file, "true")?;
+  assert_eq!(pie.require(&task), 0);
+  assert_eq!(new_test_pie().require(&task), 0);
+
+  // No cycle again, then a cycle again.
+  write_until_modified(&file, "false")?;
+  assert_eq!(pie.require(&task), 7);
+  write_until_modified(&file, "true")?;
+  assert_eq!(pie.require(&task), 0);
+
+  Ok(())
+}
+
+/// Mutually recursive tasks: `A` outputs `max(B, limit)` where `limit` is read from file `self.0`, and `B` outputs `A`.
+/// `Top` requires `A` as a fixpoint starting from `0`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+enum MaxFixpoint {
+  Top(PathBuf),
+  A(PathBuf),
+  B(PathBuf),
+}
+impl Task for MaxFixpoint {
+  type Output = u32;
+  fn execute<C: Context>(&self, context: &mut C) -> Self::Output {
+    match self {
+      Self::Top(path) => context.require_fixpoint(&Self::A(path.clone()), EqualsChecker, 0, 10),
+      Self::A(path) => {
+        let mut string = String::new();
+        context.read(path, ModifiedChecker).unwrap().as_file().unwrap().read_to_string(&mut string).unwrap();
+        let limit: u32 = string.parse().unwrap();
+        context.require(&Self::B(path.clone()), EqualsChecker).max(limit)
+      }
+      Self::B(path) => context.require(&Self::A(path.clone()), EqualsChecker),
+    }
+  }
+}
+
+#[test]
+fn require_fixpoint_restarts_from_initial() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("limit.txt");
+  let task = MaxFixpoint::Top(file.clone());
+  write(&file, "5")?;
+  assert_eq!(pie.require(&task), 5);
+
+  // Limit changed: A is executed with the stale output of B, which is discarded by iterating from `initial` again, so A
+  // is 3 just like in a clean build, instead of the stale 5.
+  write_until_modified(&file, "3")?;
+  assert_eq!(pie.require(&task), 3);
+  assert_eq!(new_test_pie().require(&task), 3);
+
+  Ok(())
+}
+
+#[test]
+fn require_fixpoint_abandoned_by_violation() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let file = temp_dir.path().join("limit.txt");
+  let task = MaxFixpoint::Top(file.clone());
+  write(&file, "5")?;
+
+  // A violation interrupts the fixpoint iteration, which is abandoned: requiring B directly in the same session does
+  // not get a provisional output of A, but closes a cycle.
+  let mut session = pie.new_session().with_max_depth(2);
+  assert_matches!(session.try_require(&task), Err(BuildViolation::DepthExceeded { max_depth: 2, .. }));
+  let b = MaxFixpoint::B(file.clone());
+  assert_matches!(session.try_require(&b), Err(BuildViolation::CyclicDependency { .. }));
+
+  Ok(())
+}
@@ -78,6 +78,7 @@ impl TestPieExt for TestPie {
     let output = run_func(&mut session);
     let dependency_check_errors: Vec<_> = session.dependency_check_errors().collect();
     assert!(dependency_check_errors.is_empty(), "expected no dependency checking errors, but there are some: {:?}", dependency_check_errors);
+    drop(session);
     test_func(&self.tracker().0);
     output
   }
@@ -0,0 +1,41 @@
+use std::fs::write;
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::Duration;
+
+use testresult::TestResult;
+
+use dev_ext::task::*;
+use dev_util::{create_temp_dir, write_until_modified};
+
+use crate::util::{new_test_pie, TestPieExt};
+
+mod util;
+
+#[test]
+fn test_watch_executes_affected_task() -> TestResult {
+  let mut pie = new_test_pie();
+  let temp_dir = create_temp_dir()?;
+
+  let path = temp_dir.path().join("test.txt");
+  write(&path, "HELLO WORLD!")?;
+  let task = ReadFile::new(&path);
+  assert_eq!(pie.require(&task)?.as_str(), "HELLO WORLD!");
+
+  // Change the watched file after the watcher has started.
+  let writer = {
+    let path = path.clone();
+    thread::spawn(move || {
+      thread::sleep(Duration::from_millis(500));
+      write_until_modified(&path, "hello world!")
+    })
+  };
+  pie.watch(&[temp_dir.path().to_path_buf()], |_| ControlFlow::Break(()))?;
+  writer.join().expect("writer thread panicked")?;
+
+  // The watcher detected the change and executed the affected task in a bottom-up build.
+  assert!(pie.tracker().0.one_execute_of(&task));
+  assert_eq!(pie.require_then_assert_no_execute(&task)?.as_str(), "hello world!");
+
+  Ok(())
+}